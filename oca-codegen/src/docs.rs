@@ -0,0 +1,556 @@
+//! Human readable documentation of OCA Bundles.
+//!
+//! The document describes the bundle and every bundle it references, each
+//! in its own section: Meta name and description, SAIDs, a table of
+//! attributes, conditional rules and the list of overlays. References link
+//! to the sections of referenced bundles. It can be rendered as Markdown or
+//! as a standalone HTML page.
+use crate::Error;
+use isolang::Language;
+use oca_ast_semantics::ast::{NestedAttrType, RefValue};
+use oca_bundle_semantics::state::{
+    attribute::Attribute,
+    dependencies::{self, said_of, Dependencies},
+    entries::EntriesElement,
+    entry_codes::EntryCodes,
+    oca::{OCABox, OCABundle},
+};
+use std::collections::HashMap;
+
+const HEADER: &[&str] = &[
+    "Attribute",
+    "Type",
+    "Label",
+    "Information",
+    "Conformance",
+    "Unit",
+    "Format",
+    "Entry codes",
+];
+
+const STYLE: &str =
+    "body { font-family: sans-serif; max-width: 70em; margin: 2em auto; padding: 0 1em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.5em; text-align: left; vertical-align: top; }
+code { word-break: break-all; }";
+
+/// Render Markdown documentation of the bundle in `language`.
+pub fn to_markdown(
+    bundle: &OCABundle,
+    dependencies: &[OCABundle],
+    language: Language,
+) -> Result<String, Error> {
+    let document = Document::new(bundle, dependencies, language)?;
+    let mut markdown = String::new();
+    for (i, section) in document.sections.iter().enumerate() {
+        let level = if i == 0 { "#" } else { "##" };
+        markdown.push_str(&format!(
+            "<a id=\"{}\"></a>\n\n{level} {}\n\n",
+            section.said,
+            md_text(&document.title(&section.said))
+        ));
+        if let Some(description) = &section.description {
+            markdown.push_str(&format!("{}\n\n", description.trim()));
+        }
+        markdown.push_str(&format!("- SAID: `{}`\n", section.said));
+        markdown.push_str(&format!("- Capture base: `{}`\n", section.capture_base));
+        if let Some(classification) = &section.classification {
+            markdown.push_str(&format!("- Classification: {}\n", md_text(classification)));
+        }
+
+        markdown.push_str(&format!("\n{level}# Attributes\n\n"));
+        markdown.push_str(&format!("| {} |\n", HEADER.join(" | ")));
+        markdown.push_str(&format!("|{}\n", "---|".repeat(HEADER.len())));
+        for row in &section.rows {
+            let entries: Vec<String> = row
+                .entries
+                .iter()
+                .map(|(code, label)| match label {
+                    Some(label) => format!("`{code}` {}", md_cell(label)),
+                    None => format!("`{code}`"),
+                })
+                .collect();
+            let cells = [
+                format!(
+                    "`{}`{}",
+                    row.name,
+                    if row.flagged { " (flagged)" } else { "" }
+                ),
+                document.type_text(&row.attr_type, &|said, title| {
+                    format!("[{}](#{said})", md_text(title))
+                }),
+                row.label.as_deref().map(md_cell).unwrap_or_default(),
+                row.information.as_deref().map(md_cell).unwrap_or_default(),
+                conformance(row.conformance.as_deref()).to_string(),
+                row.unit.as_deref().map(md_cell).unwrap_or_default(),
+                row.format
+                    .as_deref()
+                    .map(|format| format!("`{}`", format.replace('|', "\\|")))
+                    .unwrap_or_default(),
+                entries.join("<br>"),
+            ];
+            markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+
+        let conditions = section.conditions();
+        if !conditions.is_empty() {
+            markdown.push_str(&format!("\n{level}# Conditions\n\n"));
+            for (name, condition) in conditions {
+                markdown.push_str(&format!("- `{name}` applies when `{condition}`\n"));
+            }
+        }
+
+        markdown.push_str(&format!("\n{level}# Overlays\n\n"));
+        markdown.push_str("| Overlay | Language | SAID |\n|---|---|---|\n");
+        for overlay in &section.overlays {
+            markdown.push_str(&format!(
+                "| {} | {} | `{}` |\n",
+                overlay.overlay_type,
+                overlay.language.as_deref().unwrap_or_default(),
+                overlay.said
+            ));
+        }
+        markdown.push('\n');
+    }
+
+    Ok(markdown.trim_end().to_string() + "\n")
+}
+
+/// Render standalone HTML page documenting the bundle in `language`.
+pub fn to_html(
+    bundle: &OCABundle,
+    dependencies: &[OCABundle],
+    language: Language,
+) -> Result<String, Error> {
+    let document = Document::new(bundle, dependencies, language)?;
+    let lang = language.to_639_1().unwrap_or_else(|| language.to_639_3());
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"{lang}\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n",
+        escape(&document.title(&document.sections[0].said))
+    );
+    for (i, section) in document.sections.iter().enumerate() {
+        let level = if i == 0 { 1 } else { 2 };
+        html.push_str(&format!(
+            "<section id=\"{}\">\n<h{level}>{}</h{level}>\n",
+            section.said,
+            escape(&document.title(&section.said))
+        ));
+        if let Some(description) = &section.description {
+            html.push_str(&format!("<p>{}</p>\n", escape(description.trim())));
+        }
+        html.push_str("<ul>\n");
+        html.push_str(&format!("<li>SAID: <code>{}</code></li>\n", section.said));
+        html.push_str(&format!(
+            "<li>Capture base: <code>{}</code></li>\n",
+            section.capture_base
+        ));
+        if let Some(classification) = &section.classification {
+            html.push_str(&format!(
+                "<li>Classification: {}</li>\n",
+                escape(classification)
+            ));
+        }
+        html.push_str("</ul>\n");
+
+        let sub = level + 1;
+        html.push_str(&format!("<h{sub}>Attributes</h{sub}>\n<table>\n<tr>"));
+        for column in HEADER {
+            html.push_str(&format!("<th>{column}</th>"));
+        }
+        html.push_str("</tr>\n");
+        for row in &section.rows {
+            let entries: Vec<String> = row
+                .entries
+                .iter()
+                .map(|(code, label)| match label {
+                    Some(label) => format!("<code>{}</code> {}", escape(code), escape(label)),
+                    None => format!("<code>{}</code>", escape(code)),
+                })
+                .collect();
+            let cells = [
+                format!(
+                    "<code>{}</code>{}",
+                    escape(&row.name),
+                    if row.flagged { " (flagged)" } else { "" }
+                ),
+                document.type_text(&row.attr_type, &|said, title| {
+                    format!("<a href=\"#{said}\">{}</a>", escape(title))
+                }),
+                row.label.as_deref().map(escape).unwrap_or_default(),
+                row.information.as_deref().map(escape).unwrap_or_default(),
+                conformance(row.conformance.as_deref()).to_string(),
+                row.unit.as_deref().map(escape).unwrap_or_default(),
+                row.format
+                    .as_deref()
+                    .map(|format| format!("<code>{}</code>", escape(format)))
+                    .unwrap_or_default(),
+                entries.join("<br>"),
+            ];
+            html.push_str("<tr>");
+            for cell in cells {
+                html.push_str(&format!("<td>{cell}</td>"));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+
+        let conditions = section.conditions();
+        if !conditions.is_empty() {
+            html.push_str(&format!("<h{sub}>Conditions</h{sub}>\n<ul>\n"));
+            for (name, condition) in conditions {
+                html.push_str(&format!(
+                    "<li><code>{}</code> applies when <code>{}</code></li>\n",
+                    escape(name),
+                    escape(&condition)
+                ));
+            }
+            html.push_str("</ul>\n");
+        }
+
+        html.push_str(&format!(
+            "<h{sub}>Overlays</h{sub}>\n<table>\n<tr><th>Overlay</th><th>Language</th><th>SAID</th></tr>\n"
+        ));
+        for overlay in &section.overlays {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td><code>{}</code></td></tr>\n",
+                overlay.overlay_type,
+                overlay.language.as_deref().unwrap_or_default(),
+                overlay.said
+            ));
+        }
+        html.push_str("</table>\n</section>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+
+    Ok(html)
+}
+
+struct Document {
+    /// The bundle first, followed by referenced bundles in order of use.
+    sections: Vec<Section>,
+    /// Meta names of bundles by SAID.
+    titles: HashMap<String, String>,
+}
+
+struct Section {
+    said: String,
+    capture_base: String,
+    description: Option<String>,
+    classification: Option<String>,
+    rows: Vec<Row>,
+    overlays: Vec<OverlayRow>,
+}
+
+struct Row {
+    name: String,
+    attr_type: NestedAttrType,
+    flagged: bool,
+    label: Option<String>,
+    information: Option<String>,
+    conformance: Option<String>,
+    unit: Option<String>,
+    format: Option<String>,
+    /// Entry codes with their labels.
+    entries: Vec<(String, Option<String>)>,
+    condition: Option<String>,
+}
+
+struct OverlayRow {
+    overlay_type: String,
+    language: Option<String>,
+    said: String,
+}
+
+impl Document {
+    fn new(
+        bundle: &OCABundle,
+        dependencies: &[OCABundle],
+        language: Language,
+    ) -> Result<Self, Error> {
+        let dependencies = Dependencies::new(dependencies);
+        let mut document = Document {
+            sections: vec![],
+            titles: HashMap::new(),
+        };
+        let mut pending = vec![bundle];
+        while let Some(bundle) = pending.pop() {
+            let said = said_of(bundle)?;
+            if document.sections.iter().any(|section| section.said == said) {
+                continue;
+            }
+            let section = document.section(bundle, language)?;
+            // Referenced bundles in order of use.
+            let mut references = vec![];
+            for row in &section.rows {
+                references.extend(references_of(&row.attr_type)?);
+            }
+            for said in references.into_iter().rev() {
+                let dependency = dependencies
+                    .get(&said)
+                    .ok_or(dependencies::Error::MissingDependency { said })?;
+                pending.push(dependency);
+            }
+            document.sections.push(section);
+        }
+
+        Ok(document)
+    }
+
+    fn section(&mut self, bundle: &OCABundle, language: Language) -> Result<Section, Error> {
+        let said = said_of(bundle)?;
+        let oca_box = OCABox::from(bundle.clone());
+        let meta = oca_box.meta.as_ref().and_then(|meta| meta.get(&language));
+        if let Some(name) = meta.and_then(|meta| meta.get("name")) {
+            self.titles.insert(said.clone(), name.clone());
+        }
+
+        let mut attributes: Vec<&Attribute> = oca_box.attributes.values().collect();
+        attributes.sort_by(|a, b| a.name.cmp(&b.name));
+        let rows = attributes
+            .into_iter()
+            .filter_map(|attribute| {
+                let attr_type = attribute.attribute_type.clone()?;
+                let localized = |values: &Option<HashMap<Language, String>>| {
+                    values.as_ref()?.get(&language).cloned()
+                };
+                Some(Row {
+                    name: attribute.name.clone(),
+                    attr_type,
+                    flagged: attribute.is_flagged,
+                    label: localized(&attribute.labels),
+                    information: localized(&attribute.informations),
+                    conformance: attribute.conformance.clone(),
+                    unit: attribute.unit.clone(),
+                    format: attribute.format.clone(),
+                    entries: entries(attribute, language),
+                    condition: attribute.named_condition(),
+                })
+            })
+            .collect();
+        let overlays = bundle
+            .overlays
+            .iter()
+            .map(|overlay| OverlayRow {
+                overlay_type: overlay.overlay_type().to_string(),
+                language: overlay.language().map(|lang| {
+                    lang.to_639_1()
+                        .unwrap_or_else(|| lang.to_639_3())
+                        .to_string()
+                }),
+                said: overlay
+                    .said()
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        Ok(Section {
+            said,
+            capture_base: bundle
+                .capture_base
+                .said
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            description: meta.and_then(|meta| meta.get("description")).cloned(),
+            classification: oca_box.classification.filter(|c| !c.is_empty()),
+            rows,
+            overlays,
+        })
+    }
+
+    /// Meta name of the bundle, or its SAID when it has none.
+    fn title(&self, said: &str) -> String {
+        self.titles
+            .get(said)
+            .cloned()
+            .unwrap_or_else(|| said.to_string())
+    }
+
+    /// Type in OCAFILE notation, with references rendered by `link`.
+    fn type_text(&self, attr_type: &NestedAttrType, link: &dyn Fn(&str, &str) -> String) -> String {
+        match attr_type {
+            NestedAttrType::Value(value_type) => value_type.to_string(),
+            NestedAttrType::Array(item_type) => {
+                format!("Array[{}]", self.type_text(item_type, link))
+            }
+            NestedAttrType::Reference(RefValue::Said(said)) => {
+                let said = said.to_string();
+                link(&said, &self.title(&said))
+            }
+            NestedAttrType::Reference(reference) => reference.to_string(),
+            NestedAttrType::Null => "Null".to_string(),
+        }
+    }
+}
+
+impl Section {
+    /// Conditions with dependencies written as plain attribute names.
+    fn conditions(&self) -> Vec<(&str, String)> {
+        self.rows
+            .iter()
+            .filter_map(|row| {
+                let condition = row.condition.as_ref()?;
+                let mut readable = condition.clone();
+                let mut start = 0;
+                while let Some(open) = readable[start..].find("${") {
+                    let open = start + open;
+                    let Some(close) = readable[open..].find('}') else {
+                        break;
+                    };
+                    let name = readable[open + 2..open + close].to_string();
+                    readable.replace_range(open..=open + close, &name);
+                    start = open + name.len();
+                }
+                Some((row.name.as_str(), readable))
+            })
+            .collect()
+    }
+}
+
+fn references_of(attr_type: &NestedAttrType) -> Result<Vec<String>, Error> {
+    match attr_type {
+        NestedAttrType::Reference(RefValue::Said(said)) => Ok(vec![said.to_string()]),
+        NestedAttrType::Reference(RefValue::Name(name)) => {
+            Err(dependencies::Error::UnresolvedReference { name: name.clone() }.into())
+        }
+        NestedAttrType::Array(item_type) => references_of(item_type),
+        NestedAttrType::Value(_) | NestedAttrType::Null => Ok(vec![]),
+    }
+}
+
+fn entries(attribute: &Attribute, language: Language) -> Vec<(String, Option<String>)> {
+    let codes: Vec<String> = match &attribute.entry_codes {
+        Some(EntryCodes::Array(codes)) => codes.clone(),
+        Some(EntryCodes::Object(groups)) => groups.values().flatten().cloned().collect(),
+        Some(EntryCodes::Sai(_)) | None => return vec![],
+    };
+    let labels = match attribute.entries.as_ref().and_then(|e| e.get(&language)) {
+        Some(EntriesElement::Object(labels)) => Some(labels),
+        _ => None,
+    };
+    codes
+        .into_iter()
+        .map(|code| {
+            let label = labels.and_then(|labels| labels.get(&code)).cloned();
+            (code, label)
+        })
+        .collect()
+}
+
+fn conformance(conformance: Option<&str>) -> &str {
+    match conformance {
+        Some("M") => "Mandatory",
+        Some("O") => "Optional",
+        Some(other) => other,
+        None => "",
+    }
+}
+
+/// Escape Markdown inline syntax.
+fn md_text(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if "\\`*_[]<>|#".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Table cell text, new lines become line breaks.
+fn md_cell(text: &str) -> String {
+    text.trim()
+        .lines()
+        .map(md_text)
+        .collect::<Vec<_>>()
+        .join("<br>")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, build};
+
+    fn bundles() -> (OCABundle, OCABundle) {
+        let address = address();
+        let person = build(&format!(
+            r#"ADD ATTRIBUTE name=Text age=Numeric guardian=Text sex=Text home=refs:{}
+ADD META en PROPS name="Person" description="Registered person"
+ADD LABEL en ATTRS name="Full name" sex="Sex"
+ADD INFORMATION en ATTRS name="As in | passport"
+ADD CONFORMANCE ATTRS name="M" age="O"
+ADD UNIT ATTRS age="year"
+ADD ENTRY_CODE ATTRS sex=["m", "f"]
+ADD ENTRY en ATTRS sex={{"m": "Male", "f": "Female"}}
+ADD CONDITION ATTRS guardian="${{age}} < 18"
+"#,
+            address.said.as_ref().unwrap()
+        ));
+        (person, address)
+    }
+
+    #[test]
+    fn render_markdown() {
+        let (person, address) = bundles();
+        let markdown = to_markdown(&person, std::slice::from_ref(&address), Language::Eng).unwrap();
+        let person_said = person.said.as_ref().unwrap();
+        let address_said = address.said.as_ref().unwrap();
+        let lines: Vec<&str> = markdown.lines().collect();
+
+        assert_eq!(lines[0], format!("<a id=\"{person_said}\"></a>"));
+        assert_eq!(lines[2], "# Person");
+        assert_eq!(lines[4], "Registered person");
+        assert_eq!(lines[6], format!("- SAID: `{person_said}`"));
+        assert!(lines.contains(&"| `age` | Numeric |  |  | Optional | year |  |  |"));
+        assert!(lines.contains(
+            &format!("| `home` | [Address](#{address_said}) |  |  |  |  |  |  |").as_str()
+        ));
+        assert!(lines
+            .contains(&"| `name` | Text | Full name | As in \\| passport | Mandatory |  |  |  |"));
+        assert!(lines.contains(&"| `sex` | Text | Sex |  |  |  |  | `m` Male<br>`f` Female |"));
+        assert!(lines.contains(&"- `guardian` applies when `age < 18`"));
+        assert!(lines.contains(&"## Address"));
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("| Label | en | `")));
+    }
+
+    #[test]
+    fn render_html() {
+        let (person, address) = bundles();
+        let html = to_html(&person, std::slice::from_ref(&address), Language::Eng).unwrap();
+        let address_said = address.said.as_ref().unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>\n<html lang=\"en\">"));
+        assert!(html.contains("<title>Person</title>"));
+        assert!(html.contains(&format!(
+            "<section id=\"{address_said}\">\n<h2>Address</h2>"
+        )));
+        assert!(html.contains(&format!("<td><a href=\"#{address_said}\">Address</a></td>")));
+        assert!(html.contains("<td>As in | passport</td>"));
+        assert!(
+            html.contains("<li><code>guardian</code> applies when <code>age &lt; 18</code></li>")
+        );
+    }
+
+    #[test]
+    fn missing_dependency() {
+        let (person, _) = bundles();
+        assert!(matches!(
+            to_markdown(&person, &[], Language::Eng),
+            Err(Error::Dependency(
+                dependencies::Error::MissingDependency { .. }
+            ))
+        ));
+    }
+}
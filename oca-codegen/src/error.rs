@@ -0,0 +1,16 @@
+use oca_bundle_semantics::state::dependencies::Error as DependencyError;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum Error {
+    #[error("Failed to read {path}: {message}")]
+    Io { path: String, message: String },
+    #[error("Failed to load bundle: {message}")]
+    Load { message: String },
+    #[error("Can't generate value of `{attribute}`: {message}")]
+    Generation { attribute: String, message: String },
+    #[serde(untagged)]
+    #[error(transparent)]
+    Dependency(#[from] DependencyError),
+}
@@ -0,0 +1,18 @@
+//! Bundles shared by tests.
+use oca_bundle_semantics::{build::from_ast, state::oca::OCABundle};
+use oca_file_semantics::ocafile::parse_from_string;
+
+pub(crate) fn build(ocafile: &str) -> OCABundle {
+    let ast = parse_from_string(ocafile.to_string()).unwrap();
+    from_ast(None, &ast).unwrap().oca_bundle
+}
+
+/// Named bundle with a single `street` attribute, referenced by the person
+/// bundles of the tests.
+pub(crate) fn address() -> OCABundle {
+    build(
+        r#"ADD ATTRIBUTE street=Text
+ADD META en PROPS name="Address"
+"#,
+    )
+}
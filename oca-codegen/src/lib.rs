@@ -0,0 +1,63 @@
+pub mod docs;
+mod error;
+#[cfg(test)]
+mod fixtures;
+pub mod records;
+pub mod rust;
+mod types;
+pub mod typescript;
+pub mod wasm;
+
+pub use error::Error;
+use oca_bundle_semantics::state::oca::OCABundle;
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Deserialize)]
+struct BundleWithDependencies {
+    bundle: OCABundle,
+    #[serde(default)]
+    dependencies: Vec<OCABundle>,
+}
+
+/// Load bundle with its dependencies from a file.
+///
+/// Files with `.ocafile` extension are built from OCAFILE, they can't use
+/// `refn:` references. Other files are read as JSON of a bundle or of
+/// a bundle with dependencies, as returned by `Facade::get_oca_bundle`.
+pub fn load(path: impl AsRef<Path>) -> Result<(OCABundle, Vec<OCABundle>), Error> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path).map_err(|e| Error::Io {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })?;
+
+    if path.extension().is_some_and(|ext| ext == "ocafile") {
+        let ast =
+            oca_file_semantics::ocafile::parse_from_string(content).map_err(|e| Error::Load {
+                message: e.to_string(),
+            })?;
+        let oca_build =
+            oca_bundle_semantics::build::from_ast(None, &ast).map_err(|errors| Error::Load {
+                message: errors
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            })?;
+        return Ok((oca_build.oca_bundle, vec![]));
+    }
+
+    let value: serde_json::Value = serde_json::from_str(&content).map_err(|e| Error::Load {
+        message: e.to_string(),
+    })?;
+    let loaded = if value.get("bundle").is_some() {
+        serde_json::from_value::<BundleWithDependencies>(value)
+            .map(|loaded| (loaded.bundle, loaded.dependencies))
+    } else {
+        serde_json::from_value::<OCABundle>(value).map(|bundle| (bundle, vec![]))
+    };
+    loaded.map_err(|e| Error::Load {
+        message: e.to_string(),
+    })
+}
@@ -0,0 +1,406 @@
+//! Generation of random records conforming to OCA Bundles.
+//!
+//! Values respect attribute types, entry codes, formats (Text formats are
+//! regular expressions used to generate the values), cardinality of arrays
+//! and conditions. References are filled with records of the referenced
+//! bundles. Generation is driven by a seeded RNG, so the same seed gives
+//! the same records.
+use crate::Error;
+use oca_ast_semantics::ast::{AttributeType, NestedAttrType};
+use oca_bundle_semantics::state::{
+    attribute::Attribute,
+    dependencies::{said_of, Dependencies},
+    entry_codes::EntryCodes,
+    oca::{OCABox, OCABundle},
+};
+use piccolo::{Closure, Lua, Thread};
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Upper bound of repetitions in generated Text values.
+const MAX_REPEAT: u32 = 8;
+
+/// Date time tokens of OCA formats.
+const DATE_TIME_TOKENS: &[&str] = &["YYYY", "MM", "DD", "hh", "HH", "mm", "ss"];
+
+pub struct RecordGenerator<'a> {
+    bundle: &'a OCABundle,
+    dependencies: Dependencies<'a>,
+    rng: ChaCha8Rng,
+    max_items: usize,
+    optional_probability: f64,
+    /// Attributes of bundles by SAID, sorted by name.
+    attributes: HashMap<String, Vec<Attribute>>,
+}
+
+impl<'a> RecordGenerator<'a> {
+    pub fn new(bundle: &'a OCABundle, dependencies: &'a [OCABundle], seed: u64) -> Self {
+        Self {
+            bundle,
+            dependencies: Dependencies::new(dependencies),
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            max_items: 3,
+            optional_probability: 0.8,
+            attributes: HashMap::new(),
+        }
+    }
+
+    /// Maximal length of arrays without cardinality, 3 by default.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = max_items;
+        self
+    }
+
+    /// Probability that an optional attribute gets a value, 0.8 by default.
+    pub fn with_optional_probability(mut self, probability: f64) -> Self {
+        self.optional_probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Generate `count` records.
+    pub fn generate(&mut self, count: usize) -> Result<Vec<Value>, Error> {
+        (0..count).map(|_| self.record()).collect()
+    }
+
+    /// Generate single record.
+    pub fn record(&mut self) -> Result<Value, Error> {
+        self.bundle_record(self.bundle)
+    }
+
+    fn bundle_record(&mut self, bundle: &OCABundle) -> Result<Value, Error> {
+        let said = said_of(bundle)?;
+        let attributes = match self.attributes.get(&said) {
+            Some(attributes) => attributes.clone(),
+            None => {
+                let oca_box = OCABox::from(bundle.clone());
+                let mut attributes: Vec<Attribute> = oca_box.attributes.into_values().collect();
+                attributes.sort_by(|a, b| a.name.cmp(&b.name));
+                self.attributes.insert(said, attributes.clone());
+                attributes
+            }
+        };
+
+        // Conditional attributes are decided once their dependencies are.
+        let mut values: HashMap<String, Option<Value>> = HashMap::new();
+        let mut pending: Vec<&Attribute> = attributes.iter().collect();
+        while !pending.is_empty() {
+            let before = pending.len();
+            let mut waiting = vec![];
+            for attribute in pending {
+                let dependencies = attribute.dependencies.as_deref().unwrap_or_default();
+                if dependencies.iter().any(|dep| !values.contains_key(dep)) {
+                    waiting.push(attribute);
+                    continue;
+                }
+                let applies = match &attribute.condition {
+                    Some(condition) => evaluate(attribute, condition, &values)?,
+                    None => true,
+                };
+                let value = if applies && self.present(attribute) {
+                    match &attribute.attribute_type {
+                        Some(attr_type) => Some(self.value(attr_type, attribute)?),
+                        None => None,
+                    }
+                } else {
+                    None
+                };
+                values.insert(attribute.name.clone(), value);
+            }
+            if waiting.len() == before {
+                let attribute = waiting[0];
+                return Err(Error::Generation {
+                    attribute: attribute.name.clone(),
+                    message: "condition depends on unknown or cyclic attributes".to_string(),
+                });
+            }
+            pending = waiting;
+        }
+
+        let mut record = Map::new();
+        for attribute in &attributes {
+            if let Some(Some(value)) = values.remove(&attribute.name) {
+                record.insert(attribute.name.clone(), value);
+            }
+        }
+
+        Ok(Value::Object(record))
+    }
+
+    fn present(&mut self, attribute: &Attribute) -> bool {
+        attribute.conformance.as_deref() == Some("M")
+            || self.rng.gen_bool(self.optional_probability)
+    }
+
+    fn value(&mut self, attr_type: &NestedAttrType, attribute: &Attribute) -> Result<Value, Error> {
+        let value = match attr_type {
+            NestedAttrType::Value(value_type) => match entry_codes(attribute) {
+                Some(codes) if !codes.is_empty() => {
+                    let code = codes.choose(&mut self.rng).unwrap();
+                    match value_type {
+                        AttributeType::Numeric => code
+                            .parse::<f64>()
+                            .map(|number| json!(number))
+                            .unwrap_or_else(|_| json!(code)),
+                        AttributeType::Boolean => code
+                            .parse::<bool>()
+                            .map(|boolean| json!(boolean))
+                            .unwrap_or_else(|_| json!(code)),
+                        _ => json!(code),
+                    }
+                }
+                _ => self.scalar(value_type, attribute)?,
+            },
+            NestedAttrType::Array(item_type) => {
+                let (min, max) = self.items_range(attribute);
+                let count = self.rng.gen_range(min..=max);
+                let items = (0..count)
+                    .map(|_| self.value(item_type, attribute))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Value::Array(items)
+            }
+            NestedAttrType::Reference(reference) => {
+                let dependency = self.dependencies.resolve(reference)?;
+                self.bundle_record(dependency)?
+            }
+            NestedAttrType::Null => Value::Null,
+        };
+
+        Ok(value)
+    }
+
+    fn scalar(
+        &mut self,
+        value_type: &AttributeType,
+        attribute: &Attribute,
+    ) -> Result<Value, Error> {
+        let format = attribute.format.as_deref();
+        let value = match value_type {
+            AttributeType::Text => match format {
+                Some(pattern) => {
+                    let regex = rand_regex::Regex::compile(strip_anchors(pattern), MAX_REPEAT)
+                        .map_err(|e| Error::Generation {
+                            attribute: attribute.name.clone(),
+                            message: format!("format `{pattern}` can't be used: {e}"),
+                        })?;
+                    json!(self.rng.sample::<String, _>(&regex))
+                }
+                None => {
+                    let length = self.rng.gen_range(4..=12);
+                    let text: String = (0..length)
+                        .map(|_| self.rng.gen_range(b'a'..=b'z') as char)
+                        .collect();
+                    json!(text)
+                }
+            },
+            AttributeType::Numeric => json!(self.rng.gen_range(0..1000)),
+            AttributeType::Boolean => json!(self.rng.gen_bool(0.5)),
+            AttributeType::DateTime => {
+                let format = format.unwrap_or("YYYY-MM-DDThh:mm:ssZ");
+                let hour = self.rng.gen_range(0..24);
+                let parts = [
+                    self.rng.gen_range(1970..=2030),
+                    self.rng.gen_range(1..=12),
+                    // Valid in every month.
+                    self.rng.gen_range(1..=28),
+                    hour,
+                    hour,
+                    self.rng.gen_range(0..60),
+                    self.rng.gen_range(0..60),
+                ];
+                let formatted = DATE_TIME_TOKENS.iter().zip(parts).fold(
+                    format.to_string(),
+                    |formatted, (token, part)| {
+                        let width = token.len();
+                        formatted.replace(token, &format!("{part:0width$}"))
+                    },
+                );
+                json!(formatted)
+            }
+            AttributeType::Binary => {
+                let length = self.rng.gen_range(8..=32);
+                let bytes: Vec<u8> = (0..length).map(|_| self.rng.gen()).collect();
+                json!(base64::encode(bytes))
+            }
+        };
+
+        Ok(value)
+    }
+
+    /// Bounds of array length from cardinality (`n`, `min-max`, `min-` or
+    /// `-max`).
+    fn items_range(&self, attribute: &Attribute) -> (usize, usize) {
+        let default_min = usize::from(attribute.conformance.as_deref() == Some("M"));
+        let Some(cardinality) = attribute.cardinality.as_deref() else {
+            return (default_min.min(self.max_items), self.max_items);
+        };
+        let (min, max) = match cardinality.split_once('-') {
+            Some((min, max)) => (min.trim(), max.trim()),
+            None => (cardinality.trim(), cardinality.trim()),
+        };
+        let min = min.parse().unwrap_or(default_min);
+        let max = max.parse().unwrap_or(min.max(self.max_items));
+        (min.min(max), max)
+    }
+}
+
+fn entry_codes(attribute: &Attribute) -> Option<Vec<&String>> {
+    match attribute.entry_codes.as_ref()? {
+        EntryCodes::Sai(_) => None,
+        EntryCodes::Array(codes) => Some(codes.iter().collect()),
+        EntryCodes::Object(groups) => Some(groups.values().flatten().collect()),
+    }
+}
+
+/// Generation doesn't support anchors, patterns are anchored anyway.
+fn strip_anchors(pattern: &str) -> &str {
+    let pattern = pattern.strip_prefix('^').unwrap_or(pattern);
+    match pattern.strip_suffix('$') {
+        Some(stripped) if !stripped.ends_with('\\') => stripped,
+        _ => pattern,
+    }
+}
+
+/// Evaluate condition with dependencies substituted by generated values.
+/// Condition doesn't apply when any of its dependencies has no value.
+fn evaluate(
+    attribute: &Attribute,
+    condition: &str,
+    values: &HashMap<String, Option<Value>>,
+) -> Result<bool, Error> {
+    let dependencies = attribute.dependencies.as_deref().unwrap_or_default();
+    if dependencies
+        .iter()
+        .any(|dependency| !matches!(values.get(dependency), Some(Some(_))))
+    {
+        return Ok(false);
+    }
+    let script = attribute
+        .condition_with(|dependency| lua_literal(values.get(dependency).and_then(Option::as_ref)))
+        .unwrap_or_else(|| condition.to_string());
+    let error = |message: String| Error::Generation {
+        attribute: attribute.name.clone(),
+        message: format!("condition `{condition}` can't be evaluated: {message}"),
+    };
+
+    let mut lua = Lua::new();
+    let thread = lua
+        .try_run(|ctx| {
+            let closure = Closure::load(ctx, format!("return {script}").as_bytes())?;
+            let thread = Thread::new(&ctx);
+            thread.start(ctx, closure.into(), ())?;
+            Ok(ctx.state.registry.stash(&ctx, thread))
+        })
+        .map_err(|e| error(e.to_string()))?;
+    lua.run_thread::<bool>(&thread)
+        .map_err(|e| error(e.to_string()))
+}
+
+fn lua_literal(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "nil".to_string(),
+        Some(Value::Bool(value)) => value.to_string(),
+        Some(Value::Number(value)) => value.to_string(),
+        Some(Value::String(value)) => {
+            format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+        }
+        Some(Value::Array(values)) => format!(
+            "{{{}}}",
+            values
+                .iter()
+                .map(|value| lua_literal(Some(value)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        // Nested records aren't accessible from conditions.
+        Some(Value::Object(_)) => "{}".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::build;
+
+    fn bundles() -> (OCABundle, OCABundle) {
+        let address = build(
+            r#"ADD ATTRIBUTE street=Text zip=Text
+ADD CONFORMANCE ATTRS street="M" zip="M"
+ADD FORMAT ATTRS zip="^[0-9]{2}-[0-9]{3}$"
+"#,
+        );
+        let person = build(&format!(
+            r#"ADD ATTRIBUTE age=Numeric guardian=Text sex=Text born=DateTime tags=Array[Text] home=refs:{} photo=Binary
+ADD CONFORMANCE ATTRS age="M" guardian="M" sex="M" born="M" tags="M" home="M" photo="O"
+ADD ENTRY_CODE ATTRS sex=["m", "f"]
+ADD FORMAT ATTRS born="DD.MM.YYYY"
+ADD CARDINALITY ATTRS tags="2-4"
+ADD CONDITION ATTRS guardian="${{age}} < 500"
+"#,
+            address.said.as_ref().unwrap()
+        ));
+        (person, address)
+    }
+
+    #[test]
+    fn generate_records() {
+        let (person, address) = bundles();
+        let dependencies = [address];
+        let records = RecordGenerator::new(&person, &dependencies, 7)
+            .generate(20)
+            .unwrap();
+
+        for record in &records {
+            let age = record["age"].as_f64().unwrap();
+            assert_eq!(record.get("guardian").is_some(), age < 500.0);
+            assert!(["m", "f"].contains(&record["sex"].as_str().unwrap()));
+            let born: Vec<&str> = record["born"].as_str().unwrap().split('.').collect();
+            assert_eq!(
+                born.iter().map(|part| part.len()).collect::<Vec<_>>(),
+                [2, 2, 4]
+            );
+            let tags = record["tags"].as_array().unwrap().len();
+            assert!((2..=4).contains(&tags));
+            let zip = record["home"]["zip"].as_str().unwrap();
+            assert_eq!(zip.len(), 6);
+            assert_eq!(zip.chars().nth(2), Some('-'));
+        }
+        assert!(records.iter().any(|record| record.get("photo").is_some()));
+        assert!(records.iter().any(|record| record.get("photo").is_none()));
+    }
+
+    #[test]
+    fn condition_on_optional_attribute() {
+        let person = build(
+            r#"ADD ATTRIBUTE age=Numeric guardian=Text
+ADD CONFORMANCE ATTRS age="O" guardian="M"
+ADD CONDITION ATTRS guardian="${age} < 18"
+"#,
+        );
+        let records = RecordGenerator::new(&person, &[], 0)
+            .with_optional_probability(0.5)
+            .generate(20)
+            .unwrap();
+
+        assert!(records.iter().any(|record| record.get("age").is_none()));
+        for record in &records {
+            let minor = record["age"].as_f64().is_some_and(|age| age < 18.0);
+            assert_eq!(record.get("guardian").is_some(), minor);
+        }
+    }
+
+    #[test]
+    fn seeded_generation_is_reproducible() {
+        let (person, address) = bundles();
+        let dependencies = [address];
+        let generate = |seed| {
+            RecordGenerator::new(&person, &dependencies, seed)
+                .generate(5)
+                .unwrap()
+        };
+
+        assert_eq!(generate(1), generate(1));
+        assert_ne!(generate(1), generate(2));
+    }
+}
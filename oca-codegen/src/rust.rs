@@ -0,0 +1,342 @@
+//! Generation of serde annotated Rust structs.
+//!
+//! Every bundle becomes a struct, attributes which aren't mandatory become
+//! `Option` fields and entry codes become enums. Labels and information in
+//! the chosen language are written as doc comments.
+//!
+//! Generated code can be written from `build.rs`:
+//!
+//! ```no_run
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! oca_codegen::rust::generate_file("person.json", format!("{out_dir}/person.rs"), None).unwrap();
+//! ```
+//!
+//! and included with `include!(concat!(env!("OUT_DIR"), "/person.rs"));`.
+use crate::{
+    types::{collect, Field, FieldType, TypeDef},
+    Error,
+};
+use isolang::Language;
+use oca_ast_semantics::ast::AttributeType;
+use oca_bundle_semantics::state::{naming::pascal_case, oca::OCABundle};
+use std::{collections::HashSet, path::Path};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "do", "dyn", "else", "enum",
+    "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop", "macro", "match",
+    "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static", "struct", "trait",
+    "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while",
+    "yield",
+];
+
+/// Keywords which can't be used as raw identifiers.
+const RESERVED: &[&str] = &["_", "crate", "self", "Self", "super"];
+
+/// Generate Rust source with types of the bundle and of the bundles it
+/// references.
+pub fn generate(
+    bundle: &OCABundle,
+    dependencies: &[OCABundle],
+    language: Option<Language>,
+) -> Result<String, Error> {
+    let types = collect(bundle, dependencies, language)?;
+    let mut source = format!(
+        "// Generated from OCA Bundle {}. Do not edit.\n",
+        types[0].said
+    );
+    let mut type_names: HashSet<String> =
+        types.iter().map(|type_def| type_def.name.clone()).collect();
+    for type_def in &types {
+        source.push('\n');
+        source.push_str(&structure(type_def, &mut type_names));
+    }
+
+    Ok(source)
+}
+
+/// Load bundle from `input` (see [`crate::load`]) and write generated
+/// source to `output`. Intended to be called from `build.rs`.
+pub fn generate_file(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    language: Option<Language>,
+) -> Result<(), Error> {
+    let (bundle, dependencies) = crate::load(&input)?;
+    let source = generate(&bundle, &dependencies, language)?;
+    let output = output.as_ref();
+    std::fs::write(output, source).map_err(|e| Error::Io {
+        path: output.display().to_string(),
+        message: e.to_string(),
+    })
+}
+
+/// Struct of the bundle followed by enums of its entry codes. Enum names are
+/// added to `type_names` to keep them unique.
+fn structure(type_def: &TypeDef, type_names: &mut HashSet<String>) -> String {
+    let mut source = String::new();
+    source.push_str(&doc_comment(
+        "",
+        type_def.title.as_deref(),
+        type_def.description.as_deref(),
+    ));
+    source.push_str(&format!("/// OCA Bundle `{}`.\n", type_def.said));
+    source.push_str("#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]\n");
+    source.push_str(&format!("pub struct {} {{\n", type_def.name));
+    let mut enums = vec![];
+    let mut idents: Vec<String> = vec![];
+    for field in &type_def.fields {
+        let mut rust_type = match &field.entry_codes {
+            Some(codes) => {
+                let mut enum_name = format!("{}{}", type_def.name, pascal_case(&field.name));
+                while !type_names.insert(enum_name.clone()) {
+                    enum_name.push('_');
+                }
+                enums.push(entry_codes_enum(&enum_name, field, codes));
+                wrap_arrays(&field.field_type, enum_name)
+            }
+            None => rust_type(&field.field_type),
+        };
+        if field.optional {
+            rust_type = format!("Option<{rust_type}>");
+        }
+
+        source.push_str(&doc_comment(
+            "    ",
+            field.label.as_deref(),
+            field.information.as_deref(),
+        ));
+        let mut ident = field_ident(&field.name);
+        while idents.contains(&ident) {
+            ident.push('_');
+        }
+        if ident.trim_start_matches("r#") != field.name {
+            source.push_str(&format!("    #[serde(rename = {:?})]\n", field.name));
+        }
+        if field.optional {
+            source.push_str("    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n");
+        }
+        source.push_str(&format!("    pub {ident}: {rust_type},\n"));
+        idents.push(ident);
+    }
+    source.push_str("}\n");
+    for entry_codes_enum in enums {
+        source.push('\n');
+        source.push_str(&entry_codes_enum);
+    }
+
+    source
+}
+
+fn entry_codes_enum(name: &str, field: &Field, codes: &[(String, Option<String>)]) -> String {
+    let mut source = format!(
+        "/// Entry codes of `{}`.\n#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]\npub enum {name} {{\n",
+        field.name
+    );
+    let mut variants: Vec<String> = vec![];
+    for (code, label) in codes {
+        let mut variant = pascal_case(code);
+        if !variant.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            variant = format!("V{variant}");
+        }
+        if RESERVED.contains(&variant.as_str()) {
+            variant.push('_');
+        }
+        while variants.contains(&variant) {
+            variant.push('_');
+        }
+        source.push_str(&doc_comment("    ", label.as_deref(), None));
+        source.push_str(&format!(
+            "    #[serde(rename = {code:?})]\n    {variant},\n"
+        ));
+        variants.push(variant);
+    }
+    source.push_str("}\n");
+
+    source
+}
+
+fn rust_type(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Value(AttributeType::Text) => "String".to_string(),
+        FieldType::Value(AttributeType::Numeric) => "f64".to_string(),
+        FieldType::Value(AttributeType::Boolean) => "bool".to_string(),
+        // Date time as ISO 8601 string, binary as base64 string.
+        FieldType::Value(AttributeType::DateTime) => "String".to_string(),
+        FieldType::Value(AttributeType::Binary) => "String".to_string(),
+        FieldType::Reference(name) => name.clone(),
+        FieldType::Array(item_type) => format!("Vec<{}>", rust_type(item_type)),
+        FieldType::Null => "()".to_string(),
+    }
+}
+
+/// Use `inner` as the innermost type of (possibly nested) arrays.
+fn wrap_arrays(field_type: &FieldType, inner: String) -> String {
+    match field_type {
+        FieldType::Array(item_type) => format!("Vec<{}>", wrap_arrays(item_type, inner)),
+        _ => inner,
+    }
+}
+
+fn field_ident(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        ident = format!("_{ident}");
+    }
+    if RESERVED.contains(&ident.as_str()) {
+        ident.push('_');
+    } else if KEYWORDS.contains(&ident.as_str()) {
+        ident = format!("r#{ident}");
+    }
+    ident
+}
+
+fn doc_comment(indent: &str, summary: Option<&str>, details: Option<&str>) -> String {
+    let mut lines: Vec<&str> = vec![];
+    if let Some(summary) = summary {
+        lines.extend(summary.lines());
+    }
+    if let Some(details) = details {
+        if !lines.is_empty() {
+            lines.push("");
+        }
+        lines.extend(details.lines());
+    }
+    lines
+        .into_iter()
+        .map(|line| {
+            if line.is_empty() {
+                format!("{indent}///\n")
+            } else {
+                format!("{indent}/// {line}\n")
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::build;
+
+    #[test]
+    fn generate_structs() {
+        let address = build(
+            r#"ADD ATTRIBUTE street=Text
+ADD META en PROPS name="Address"
+ADD CONFORMANCE ATTRS street="M"
+"#,
+        );
+        let address_said = address.said.clone().unwrap();
+        let person = build(&format!(
+            r#"ADD ATTRIBUTE name=Text age=Numeric type=Text address=refs:{address_said} tags=Array[Text]
+ADD META en PROPS name="Person record" description="Registered person"
+ADD LABEL en ATTRS name="Full name" type="Type"
+ADD INFORMATION en ATTRS name="As in passport"
+ADD CONFORMANCE ATTRS name="M" tags="M"
+ADD ENTRY_CODE ATTRS type=["natural", "legal"] tags=["a", "b"]
+ADD ENTRY en ATTRS type={{"natural": "Natural person", "legal": "Legal person"}}
+"#
+        ));
+
+        let source = generate(&person, &[address], Some(Language::Eng)).unwrap();
+        let person_said = person.said.unwrap();
+
+        assert_eq!(
+            source,
+            format!(
+                r#"// Generated from OCA Bundle {person_said}. Do not edit.
+
+/// Person record
+///
+/// Registered person
+/// OCA Bundle `{person_said}`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PersonRecord {{
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age: Option<f64>,
+    /// Full name
+    ///
+    /// As in passport
+    pub name: String,
+    pub tags: Vec<PersonRecordTags>,
+    /// Type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<PersonRecordType>,
+}}
+
+/// Entry codes of `tags`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum PersonRecordTags {{
+    #[serde(rename = "a")]
+    A,
+    #[serde(rename = "b")]
+    B,
+}}
+
+/// Entry codes of `type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum PersonRecordType {{
+    /// Natural person
+    #[serde(rename = "natural")]
+    Natural,
+    /// Legal person
+    #[serde(rename = "legal")]
+    Legal,
+}}
+
+/// Address
+/// OCA Bundle `{address_said}`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Address {{
+    pub street: String,
+}}
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn field_idents() {
+        assert_eq!(field_ident("type"), "r#type");
+        assert_eq!(field_ident("first-name"), "first_name");
+        assert_eq!(field_ident("1st"), "_1st");
+        assert_eq!(field_ident("crate"), "crate_");
+        assert_eq!(field_ident("Self"), "Self_");
+        assert_eq!(field_ident("-"), "__");
+    }
+
+    #[test]
+    fn generate_valid_source() {
+        let bundle = build(
+            r#"ADD ATTRIBUTE crate=Text self=Text Self=Text super=Text type=Text
+ADD ATTRIBUTE first-name=Text first_name=Text
+ADD META en PROPS name="Person"
+ADD ENTRY_CODE ATTRS first-name=["self", "a"] first_name=["b"]
+"#,
+        );
+        let source = generate(&bundle, &[], None).unwrap();
+        let file = syn::parse_file(&source).unwrap();
+
+        let mut names = HashSet::new();
+        for item in &file.items {
+            match item {
+                syn::Item::Struct(item) => {
+                    assert!(names.insert(item.ident.to_string()));
+                    let mut fields = HashSet::new();
+                    for field in &item.fields {
+                        assert!(fields.insert(field.ident.as_ref().unwrap().to_string()));
+                    }
+                    assert_eq!(fields.len(), 7);
+                }
+                syn::Item::Enum(item) => assert!(names.insert(item.ident.to_string())),
+                _ => panic!("unexpected item"),
+            }
+        }
+        assert_eq!(names.len(), 3);
+    }
+}
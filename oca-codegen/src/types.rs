@@ -0,0 +1,158 @@
+//! Language independent view of bundles used by the generators.
+use crate::Error;
+use isolang::Language;
+use oca_ast_semantics::ast::{AttributeType, NestedAttrType};
+use oca_bundle_semantics::state::{
+    attribute::Attribute,
+    dependencies::{said_of, Dependencies},
+    entries::EntriesElement,
+    entry_codes::EntryCodes,
+    naming::{pascal_case, type_name},
+    oca::OCABundle,
+    view::BundleView,
+};
+use std::collections::HashMap;
+
+pub(crate) struct TypeDef {
+    pub said: String,
+    /// PascalCase type name, unique among generated types.
+    pub name: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub fields: Vec<Field>,
+}
+
+pub(crate) struct Field {
+    /// Attribute name as used in records.
+    pub name: String,
+    pub field_type: FieldType,
+    pub optional: bool,
+    pub label: Option<String>,
+    pub information: Option<String>,
+    /// Entry codes with their labels.
+    pub entry_codes: Option<Vec<(String, Option<String>)>>,
+}
+
+pub(crate) enum FieldType {
+    Value(AttributeType),
+    /// Name of the referenced type.
+    Reference(String),
+    Array(Box<FieldType>),
+    Null,
+}
+
+/// Collect type definitions of the bundle and all bundles it references.
+/// The bundle type comes first.
+pub(crate) fn collect(
+    bundle: &OCABundle,
+    dependencies: &[OCABundle],
+    language: Option<Language>,
+) -> Result<Vec<TypeDef>, Error> {
+    let mut collector = Collector {
+        dependencies: Dependencies::new(dependencies),
+        language,
+        names: HashMap::new(),
+        types: vec![],
+    };
+    collector.type_def(bundle)?;
+
+    Ok(collector.types)
+}
+
+struct Collector<'a> {
+    dependencies: Dependencies<'a>,
+    language: Option<Language>,
+    /// Type names by bundle SAID.
+    names: HashMap<String, String>,
+    types: Vec<TypeDef>,
+}
+
+impl Collector<'_> {
+    fn type_def(&mut self, bundle: &OCABundle) -> Result<String, Error> {
+        let said = said_of(bundle)?;
+        if let Some(name) = self.names.get(&said) {
+            return Ok(name.clone());
+        }
+        let view = BundleView::new(bundle);
+        let meta = view.metas_or_first(self.language);
+        let title = meta.and_then(|meta| meta.get("name")).cloned();
+        let description = meta.and_then(|meta| meta.get("description")).cloned();
+        let mut name = type_name(&view, self.language);
+        if self.names.values().any(|existing| *existing == name) {
+            name = format!("{name}{}", pascal_case(&said));
+        }
+        self.names.insert(said.clone(), name.clone());
+        let index = self.types.len();
+        self.types.push(TypeDef {
+            said,
+            name: name.clone(),
+            title,
+            description,
+            fields: vec![],
+        });
+
+        let mut fields = vec![];
+        for attribute in view.attributes() {
+            let attribute = attribute.attribute();
+            let Some(attr_type) = &attribute.attribute_type else {
+                continue;
+            };
+            let field_type = self.field_type(attr_type)?;
+            fields.push(Field {
+                name: attribute.name.clone(),
+                field_type,
+                optional: attribute.conformance.as_deref() != Some("M"),
+                label: self.localized(&attribute.labels),
+                information: self.localized(&attribute.informations),
+                entry_codes: self.entry_codes(attribute),
+            });
+        }
+        self.types[index].fields = fields;
+
+        Ok(name)
+    }
+
+    fn field_type(&mut self, attr_type: &NestedAttrType) -> Result<FieldType, Error> {
+        let field_type = match attr_type {
+            NestedAttrType::Value(value_type) => FieldType::Value(*value_type),
+            NestedAttrType::Array(item_type) => {
+                FieldType::Array(Box::new(self.field_type(item_type)?))
+            }
+            NestedAttrType::Reference(reference) => {
+                let dependency = self.dependencies.resolve(reference)?;
+                FieldType::Reference(self.type_def(dependency)?)
+            }
+            NestedAttrType::Null => FieldType::Null,
+        };
+
+        Ok(field_type)
+    }
+
+    fn localized(&self, values: &Option<HashMap<Language, String>>) -> Option<String> {
+        values.as_ref()?.get(&self.language?).cloned()
+    }
+
+    fn entry_codes(&self, attribute: &Attribute) -> Option<Vec<(String, Option<String>)>> {
+        let codes: Vec<String> = match attribute.entry_codes.as_ref()? {
+            EntryCodes::Sai(_) => return None,
+            EntryCodes::Array(codes) => codes.clone(),
+            EntryCodes::Object(groups) => groups.values().flatten().cloned().collect(),
+        };
+        let labels =
+            self.language
+                .and_then(|lang| match attribute.entries.as_ref()?.get(&lang)? {
+                    EntriesElement::Object(labels) => Some(labels),
+                    EntriesElement::Sai(_) => None,
+                });
+
+        Some(
+            codes
+                .into_iter()
+                .map(|code| {
+                    let label = labels.and_then(|labels| labels.get(&code)).cloned();
+                    (code, label)
+                })
+                .collect(),
+        )
+    }
+}
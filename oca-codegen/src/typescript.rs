@@ -0,0 +1,376 @@
+//! Generation of TypeScript declarations and form models.
+//!
+//! Declarations follow the same rules as the Rust structs: attributes which
+//! aren't mandatory are optional properties and entry codes become string
+//! literal unions. The form model is a JSON description of the capture form
+//! with texts in all languages of the bundle.
+use crate::{
+    types::{collect, FieldType, TypeDef},
+    Error,
+};
+use isolang::Language;
+use oca_ast_semantics::ast::{AttributeType, NestedAttrType};
+use oca_bundle_semantics::state::{
+    attribute::Attribute,
+    dependencies::{said_of, Dependencies},
+    entries::EntriesElement,
+    entry_codes::EntryCodes,
+    naming::{lang_code, pascal_case},
+    oca::{OCABox, OCABundle},
+};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Generate `.d.ts` declarations of the bundle and of the bundles it
+/// references.
+pub fn generate_declarations(
+    bundle: &OCABundle,
+    dependencies: &[OCABundle],
+    language: Option<Language>,
+) -> Result<String, Error> {
+    let types = collect(bundle, dependencies, language)?;
+    let mut source = format!(
+        "// Generated from OCA Bundle {}. Do not edit.\n",
+        types[0].said
+    );
+    for type_def in &types {
+        source.push('\n');
+        source.push_str(&interface(type_def));
+    }
+
+    Ok(source)
+}
+
+fn interface(type_def: &TypeDef) -> String {
+    let mut source = doc_comment(
+        "",
+        type_def.title.as_deref(),
+        type_def.description.as_deref(),
+    );
+    source.push_str(&format!("export interface {} {{\n", type_def.name));
+    let mut aliases = vec![];
+    for field in &type_def.fields {
+        let ts_type = match &field.entry_codes {
+            Some(codes) => {
+                let alias = format!("{}{}", type_def.name, pascal_case(&field.name));
+                let union: Vec<String> = codes.iter().map(|(code, _)| string(code)).collect();
+                aliases.push(format!(
+                    "/** Entry codes of `{}`. */\nexport type {alias} = {};\n",
+                    field.name,
+                    union.join(" | ")
+                ));
+                wrap_arrays(&field.field_type, alias)
+            }
+            None => ts_type(&field.field_type),
+        };
+        source.push_str(&doc_comment(
+            "  ",
+            field.label.as_deref(),
+            field.information.as_deref(),
+        ));
+        let optional = if field.optional { "?" } else { "" };
+        source.push_str(&format!(
+            "  {}{optional}: {ts_type};\n",
+            property_name(&field.name)
+        ));
+    }
+    source.push_str("}\n");
+    for alias in aliases {
+        source.push('\n');
+        source.push_str(&alias);
+    }
+
+    source
+}
+
+fn ts_type(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Value(AttributeType::Numeric) => "number".to_string(),
+        FieldType::Value(AttributeType::Boolean) => "boolean".to_string(),
+        FieldType::Value(_) => "string".to_string(),
+        FieldType::Reference(name) => name.clone(),
+        FieldType::Array(item_type) => format!("{}[]", ts_type(item_type)),
+        FieldType::Null => "null".to_string(),
+    }
+}
+
+fn wrap_arrays(field_type: &FieldType, inner: String) -> String {
+    match field_type {
+        FieldType::Array(item_type) => format!("{}[]", wrap_arrays(item_type, inner)),
+        _ => inner,
+    }
+}
+
+fn property_name(name: &str) -> String {
+    let is_identifier = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        name.to_string()
+    } else {
+        string(name)
+    }
+}
+
+fn string(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+fn doc_comment(indent: &str, summary: Option<&str>, details: Option<&str>) -> String {
+    let mut lines: Vec<&str> = vec![];
+    if let Some(summary) = summary {
+        lines.extend(summary.lines());
+    }
+    if let Some(details) = details {
+        if !lines.is_empty() {
+            lines.push("");
+        }
+        lines.extend(details.lines());
+    }
+    if lines.is_empty() {
+        return String::new();
+    }
+    let mut comment = format!("{indent}/**\n");
+    for line in lines {
+        let line = line.replace("*/", "*\\/");
+        if line.is_empty() {
+            comment.push_str(&format!("{indent} *\n"));
+        } else {
+            comment.push_str(&format!("{indent} * {line}\n"));
+        }
+    }
+    comment.push_str(&format!("{indent} */\n"));
+    comment
+}
+
+/// Generate form model of the bundle.
+///
+/// Every attribute becomes a field with its type, conformance, labels, help
+/// texts (from the Information overlay), entry options and condition.
+/// Fields referencing other bundles contain their form models.
+pub fn generate_form_model(bundle: &OCABundle, dependencies: &[OCABundle]) -> Result<Value, Error> {
+    form_model(bundle, &Dependencies::new(dependencies))
+}
+
+fn form_model(bundle: &OCABundle, dependencies: &Dependencies) -> Result<Value, Error> {
+    let said = said_of(bundle)?;
+    let oca_box = OCABox::from(bundle.clone());
+
+    let mut languages = BTreeSet::new();
+    let mut meta = BTreeMap::new();
+    for (lang, values) in oca_box.meta.iter().flatten() {
+        languages.insert(lang_code(lang));
+        let values: BTreeMap<_, _> = values.iter().collect();
+        meta.insert(lang_code(lang), values);
+    }
+
+    let mut attributes: Vec<&Attribute> = oca_box.attributes.values().collect();
+    attributes.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut fields = vec![];
+    for attribute in attributes {
+        let Some(attr_type) = &attribute.attribute_type else {
+            continue;
+        };
+        for lang in attribute.labels.iter().flat_map(|labels| labels.keys()) {
+            languages.insert(lang_code(lang));
+        }
+        fields.push(field(attribute, attr_type, dependencies)?);
+    }
+
+    Ok(json!({
+        "said": said,
+        "languages": languages,
+        "meta": meta,
+        "fields": fields,
+    }))
+}
+
+fn field(
+    attribute: &Attribute,
+    attr_type: &NestedAttrType,
+    dependencies: &Dependencies,
+) -> Result<Value, Error> {
+    let mut field = Map::new();
+    field.insert("name".to_string(), json!(attribute.name));
+
+    let mut innermost = attr_type;
+    let mut array = false;
+    while let NestedAttrType::Array(item_type) = innermost {
+        innermost = item_type;
+        array = true;
+    }
+    match innermost {
+        NestedAttrType::Value(value_type) => {
+            field.insert("type".to_string(), json!(value_type));
+        }
+        NestedAttrType::Reference(reference) => {
+            let dependency = dependencies.resolve(reference)?;
+            field.insert("type".to_string(), json!("Reference"));
+            field.insert("form".to_string(), form_model(dependency, dependencies)?);
+        }
+        NestedAttrType::Array(_) | NestedAttrType::Null => {
+            field.insert("type".to_string(), Value::Null);
+        }
+    }
+    field.insert("array".to_string(), json!(array));
+    field.insert(
+        "required".to_string(),
+        json!(attribute.conformance.as_deref() == Some("M")),
+    );
+    field.insert("flagged".to_string(), json!(attribute.is_flagged));
+    field.insert("label".to_string(), localized(&attribute.labels));
+    field.insert("help".to_string(), localized(&attribute.informations));
+    if let Some(options) = options(attribute) {
+        field.insert("options".to_string(), options);
+    }
+    for (key, value) in [
+        ("format", &attribute.format),
+        ("unit", &attribute.unit),
+        ("cardinality", &attribute.cardinality),
+    ] {
+        if let Some(value) = value {
+            field.insert(key.to_string(), json!(value));
+        }
+    }
+    if let Some(condition) = attribute.named_condition() {
+        field.insert("condition".to_string(), json!(condition));
+        field.insert(
+            "dependencies".to_string(),
+            json!(attribute.dependencies.clone().unwrap_or_default()),
+        );
+    }
+
+    Ok(Value::Object(field))
+}
+
+fn options(attribute: &Attribute) -> Option<Value> {
+    let codes: Vec<&String> = match attribute.entry_codes.as_ref()? {
+        EntryCodes::Sai(_) => return None,
+        EntryCodes::Array(codes) => codes.iter().collect(),
+        EntryCodes::Object(groups) => groups.values().flatten().collect(),
+    };
+    let options = codes
+        .into_iter()
+        .map(|code| {
+            let labels: BTreeMap<String, &String> = attribute
+                .entries
+                .iter()
+                .flatten()
+                .filter_map(|(lang, entries)| match entries {
+                    EntriesElement::Object(entries) => {
+                        entries.get(code).map(|label| (lang_code(lang), label))
+                    }
+                    EntriesElement::Sai(_) => None,
+                })
+                .collect();
+            json!({ "value": code, "label": labels })
+        })
+        .collect();
+
+    Some(Value::Array(options))
+}
+
+fn localized(values: &Option<HashMap<Language, String>>) -> Value {
+    let values: BTreeMap<String, &String> = values
+        .iter()
+        .flatten()
+        .map(|(lang, value)| (lang_code(lang), value))
+        .collect();
+    json!(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, build};
+
+    fn bundles() -> (OCABundle, OCABundle) {
+        let address = address();
+        let person = build(&format!(
+            r#"ADD ATTRIBUTE name=Text age=Numeric type=Text address=refs:{} first-name=Array[Text]
+ADD META en PROPS name="Person record"
+ADD LABEL en ATTRS name="Full name" type="Type"
+ADD LABEL pl ATTRS name="Imię i nazwisko"
+ADD INFORMATION en ATTRS name="As in passport"
+ADD CONFORMANCE ATTRS name="M"
+ADD ENTRY_CODE ATTRS type=["natural", "legal"]
+ADD ENTRY en ATTRS type={{"natural": "Natural person", "legal": "Legal person"}}
+ADD CONDITION ATTRS type="${{age}} > 18"
+"#,
+            address.said.as_ref().unwrap()
+        ));
+        (person, address)
+    }
+
+    #[test]
+    fn generate_typescript_declarations() {
+        let (person, address) = bundles();
+        let source = generate_declarations(&person, &[address], Some(Language::Eng)).unwrap();
+
+        let expected = r#"/**
+ * Person record
+ */
+export interface PersonRecord {
+  address?: Address;
+  age?: number;
+  "first-name"?: string[];
+  /**
+   * Full name
+   *
+   * As in passport
+   */
+  name: string;
+  /**
+   * Type
+   */
+  type?: PersonRecordType;
+}
+
+/** Entry codes of `type`. */
+export type PersonRecordType = "natural" | "legal";
+
+/**
+ * Address
+ */
+export interface Address {
+  street?: string;
+}
+"#;
+        assert!(source.ends_with(expected), "{source}");
+    }
+
+    #[test]
+    fn generate_form() {
+        let (person, address) = bundles();
+        let address_said = address.said.clone().unwrap();
+        let model = generate_form_model(&person, &[address]).unwrap();
+
+        assert_eq!(model["languages"], json!(["en", "pl"]));
+        assert_eq!(model["meta"]["en"]["name"], "Person record");
+        let fields = model["fields"].as_array().unwrap();
+        assert_eq!(fields[0]["form"]["said"], json!(address_said.to_string()));
+        assert_eq!(fields[2]["array"], json!(true));
+        assert_eq!(
+            fields[3],
+            json!({
+                "name": "name",
+                "type": "Text",
+                "array": false,
+                "required": true,
+                "flagged": false,
+                "label": {"en": "Full name", "pl": "Imię i nazwisko"},
+                "help": {"en": "As in passport"}
+            })
+        );
+        assert_eq!(
+            fields[4]["options"],
+            json!([
+                {"value": "natural", "label": {"en": "Natural person"}},
+                {"value": "legal", "label": {"en": "Legal person"}}
+            ])
+        );
+        assert_eq!(fields[4]["condition"], "${age} > 18");
+        assert_eq!(fields[4]["dependencies"], json!(["age"]));
+    }
+}
@@ -0,0 +1,57 @@
+//! Bindings for JavaScript. Bundles are passed as JSON strings, as returned
+//! by `Facade::get_oca_bundle`, optionally with dependencies.
+use crate::{typescript, Error};
+use isolang::Language;
+use oca_bundle_semantics::state::oca::OCABundle;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+fn parse(bundle: &str, dependencies: Option<String>) -> Result<(OCABundle, Vec<OCABundle>), Error> {
+    let bundle = serde_json::from_str(bundle).map_err(|e| Error::Load {
+        message: e.to_string(),
+    })?;
+    let dependencies = match dependencies {
+        Some(dependencies) => serde_json::from_str(&dependencies).map_err(|e| Error::Load {
+            message: e.to_string(),
+        })?,
+        None => vec![],
+    };
+
+    Ok((bundle, dependencies))
+}
+
+fn to_js_error(error: Error) -> JsValue {
+    JsValue::from_str(&error.to_string())
+}
+
+/// Generate TypeScript declarations. `language` is a two or three letter
+/// ISO 639 code.
+#[wasm_bindgen(js_name = generateTypeScript)]
+pub fn generate_typescript(
+    bundle: &str,
+    dependencies: Option<String>,
+    language: Option<String>,
+) -> Result<String, JsValue> {
+    let language = match language {
+        Some(code) => Some(
+            Language::from_639_1(&code)
+                .or_else(|| Language::from_639_3(&code))
+                .ok_or_else(|| JsValue::from_str(&format!("Unknown language: {code}")))?,
+        ),
+        None => None,
+    };
+    let (bundle, dependencies) = parse(bundle, dependencies).map_err(to_js_error)?;
+
+    typescript::generate_declarations(&bundle, &dependencies, language).map_err(to_js_error)
+}
+
+/// Generate form model as a plain JavaScript object.
+#[wasm_bindgen(js_name = generateFormModel)]
+pub fn generate_form_model(bundle: &str, dependencies: Option<String>) -> Result<JsValue, JsValue> {
+    let (bundle, dependencies) = parse(bundle, dependencies).map_err(to_js_error)?;
+    let model = typescript::generate_form_model(&bundle, &dependencies).map_err(to_js_error)?;
+
+    model
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(JsValue::from)
+}
@@ -0,0 +1,67 @@
+//! Helpers for building OCA AST commands in the shape produced by the
+//! OCAFILE parser.
+use indexmap::IndexMap;
+use oca_ast_semantics::ast::{
+    CaptureContent, Command, CommandType, Content, NestedAttrType, NestedValue, ObjectKind,
+    OverlayType,
+};
+use oca_bundle_semantics::state::spec_version::SpecVersion;
+
+pub(crate) fn capture_base(content: CaptureContent) -> Command {
+    Command {
+        kind: CommandType::Add,
+        object_kind: ObjectKind::CaptureBase(content),
+    }
+}
+
+pub(crate) fn attributes(attributes: IndexMap<String, NestedAttrType>) -> Command {
+    capture_base(CaptureContent {
+        attributes: Some(attributes),
+        properties: None,
+        flagged_attributes: None,
+    })
+}
+
+/// Overlay command, `lang` is put in front of other properties.
+pub(crate) fn overlay(
+    overlay_type: fn(String) -> OverlayType,
+    lang: Option<&str>,
+    attributes: Option<IndexMap<String, NestedValue>>,
+    properties: IndexMap<String, NestedValue>,
+) -> Command {
+    let mut all_properties = IndexMap::new();
+    if let Some(lang) = lang {
+        all_properties.insert("lang".to_string(), NestedValue::Value(lang.to_string()));
+    }
+    all_properties.extend(properties);
+
+    Command {
+        kind: CommandType::Add,
+        object_kind: ObjectKind::Overlay(
+            overlay_type(SpecVersion::CURRENT.to_string()),
+            Content {
+                attributes,
+                properties: Some(all_properties),
+            },
+        ),
+    }
+}
+
+/// Make the name usable as OCAFILE attribute or reference name.
+pub(crate) fn identifier(name: &str) -> String {
+    let identifier: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if identifier.is_empty() {
+        "attribute".to_string()
+    } else {
+        identifier
+    }
+}
@@ -0,0 +1,297 @@
+//! Export of OCA Bundles to Avro record schemas.
+//!
+//! Every bundle becomes a record and referenced bundles become nested
+//! records, defined where they are used first. Attributes which aren't
+//! mandatory become unions with `null`, entry codes become enums and ISO
+//! 8601 date times use the `date`, `time-millis` and `timestamp-millis`
+//! logical types.
+use crate::Error;
+use isolang::Language;
+use oca_ast_semantics::ast::{AttributeType, NestedAttrType};
+use oca_bundle_semantics::state::{
+    attribute::Attribute,
+    dependencies::{said_of, Dependencies},
+    entry_codes::EntryCodes,
+    naming::{pascal_case, type_name},
+    oca::OCABundle,
+    view::BundleView,
+};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Convert OCA Bundle into Avro schema.
+///
+/// Record names are taken from the Meta overlay in `language`, documentation
+/// from the Meta, Label and Information overlays. The SAID of each bundle is
+/// kept in the `said` property of its record.
+pub fn to_avro(
+    bundle: &OCABundle,
+    dependencies: &[OCABundle],
+    language: Option<Language>,
+) -> Result<Value, Error> {
+    let mut exporter = Exporter {
+        dependencies: Dependencies::new(dependencies),
+        language,
+        records: HashMap::new(),
+        names: vec![],
+    };
+
+    exporter.record(bundle)
+}
+
+struct Exporter<'a> {
+    dependencies: Dependencies<'a>,
+    language: Option<Language>,
+    /// Record names by bundle SAID.
+    records: HashMap<String, String>,
+    /// Names of all defined types, Avro requires them to be unique.
+    names: Vec<String>,
+}
+
+impl Exporter<'_> {
+    /// Full record definition, or its name when it is already defined.
+    fn record(&mut self, bundle: &OCABundle) -> Result<Value, Error> {
+        let said = said_of(bundle)?;
+        if let Some(name) = self.records.get(&said) {
+            return Ok(json!(name));
+        }
+        let view = BundleView::new(bundle);
+        let meta = view.metas_or_first(self.language);
+        let name = self.unique_name(type_name(&view, self.language), &said);
+        self.records.insert(said.clone(), name.clone());
+
+        let mut record = Map::new();
+        record.insert("type".to_string(), json!("record"));
+        record.insert("name".to_string(), json!(name));
+        if let Some(description) = meta.and_then(|meta| meta.get("description")) {
+            record.insert("doc".to_string(), json!(description));
+        }
+        record.insert("said".to_string(), json!(said));
+
+        let attributes: Vec<&Attribute> = view
+            .attributes()
+            .into_iter()
+            .map(|attribute| attribute.attribute())
+            .collect();
+        let mut fields = vec![];
+        for attribute in attributes {
+            let Some(attr_type) = &attribute.attribute_type else {
+                continue;
+            };
+            let mut field = Map::new();
+            field.insert("name".to_string(), json!(field_name(&attribute.name)));
+            if let Some(doc) = self.doc(attribute) {
+                field.insert("doc".to_string(), json!(doc));
+            }
+            let enum_name = format!("{name}{}", pascal_case(&attribute.name));
+            let field_type = self.field_type(attr_type, attribute, &enum_name)?;
+            if attribute.conformance.as_deref() == Some("M") {
+                field.insert("type".to_string(), field_type);
+            } else {
+                field.insert("type".to_string(), json!(["null", field_type]));
+                field.insert("default".to_string(), Value::Null);
+            }
+            fields.push(Value::Object(field));
+        }
+        record.insert("fields".to_string(), Value::Array(fields));
+
+        Ok(Value::Object(record))
+    }
+
+    fn field_type(
+        &mut self,
+        attr_type: &NestedAttrType,
+        attribute: &Attribute,
+        enum_name: &str,
+    ) -> Result<Value, Error> {
+        let field_type = match attr_type {
+            NestedAttrType::Value(value_type) => {
+                match self.entry_codes_enum(attribute, enum_name) {
+                    Some(entry_codes) => entry_codes,
+                    None => value_type_schema(value_type, attribute.format.as_deref()),
+                }
+            }
+            NestedAttrType::Array(item_type) => json!({
+                "type": "array",
+                "items": self.field_type(item_type, attribute, enum_name)?,
+            }),
+            NestedAttrType::Reference(reference) => {
+                let dependency = self.dependencies.resolve(reference)?;
+                self.record(dependency)?
+            }
+            NestedAttrType::Null => json!("null"),
+        };
+
+        Ok(field_type)
+    }
+
+    /// Enum of entry codes. Codes which aren't valid Avro symbols can't be
+    /// represented, such attributes stay strings.
+    fn entry_codes_enum(&mut self, attribute: &Attribute, name: &str) -> Option<Value> {
+        let symbols: Vec<&String> = match attribute.entry_codes.as_ref()? {
+            EntryCodes::Sai(_) => return None,
+            EntryCodes::Array(codes) => codes.iter().collect(),
+            EntryCodes::Object(groups) => groups.values().flatten().collect(),
+        };
+        if !symbols.iter().all(|symbol| is_name(symbol)) {
+            return None;
+        }
+        if let Some(existing) = self.names.iter().find(|existing| *existing == name) {
+            return Some(json!(existing));
+        }
+        self.names.push(name.to_string());
+
+        Some(json!({
+            "type": "enum",
+            "name": name,
+            "symbols": symbols,
+        }))
+    }
+
+    fn doc(&self, attribute: &Attribute) -> Option<String> {
+        let lang = self.language?;
+        let label = attribute.labels.as_ref().and_then(|l| l.get(&lang));
+        let information = attribute.informations.as_ref().and_then(|i| i.get(&lang));
+        match (label, information) {
+            (Some(label), Some(information)) => Some(format!("{label}\n\n{information}")),
+            (label, information) => label.or(information).cloned(),
+        }
+    }
+
+    fn unique_name(&mut self, name: String, said: &str) -> String {
+        let name = if self.names.contains(&name) {
+            format!("{name}{}", pascal_case(said))
+        } else {
+            name
+        };
+        self.names.push(name.clone());
+        name
+    }
+}
+
+fn value_type_schema(value_type: &AttributeType, format: Option<&str>) -> Value {
+    match value_type {
+        AttributeType::Text => json!("string"),
+        AttributeType::Numeric => json!("double"),
+        AttributeType::Boolean => json!("boolean"),
+        AttributeType::Binary => json!("bytes"),
+        AttributeType::DateTime => match format {
+            Some("YYYY-MM-DD") => json!({ "type": "int", "logicalType": "date" }),
+            Some("hh:mm:ss") | Some("HH:mm:ss") => {
+                json!({ "type": "int", "logicalType": "time-millis" })
+            }
+            None => json!({ "type": "long", "logicalType": "timestamp-millis" }),
+            Some(f)
+                if f.starts_with("YYYY-MM-DDThh:mm:ss") || f.starts_with("YYYY-MM-DDTHH:mm:ss") =>
+            {
+                json!({ "type": "long", "logicalType": "timestamp-millis" })
+            }
+            // Custom formats are kept as formatted strings.
+            Some(_) => json!("string"),
+        },
+    }
+}
+
+/// Avro name: `[A-Za-z_][A-Za-z0-9_]*`.
+fn is_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn field_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if is_name(&name) {
+        name
+    } else {
+        format!("_{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::build;
+
+    #[test]
+    fn export_avro() {
+        let address = build(
+            r#"ADD ATTRIBUTE street=Text
+ADD META en PROPS name="Address"
+ADD CONFORMANCE ATTRS street="M"
+"#,
+        );
+        let address_said = address.said.clone().unwrap().to_string();
+        let person = build(&format!(
+            r#"ADD ATTRIBUTE name=Text born=DateTime sex=Text home=refs:{address_said} previous=Array[refs:{address_said}] photo=Binary
+ADD META en PROPS name="Person record" description="Registered person"
+ADD LABEL en ATTRS name="Full name"
+ADD INFORMATION en ATTRS name="As in passport"
+ADD CONFORMANCE ATTRS name="M" previous="M"
+ADD ENTRY_CODE ATTRS sex=["m", "f"]
+ADD FORMAT ATTRS born="YYYY-MM-DD"
+"#
+        ));
+
+        let schema = to_avro(&person, &[address], Some(Language::Eng)).unwrap();
+
+        assert_eq!(
+            schema,
+            json!({
+                "type": "record",
+                "name": "PersonRecord",
+                "doc": "Registered person",
+                "said": person.said.unwrap().to_string(),
+                "fields": [
+                    {
+                        "name": "born",
+                        "type": ["null", { "type": "int", "logicalType": "date" }],
+                        "default": null
+                    },
+                    {
+                        "name": "home",
+                        "type": ["null", {
+                            "type": "record",
+                            "name": "Address",
+                            "said": address_said,
+                            "fields": [{ "name": "street", "type": "string" }]
+                        }],
+                        "default": null
+                    },
+                    {
+                        "name": "name",
+                        "doc": "Full name\n\nAs in passport",
+                        "type": "string"
+                    },
+                    { "name": "photo", "type": ["null", "bytes"], "default": null },
+                    { "name": "previous", "type": { "type": "array", "items": "Address" } },
+                    {
+                        "name": "sex",
+                        "type": ["null", {
+                            "type": "enum",
+                            "name": "PersonRecordSex",
+                            "symbols": ["m", "f"]
+                        }],
+                        "default": null
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn codes_which_are_not_symbols() {
+        let bundle = build(
+            r#"ADD ATTRIBUTE level=Text
+ADD CONFORMANCE ATTRS level="M"
+ADD ENTRY_CODE ATTRS level=["1", "2"]
+"#,
+        );
+        let schema = to_avro(&bundle, &[], None).unwrap();
+
+        assert!(schema["name"].as_str().unwrap().starts_with("Bundle"));
+        assert_eq!(schema["fields"][0]["type"], json!("string"));
+    }
+}
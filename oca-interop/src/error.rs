@@ -0,0 +1,26 @@
+use oca_bundle_semantics::state::dependencies::Error as DependencyError;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum Error {
+    #[error("Attribute name `{name}` is reserved")]
+    ReservedName { name: String },
+    #[error("Invalid schema: {message}")]
+    InvalidSchema { message: String },
+    #[error("Invalid template, sheet {sheet} row {row}: {message}")]
+    InvalidTemplate {
+        sheet: String,
+        row: usize,
+        message: String,
+    },
+    #[error("Invalid data: {message}")]
+    InvalidData { message: String },
+    #[error("Invalid translation file: {message}")]
+    InvalidTranslation { message: String },
+    #[error("Spreadsheet error: {message}")]
+    Spreadsheet { message: String },
+    #[serde(untagged)]
+    #[error(transparent)]
+    Dependency(#[from] DependencyError),
+}
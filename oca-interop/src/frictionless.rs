@@ -0,0 +1,508 @@
+//! Conversions between OCA Bundles and Frictionless Table Schema.
+//!
+//! Fields correspond to capture base attributes. `constraints.enum` maps to
+//! entry codes, `constraints.required` to conformance, `format` and
+//! `constraints.pattern` to the Format overlay and `title` and
+//! `description` to labels and information.
+//!
+//! OCA date time formats (`YYYY-MM-DD`) are translated to and from the
+//! `strptime` patterns used by Table Schema (`%Y-%m-%d`).
+use crate::{
+    ast::{self, identifier},
+    Error, Warning,
+};
+use indexmap::IndexMap;
+use isolang::Language;
+use oca_ast_semantics::ast::{AttributeType, NestedAttrType, NestedValue, OCAAst, OverlayType};
+use oca_bundle_semantics::state::{
+    attribute::Attribute,
+    entry_codes::EntryCodes,
+    oca::{OCABox, OCABundle},
+};
+use oca_file_semantics::ocafile::generate_from_ast;
+use serde_json::{json, Map, Value};
+
+pub const TABLE_SCHEMA_PROFILE: &str = "https://datapackage.org/profiles/2.0/tableschema.json";
+
+/// Date time tokens of OCA format and their `strptime` counterparts.
+const DATE_TIME_TOKENS: &[(&str, &str)] = &[
+    ("YYYY", "%Y"),
+    ("MM", "%m"),
+    ("DD", "%d"),
+    ("hh", "%H"),
+    ("HH", "%H"),
+    ("mm", "%M"),
+    ("ss", "%S"),
+];
+
+const SCHEMA_KEYWORDS: &[&str] = &["$schema", "fields"];
+const FIELD_KEYWORDS: &[&str] = &[
+    "name",
+    "type",
+    "format",
+    "title",
+    "description",
+    "constraints",
+    "itemType",
+    "example",
+];
+const CONSTRAINTS: &[&str] = &["required", "enum", "pattern"];
+
+/// Convert OCA Bundle into Table Schema.
+///
+/// Titles and descriptions are taken from the Label and Information
+/// overlays in `language`. When `language` is `None` they are omitted.
+/// References to other bundles have no counterpart in Table Schema and are
+/// exported as `object` fields.
+pub fn to_table_schema(bundle: &OCABundle, language: Option<Language>) -> Value {
+    let oca_box = OCABox::from(bundle.clone());
+    let mut attributes: Vec<&Attribute> = oca_box.attributes.values().collect();
+    attributes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let fields: Vec<Value> = attributes
+        .into_iter()
+        .map(|attribute| Value::Object(field(attribute, language)))
+        .collect();
+
+    json!({
+        "$schema": TABLE_SCHEMA_PROFILE,
+        "fields": fields,
+    })
+}
+
+fn field(attribute: &Attribute, language: Option<Language>) -> Map<String, Value> {
+    let mut field = Map::new();
+    field.insert("name".to_string(), json!(attribute.name));
+    let format = attribute.format.as_deref();
+    let mut constraints = Map::new();
+    match &attribute.attribute_type {
+        Some(NestedAttrType::Value(value_type)) => {
+            field.extend(value_field(value_type, format, &mut constraints));
+        }
+        Some(NestedAttrType::Array(item_type)) => match item_type.as_ref() {
+            NestedAttrType::Value(value_type) => {
+                field.insert("type".to_string(), json!("list"));
+                let item = value_field(value_type, None, &mut Map::new());
+                field.insert("itemType".to_string(), item["type"].clone());
+            }
+            _ => {
+                field.insert("type".to_string(), json!("array"));
+            }
+        },
+        Some(NestedAttrType::Reference(_)) => {
+            field.insert("type".to_string(), json!("object"));
+        }
+        Some(NestedAttrType::Null) | None => {
+            field.insert("type".to_string(), json!("any"));
+        }
+    }
+    if let Some(lang) = language {
+        if let Some(label) = attribute.labels.as_ref().and_then(|l| l.get(&lang)) {
+            field.insert("title".to_string(), json!(label));
+        }
+        if let Some(info) = attribute.informations.as_ref().and_then(|i| i.get(&lang)) {
+            field.insert("description".to_string(), json!(info));
+        }
+    }
+
+    if attribute.conformance.as_deref() == Some("M") {
+        constraints.insert("required".to_string(), json!(true));
+    }
+    let codes: Option<Vec<&String>> = match &attribute.entry_codes {
+        Some(EntryCodes::Array(codes)) => Some(codes.iter().collect()),
+        Some(EntryCodes::Object(groups)) => Some(groups.values().flatten().collect()),
+        Some(EntryCodes::Sai(_)) | None => None,
+    };
+    if let Some(codes) = codes {
+        constraints.insert("enum".to_string(), json!(codes));
+    }
+    if !constraints.is_empty() {
+        field.insert("constraints".to_string(), Value::Object(constraints));
+    }
+
+    field
+}
+
+/// Type and format of the field. Text format becomes pattern constraint.
+fn value_field(
+    value_type: &AttributeType,
+    format: Option<&str>,
+    constraints: &mut Map<String, Value>,
+) -> Map<String, Value> {
+    let mut field = Map::new();
+    match value_type {
+        AttributeType::Text => {
+            field.insert("type".to_string(), json!("string"));
+            if let Some(pattern) = format {
+                constraints.insert("pattern".to_string(), json!(pattern));
+            }
+        }
+        AttributeType::Numeric => {
+            field.insert("type".to_string(), json!("number"));
+        }
+        AttributeType::Boolean => {
+            field.insert("type".to_string(), json!("boolean"));
+        }
+        AttributeType::DateTime => {
+            let (field_type, format) = match format {
+                None => ("datetime", None),
+                Some("YYYY-MM-DD") => ("date", None),
+                Some("hh:mm:ss") => ("time", None),
+                Some(format) if !format.contains("hh") && !format.contains("HH") => {
+                    ("date", Some(to_strptime(format)))
+                }
+                Some(format) if !format.contains('Y') && !format.contains('D') => {
+                    ("time", Some(to_strptime(format)))
+                }
+                Some(format) => ("datetime", Some(to_strptime(format))),
+            };
+            field.insert("type".to_string(), json!(field_type));
+            if let Some(format) = format {
+                field.insert("format".to_string(), json!(format));
+            }
+        }
+        AttributeType::Binary => {
+            field.insert("type".to_string(), json!("string"));
+            field.insert("format".to_string(), json!("binary"));
+        }
+    }
+
+    field
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    pub oca_ast: OCAAst,
+    pub ocafile: String,
+    pub warnings: Vec<Warning>,
+}
+
+/// Convert Table Schema into OCA AST.
+///
+/// Field titles and descriptions are stored as labels and information in
+/// `language`.
+pub fn from_table_schema(schema: &Value, language: Language) -> Result<Import, Error> {
+    let lang = language
+        .to_639_1()
+        .ok_or_else(|| Error::InvalidSchema {
+            message: format!("language {} has no two letter code", language.to_639_3()),
+        })?
+        .to_string();
+    let fields = schema
+        .get("fields")
+        .and_then(Value::as_array)
+        .ok_or_else(|| Error::InvalidSchema {
+            message: "table schema has no fields".to_string(),
+        })?;
+
+    let mut warnings = vec![];
+    let mut warn = |pointer: &str, message: String| {
+        warnings.push(Warning {
+            pointer: pointer.to_string(),
+            message,
+        })
+    };
+    if let Value::Object(schema) = schema {
+        for keyword in schema.keys() {
+            if !SCHEMA_KEYWORDS.contains(&keyword.as_str()) {
+                warn("", format!("property `{keyword}` is not supported"));
+            }
+        }
+    }
+
+    let mut attributes = IndexMap::new();
+    let mut labels = IndexMap::new();
+    let mut informations = IndexMap::new();
+    let mut conformances = IndexMap::new();
+    let mut entry_codes = IndexMap::new();
+    let mut formats = IndexMap::new();
+    for (i, field) in fields.iter().enumerate() {
+        let pointer = format!("/fields/{i}");
+        let Some(name) = field.get("name").and_then(Value::as_str) else {
+            return Err(Error::InvalidSchema {
+                message: format!("field {i} has no name"),
+            });
+        };
+        let attr_name = identifier(name);
+        if attr_name != name {
+            warn(&pointer, format!("field `{name}` renamed to `{attr_name}`"));
+        }
+        if attributes.contains_key(&attr_name) {
+            warn(
+                &pointer,
+                format!("attribute `{attr_name}` is already defined"),
+            );
+            continue;
+        }
+        for keyword in field.as_object().into_iter().flat_map(|field| field.keys()) {
+            if !FIELD_KEYWORDS.contains(&keyword.as_str()) {
+                warn(&pointer, format!("property `{keyword}` is not supported"));
+            }
+        }
+
+        let field_type = field.get("type").and_then(Value::as_str).unwrap_or("any");
+        let field_format = field
+            .get("format")
+            .and_then(Value::as_str)
+            .filter(|format| !matches!(*format, "default" | "any"));
+        let (attr_type, mut format) = match field_type {
+            "list" | "array" => {
+                let item_type = match field.get("itemType").and_then(Value::as_str) {
+                    Some(item_type) if field_type == "list" => item_type,
+                    _ => {
+                        warn(&pointer, "list items mapped to Text".to_string());
+                        "string"
+                    }
+                };
+                let (item_type, format, warning) = attribute_type(item_type, None);
+                if let Some(warning) = warning {
+                    warn(&pointer, warning);
+                }
+                (
+                    NestedAttrType::Array(Box::new(NestedAttrType::Value(item_type))),
+                    format,
+                )
+            }
+            field_type => {
+                let (value_type, format, warning) = attribute_type(field_type, field_format);
+                if let Some(warning) = warning {
+                    warn(&pointer, warning);
+                }
+                (NestedAttrType::Value(value_type), format)
+            }
+        };
+
+        let empty = Map::new();
+        let constraints = field
+            .get("constraints")
+            .and_then(Value::as_object)
+            .unwrap_or(&empty);
+        for constraint in constraints.keys() {
+            if !CONSTRAINTS.contains(&constraint.as_str()) {
+                warn(
+                    &format!("{pointer}/constraints"),
+                    format!("constraint `{constraint}` is not supported"),
+                );
+            }
+        }
+        if let Some(pattern) = constraints.get("pattern").and_then(Value::as_str) {
+            format = Some(pattern.to_string());
+        }
+        if let Some(values) = constraints.get("enum").and_then(Value::as_array) {
+            let mut codes = vec![];
+            for value in values {
+                match value {
+                    Value::String(code) => codes.push(code.clone()),
+                    Value::Number(code) => codes.push(code.to_string()),
+                    Value::Bool(code) => codes.push(code.to_string()),
+                    other => warn(
+                        &format!("{pointer}/constraints/enum"),
+                        format!("enum value {other} is not supported"),
+                    ),
+                }
+            }
+            entry_codes.insert(
+                attr_name.clone(),
+                NestedValue::Array(codes.into_iter().map(NestedValue::Value).collect()),
+            );
+        }
+        let required = constraints.get("required").and_then(Value::as_bool) == Some(true);
+        conformances.insert(
+            attr_name.clone(),
+            NestedValue::Value(if required { "M" } else { "O" }.to_string()),
+        );
+        if let Some(format) = format {
+            formats.insert(attr_name.clone(), NestedValue::Value(format));
+        }
+        if let Some(title) = field.get("title").and_then(Value::as_str) {
+            labels.insert(attr_name.clone(), NestedValue::Value(title.to_string()));
+        }
+        if let Some(description) = field.get("description").and_then(Value::as_str) {
+            informations.insert(
+                attr_name.clone(),
+                NestedValue::Value(description.to_string()),
+            );
+        }
+        attributes.insert(attr_name, attr_type);
+    }
+    if attributes.is_empty() {
+        return Err(Error::InvalidSchema {
+            message: "table schema has no fields".to_string(),
+        });
+    }
+
+    let mut oca_ast = OCAAst::new();
+    oca_ast.commands.push(ast::attributes(attributes));
+    for (overlay_type, attributes, localized) in [
+        (
+            OverlayType::Label as fn(String) -> OverlayType,
+            labels,
+            true,
+        ),
+        (OverlayType::Information, informations, true),
+        (OverlayType::Conformance, conformances, false),
+        (OverlayType::EntryCode, entry_codes, false),
+        (OverlayType::Format, formats, false),
+    ] {
+        if attributes.is_empty() {
+            continue;
+        }
+        oca_ast.commands.push(ast::overlay(
+            overlay_type,
+            localized.then_some(lang.as_str()),
+            Some(attributes),
+            IndexMap::new(),
+        ));
+    }
+    let ocafile = generate_from_ast(&oca_ast);
+
+    Ok(Import {
+        oca_ast,
+        ocafile,
+        warnings,
+    })
+}
+
+/// Attribute type and OCA format of Table Schema field type, with warning
+/// when the mapping loses information.
+fn attribute_type(
+    field_type: &str,
+    format: Option<&str>,
+) -> (AttributeType, Option<String>, Option<String>) {
+    let date_time = |default: Option<&str>| {
+        let format = format.map(from_strptime).or(default.map(str::to_string));
+        (AttributeType::DateTime, format, None)
+    };
+    match (field_type, format) {
+        ("string", Some("binary")) => (AttributeType::Binary, None, None),
+        ("string", None) => (AttributeType::Text, None, None),
+        ("string", Some(format)) => (
+            AttributeType::Text,
+            None,
+            Some(format!("format `{format}` is not supported")),
+        ),
+        ("number", _) => (AttributeType::Numeric, None, None),
+        ("integer", _) => (
+            AttributeType::Numeric,
+            None,
+            Some("integer mapped to Numeric".to_string()),
+        ),
+        ("boolean", _) => (AttributeType::Boolean, None, None),
+        ("date", _) => date_time(Some("YYYY-MM-DD")),
+        ("time", _) => date_time(Some("hh:mm:ss")),
+        ("datetime", _) => date_time(None),
+        (other, _) => (
+            AttributeType::Text,
+            None,
+            Some(format!("type `{other}` mapped to Text")),
+        ),
+    }
+}
+
+fn to_strptime(format: &str) -> String {
+    DATE_TIME_TOKENS
+        .iter()
+        .fold(format.to_string(), |format, (oca, strptime)| {
+            format.replace(oca, strptime)
+        })
+}
+
+fn from_strptime(format: &str) -> String {
+    DATE_TIME_TOKENS
+        .iter()
+        .filter(|(oca, _)| *oca != "HH")
+        .fold(format.to_string(), |format, (oca, strptime)| {
+            format.replace(strptime, oca)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::build;
+    use oca_bundle_semantics::build::from_ast;
+
+    const OCAFILE: &str = r#"ADD ATTRIBUTE name=Text sex=Text birthdate=DateTime visited=DateTime scores=Array[Numeric] photo=Binary
+ADD LABEL en ATTRS name="Full name" sex="Sex"
+ADD INFORMATION en ATTRS name="As in passport"
+ADD CONFORMANCE ATTRS name="M" sex="O" birthdate="O" visited="O" scores="O" photo="O"
+ADD ENTRY_CODE ATTRS sex=["m", "f"]
+ADD FORMAT ATTRS name="^[A-Z]" birthdate="YYYY-MM-DD" visited="DD.MM.YYYY hh:mm"
+"#;
+
+    #[test]
+    fn export_table_schema() {
+        let bundle = build(OCAFILE);
+        assert_eq!(
+            to_table_schema(&bundle, Some(Language::Eng)),
+            json!({
+                "$schema": TABLE_SCHEMA_PROFILE,
+                "fields": [
+                    { "name": "birthdate", "type": "date" },
+                    {
+                        "name": "name",
+                        "type": "string",
+                        "title": "Full name",
+                        "description": "As in passport",
+                        "constraints": { "pattern": "^[A-Z]", "required": true }
+                    },
+                    { "name": "photo", "type": "string", "format": "binary" },
+                    { "name": "scores", "type": "list", "itemType": "number" },
+                    {
+                        "name": "sex",
+                        "type": "string",
+                        "title": "Sex",
+                        "constraints": { "enum": ["m", "f"] }
+                    },
+                    { "name": "visited", "type": "datetime", "format": "%d.%m.%Y %H:%M" }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn table_schema_roundtrip() {
+        let bundle = build(OCAFILE);
+        let schema = to_table_schema(&bundle, Some(Language::Eng));
+        let import = from_table_schema(&schema, Language::Eng).unwrap();
+
+        assert!(import.warnings.is_empty());
+        assert_eq!(
+            from_ast(None, &import.oca_ast).unwrap().oca_bundle.said,
+            bundle.said
+        );
+        assert_eq!(build(&import.ocafile).said, bundle.said);
+    }
+
+    #[test]
+    fn import_table_schema() {
+        let schema = json!({
+            "fields": [
+                { "name": "id", "type": "integer", "constraints": { "required": true, "unique": true } },
+                { "name": "home page", "type": "string", "format": "uri" },
+                { "name": "location", "type": "geopoint" }
+            ],
+            "primaryKey": ["id"]
+        });
+        let import = from_table_schema(&schema, Language::Eng).unwrap();
+
+        assert_eq!(
+            import.ocafile,
+            r#"ADD ATTRIBUTE id=Numeric home_page=Text location=Text
+ADD CONFORMANCE ATTRS id="M" home_page="O" location="O"
+"#
+        );
+        let warnings: Vec<String> = import.warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "#: property `primaryKey` is not supported",
+                "/fields/0: integer mapped to Numeric",
+                "/fields/0/constraints: constraint `unique` is not supported",
+                "/fields/1: field `home page` renamed to `home_page`",
+                "/fields/1: format `uri` is not supported",
+                "/fields/2: type `geopoint` mapped to Text",
+            ]
+        );
+    }
+}
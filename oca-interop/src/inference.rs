@@ -0,0 +1,422 @@
+//! Inference of OCA Bundle drafts from sample data.
+//!
+//! Every CSV column or NDJSON property becomes an attribute typed after the
+//! observed values. Columns with missing or empty values are optional, the
+//! others mandatory, and text columns with few distinct, repeating values
+//! get entry codes. The result is a draft meant to be reviewed and extended
+//! with labels and other overlays.
+use crate::{
+    ast::{self, identifier},
+    Error, Warning,
+};
+use indexmap::IndexMap;
+use oca_ast_semantics::ast::{AttributeType, NestedAttrType, NestedValue, OCAAst, OverlayType};
+use oca_file_semantics::ocafile::generate_from_ast;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+const DATE_FORMAT: &str = "YYYY-MM-DD";
+const TIME_FORMAT: &str = "hh:mm:ss";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    entry_codes_limit: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            entry_codes_limit: 10,
+        }
+    }
+}
+
+impl Options {
+    /// Maximal number of distinct values of a column turned into entry
+    /// codes, `0` disables entry codes.
+    pub fn with_entry_codes_limit(mut self, limit: usize) -> Self {
+        self.entry_codes_limit = limit;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inference {
+    pub oca_ast: OCAAst,
+    /// OCAFILE draft generated from `oca_ast`.
+    pub ocafile: String,
+    /// Warnings point to the column, e.g. `/address`.
+    pub warnings: Vec<Warning>,
+}
+
+/// Infer bundle from CSV with header row.
+pub fn infer_from_csv(csv: &str, options: &Options) -> Result<Inference, Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(csv.as_bytes());
+    let header = reader.headers().map_err(invalid_data)?.clone();
+    let mut columns: Vec<Column> = header.iter().map(Column::new).collect();
+    for record in reader.records() {
+        let record = record.map_err(invalid_data)?;
+        for (i, column) in columns.iter_mut().enumerate() {
+            column.observe_text(record.get(i).unwrap_or_default());
+        }
+    }
+
+    infer(columns, options)
+}
+
+/// Infer bundle from newline delimited JSON objects.
+pub fn infer_from_ndjson(ndjson: &str, options: &Options) -> Result<Inference, Error> {
+    let mut columns: Vec<Column> = vec![];
+    let mut records = 0;
+    for (i, line) in ndjson.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = serde_json::from_str(line).map_err(|e| Error::InvalidData {
+            message: format!("line {}: {e}", i + 1),
+        })?;
+        let Value::Object(record) = value else {
+            return Err(Error::InvalidData {
+                message: format!("line {}: record is not an object", i + 1),
+            });
+        };
+        for key in record.keys() {
+            if !columns.iter().any(|column| column.name == *key) {
+                let mut column = Column::new(key);
+                // Missing in all previous records.
+                column.missing = records > 0;
+                columns.push(column);
+            }
+        }
+        for column in columns.iter_mut() {
+            match record.get(&column.name) {
+                Some(value) => column.observe_json(value),
+                None => column.missing = true,
+            }
+        }
+        records += 1;
+    }
+
+    infer(columns, options)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Boolean,
+    Numeric,
+    Date,
+    Time,
+    DateTime,
+    Text,
+    /// JSON object, which has no attribute counterpart.
+    Object,
+}
+
+struct Column {
+    name: String,
+    kind: Option<Kind>,
+    missing: bool,
+    /// Number of present values.
+    count: usize,
+    distinct: BTreeSet<String>,
+    /// Statistics of items when the values are arrays.
+    items: Option<Box<Column>>,
+}
+
+impl Column {
+    fn new(name: &str) -> Self {
+        Column {
+            name: name.to_string(),
+            kind: None,
+            missing: false,
+            count: 0,
+            distinct: BTreeSet::new(),
+            items: None,
+        }
+    }
+
+    fn observe_text(&mut self, value: &str) {
+        let value = value.trim();
+        if value.is_empty() {
+            self.missing = true;
+            return;
+        }
+        let kind = if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+            Kind::Boolean
+        } else if value.parse::<f64>().is_ok_and(f64::is_finite) {
+            Kind::Numeric
+        } else {
+            string_kind(value)
+        };
+        self.observe(kind, value);
+    }
+
+    fn observe_json(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.missing = true,
+            Value::Bool(value) => self.observe(Kind::Boolean, &value.to_string()),
+            Value::Number(value) => self.observe(Kind::Numeric, &value.to_string()),
+            Value::String(value) => self.observe(string_kind(value), value),
+            Value::Array(values) => {
+                self.count += 1;
+                let items = self
+                    .items
+                    .get_or_insert_with(|| Box::new(Column::new(&self.name)));
+                for value in values {
+                    items.observe_json(value);
+                }
+            }
+            Value::Object(_) => self.observe(Kind::Object, ""),
+        }
+    }
+
+    fn observe(&mut self, kind: Kind, value: &str) {
+        self.count += 1;
+        self.kind = Some(match self.kind {
+            None => kind,
+            Some(observed) if observed == kind => kind,
+            Some(Kind::Date | Kind::DateTime) if matches!(kind, Kind::Date | Kind::DateTime) => {
+                Kind::DateTime
+            }
+            Some(Kind::Object) => Kind::Object,
+            Some(_) if kind == Kind::Object => Kind::Object,
+            Some(_) => Kind::Text,
+        });
+        self.distinct.insert(value.to_string());
+    }
+}
+
+fn infer(columns: Vec<Column>, options: &Options) -> Result<Inference, Error> {
+    if columns.is_empty() {
+        return Err(Error::InvalidData {
+            message: "no columns found".to_string(),
+        });
+    }
+    let mut warnings = vec![];
+    let mut attributes = IndexMap::new();
+    let mut conformances = IndexMap::new();
+    let mut formats = IndexMap::new();
+    let mut entry_codes = IndexMap::new();
+    for column in columns {
+        let pointer = format!("/{}", column.name.replace('~', "~0").replace('/', "~1"));
+        let name = identifier(&column.name);
+        if name != column.name {
+            warnings.push(Warning {
+                pointer: pointer.clone(),
+                message: format!("column `{}` renamed to `{name}`", column.name),
+            });
+        }
+        if attributes.contains_key(&name) {
+            warnings.push(Warning {
+                pointer,
+                message: format!("attribute `{name}` is already defined"),
+            });
+            continue;
+        }
+
+        let (attr_type, format) = match (&column.items, column.kind) {
+            (Some(items), None) => {
+                let (item_type, format) = value_type(items.kind, &pointer, &mut warnings);
+                (
+                    NestedAttrType::Array(Box::new(NestedAttrType::Value(item_type))),
+                    format,
+                )
+            }
+            (Some(_), Some(_)) => {
+                warnings.push(Warning {
+                    pointer: pointer.clone(),
+                    message: "arrays mixed with single values mapped to Text".to_string(),
+                });
+                (NestedAttrType::Value(AttributeType::Text), None)
+            }
+            (None, kind) => {
+                let (value_type, format) = value_type(kind, &pointer, &mut warnings);
+                if value_type == AttributeType::Text
+                    && kind == Some(Kind::Text)
+                    && column.distinct.len() <= options.entry_codes_limit
+                    && column.distinct.len() * 2 <= column.count
+                {
+                    entry_codes.insert(
+                        name.clone(),
+                        NestedValue::Array(
+                            column
+                                .distinct
+                                .iter()
+                                .cloned()
+                                .map(NestedValue::Value)
+                                .collect(),
+                        ),
+                    );
+                }
+                (NestedAttrType::Value(value_type), format)
+            }
+        };
+        attributes.insert(name.clone(), attr_type);
+        let conformance = if column.missing || column.count == 0 {
+            "O"
+        } else {
+            "M"
+        };
+        conformances.insert(name.clone(), NestedValue::Value(conformance.to_string()));
+        if let Some(format) = format {
+            formats.insert(name, NestedValue::Value(format.to_string()));
+        }
+    }
+
+    let mut oca_ast = OCAAst::new();
+    oca_ast.commands.push(ast::attributes(attributes));
+    for (overlay_type, attributes) in [
+        (
+            OverlayType::Conformance as fn(String) -> OverlayType,
+            conformances,
+        ),
+        (OverlayType::EntryCode, entry_codes),
+        (OverlayType::Format, formats),
+    ] {
+        if !attributes.is_empty() {
+            oca_ast.commands.push(ast::overlay(
+                overlay_type,
+                None,
+                Some(attributes),
+                IndexMap::new(),
+            ));
+        }
+    }
+    let ocafile = generate_from_ast(&oca_ast);
+
+    Ok(Inference {
+        oca_ast,
+        ocafile,
+        warnings,
+    })
+}
+
+fn value_type(
+    kind: Option<Kind>,
+    pointer: &str,
+    warnings: &mut Vec<Warning>,
+) -> (AttributeType, Option<&'static str>) {
+    match kind {
+        Some(Kind::Boolean) => (AttributeType::Boolean, None),
+        Some(Kind::Numeric) => (AttributeType::Numeric, None),
+        Some(Kind::Date) => (AttributeType::DateTime, Some(DATE_FORMAT)),
+        Some(Kind::Time) => (AttributeType::DateTime, Some(TIME_FORMAT)),
+        Some(Kind::DateTime) => (AttributeType::DateTime, None),
+        Some(Kind::Text) => (AttributeType::Text, None),
+        Some(Kind::Object) => {
+            warnings.push(Warning {
+                pointer: pointer.to_string(),
+                message: "objects mapped to Text".to_string(),
+            });
+            (AttributeType::Text, None)
+        }
+        None => {
+            warnings.push(Warning {
+                pointer: pointer.to_string(),
+                message: "no values, mapped to Text".to_string(),
+            });
+            (AttributeType::Text, None)
+        }
+    }
+}
+
+/// Recognize ISO 8601 dates and times, other strings are text.
+fn string_kind(value: &str) -> Kind {
+    let matches = |value: &str, pattern: &str| {
+        value.len() == pattern.len()
+            && value.chars().zip(pattern.chars()).all(|(c, p)| match p {
+                'd' => c.is_ascii_digit(),
+                p => c == p,
+            })
+    };
+    if matches(value, "dddd-dd-dd") {
+        Kind::Date
+    } else if matches(value, "dd:dd:dd") {
+        Kind::Time
+    } else if value
+        .get(..19)
+        .is_some_and(|prefix| matches(prefix, "dddd-dd-ddTdd:dd:dd"))
+    {
+        Kind::DateTime
+    } else {
+        Kind::Text
+    }
+}
+
+fn invalid_data(error: csv::Error) -> Error {
+    Error::InvalidData {
+        message: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oca_bundle_semantics::build::from_ast;
+
+    #[test]
+    fn infer_csv() {
+        let csv = "id,name,status,born,active,last login,score
+1,Alice,new,1990-01-02,true,2024-01-01T10:00:00Z,1.5
+2,Bob,new,1985-12-30,false,,2
+3,Carol,closed,2001-07-15,TRUE,2024-02-01T08:30:00+01:00,
+4,Dave,new,1979-03-04,false,2024-03-01T12:00:00Z,7
+";
+        let inference = infer_from_csv(csv, &Options::default()).unwrap();
+
+        assert_eq!(
+            inference.ocafile,
+            r#"ADD ATTRIBUTE id=Numeric name=Text status=Text born=DateTime active=Boolean last_login=DateTime score=Numeric
+ADD CONFORMANCE ATTRS id="M" name="M" status="M" born="M" active="M" last_login="O" score="O"
+ADD ENTRY_CODE ATTRS status=["closed", "new"]
+ADD FORMAT ATTRS born="YYYY-MM-DD"
+"#
+        );
+        assert_eq!(
+            inference.warnings,
+            vec![Warning {
+                pointer: "/last login".to_string(),
+                message: "column `last login` renamed to `last_login`".to_string(),
+            }]
+        );
+        assert!(from_ast(None, &inference.oca_ast).is_ok());
+    }
+
+    #[test]
+    fn infer_non_ascii_text() {
+        let csv = "city\naaaaaaaaaaaaaaaaaaé and more text\nZürich\n";
+        let inference = infer_from_csv(csv, &Options::default()).unwrap();
+        assert!(inference.ocafile.starts_with("ADD ATTRIBUTE city=Text\n"));
+    }
+
+    #[test]
+    fn infer_ndjson() {
+        let ndjson = r#"{"name": "Alice", "tags": ["a", "b"], "scores": [1, 2]}
+{"name": "Bob", "tags": [], "address": {"city": "Bern"}}
+
+{"name": null, "tags": ["c"], "scores": [3.5]}
+"#;
+        let inference =
+            infer_from_ndjson(ndjson, &Options::default().with_entry_codes_limit(0)).unwrap();
+
+        assert_eq!(
+            inference.ocafile,
+            r#"ADD ATTRIBUTE name=Text tags=Array[Text] scores=Array[Numeric] address=Text
+ADD CONFORMANCE ATTRS name="O" tags="M" scores="O" address="O"
+"#
+        );
+        assert_eq!(inference.warnings[0].message, "objects mapped to Text");
+    }
+
+    #[test]
+    fn invalid_ndjson() {
+        assert_eq!(
+            infer_from_ndjson("[1, 2]", &Options::default()).unwrap_err(),
+            Error::InvalidData {
+                message: "line 1: record is not an object".to_string(),
+            }
+        );
+    }
+}
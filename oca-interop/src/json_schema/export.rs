@@ -0,0 +1,327 @@
+//! Export of OCA Bundles to JSON Schema.
+//!
+//! Capture base attributes become object properties. Referenced bundles are
+//! taken from the given dependencies and placed under `$defs`, so the
+//! resulting schema is self-contained.
+use super::{Error, SCHEMA_DIALECT};
+use isolang::Language;
+use oca_ast_semantics::ast::{AttributeType, NestedAttrType, RefValue};
+use oca_bundle_semantics::state::{
+    attribute::Attribute,
+    dependencies::{Dependencies, Error as DependencyError},
+    entry_codes::EntryCodes,
+    oca::{OCABox, OCABundle},
+};
+use serde_json::{json, Map, Value};
+
+/// Convert OCA Bundle into JSON Schema.
+///
+/// Titles and descriptions are taken from the Label, Information and Meta
+/// overlays in `language`. When `language` is `None` they are omitted.
+pub fn to_json_schema(
+    bundle: &OCABundle,
+    dependencies: &[OCABundle],
+    language: Option<Language>,
+) -> Result<Value, Error> {
+    let mut exporter = Exporter {
+        dependencies: Dependencies::new(dependencies),
+        language,
+        defs: Map::new(),
+    };
+
+    let mut schema = Map::new();
+    schema.insert("$schema".to_string(), json!(SCHEMA_DIALECT));
+    if let Some(said) = &bundle.said {
+        schema.insert("$id".to_string(), json!(format!("urn:said:{said}")));
+    }
+    schema.extend(exporter.object_schema(bundle)?);
+    if !exporter.defs.is_empty() {
+        let mut defs: Vec<_> = exporter.defs.into_iter().collect();
+        defs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        schema.insert(
+            "$defs".to_string(),
+            Value::Object(defs.into_iter().collect()),
+        );
+    }
+
+    Ok(Value::Object(schema))
+}
+
+struct Exporter<'a> {
+    dependencies: Dependencies<'a>,
+    language: Option<Language>,
+    defs: Map<String, Value>,
+}
+
+impl Exporter<'_> {
+    fn object_schema(&mut self, bundle: &OCABundle) -> Result<Map<String, Value>, Error> {
+        let oca_box = OCABox::from(bundle.clone());
+        let mut schema = Map::new();
+
+        if let Some(meta) = self
+            .language
+            .and_then(|lang| oca_box.meta.as_ref()?.get(&lang).cloned())
+        {
+            if let Some(name) = meta.get("name") {
+                schema.insert("title".to_string(), json!(name));
+            }
+            if let Some(description) = meta.get("description") {
+                schema.insert("description".to_string(), json!(description));
+            }
+        }
+        schema.insert("type".to_string(), json!("object"));
+
+        let mut attributes: Vec<&Attribute> = oca_box.attributes.values().collect();
+        attributes.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut properties = Map::new();
+        let mut required = vec![];
+        for attribute in attributes {
+            properties.insert(
+                attribute.name.clone(),
+                Value::Object(self.property_schema(attribute)?),
+            );
+            if attribute.conformance.as_deref() == Some("M") {
+                required.push(json!(attribute.name));
+            }
+        }
+        schema.insert("properties".to_string(), Value::Object(properties));
+        if !required.is_empty() {
+            schema.insert("required".to_string(), Value::Array(required));
+        }
+
+        Ok(schema)
+    }
+
+    fn property_schema(&mut self, attribute: &Attribute) -> Result<Map<String, Value>, Error> {
+        let mut schema = Map::new();
+        if let Some(lang) = self.language {
+            if let Some(label) = attribute.labels.as_ref().and_then(|l| l.get(&lang)) {
+                schema.insert("title".to_string(), json!(label));
+            }
+            if let Some(info) = attribute.informations.as_ref().and_then(|i| i.get(&lang)) {
+                schema.insert("description".to_string(), json!(info));
+            }
+        }
+        if let Some(attr_type) = &attribute.attribute_type {
+            schema.extend(self.type_schema(attr_type, attribute)?);
+        }
+
+        Ok(schema)
+    }
+
+    fn type_schema(
+        &mut self,
+        attr_type: &NestedAttrType,
+        attribute: &Attribute,
+    ) -> Result<Map<String, Value>, Error> {
+        let mut schema = Map::new();
+        match attr_type {
+            NestedAttrType::Value(value_type) => {
+                schema.extend(value_schema(value_type, attribute.format.as_deref()));
+                if let Some(codes) = &attribute.entry_codes {
+                    if let Some(codes) = entry_codes_enum(codes) {
+                        schema.insert("enum".to_string(), codes);
+                    }
+                }
+            }
+            NestedAttrType::Array(item_type) => {
+                schema.insert("type".to_string(), json!("array"));
+                schema.insert(
+                    "items".to_string(),
+                    Value::Object(self.type_schema(item_type, attribute)?),
+                );
+                if let Some(cardinality) = &attribute.cardinality {
+                    schema.extend(cardinality_schema(cardinality));
+                }
+            }
+            NestedAttrType::Reference(RefValue::Said(said)) => {
+                let said = said.to_string();
+                self.add_def(&said)?;
+                schema.insert("$ref".to_string(), json!(format!("#/$defs/{said}")));
+            }
+            NestedAttrType::Reference(RefValue::Name(name)) => {
+                return Err(DependencyError::UnresolvedReference { name: name.clone() }.into())
+            }
+            NestedAttrType::Null => {
+                schema.insert("type".to_string(), json!("null"));
+            }
+        }
+
+        Ok(schema)
+    }
+
+    fn add_def(&mut self, said: &str) -> Result<(), Error> {
+        if self.defs.contains_key(said) {
+            return Ok(());
+        }
+        let dependency =
+            self.dependencies
+                .get(said)
+                .ok_or_else(|| DependencyError::MissingDependency {
+                    said: said.to_string(),
+                })?;
+        // Reserve the slot first so cyclic references terminate.
+        self.defs.insert(said.to_string(), Value::Null);
+        let def = self.object_schema(dependency)?;
+        self.defs.insert(said.to_string(), Value::Object(def));
+
+        Ok(())
+    }
+}
+
+fn value_schema(value_type: &AttributeType, format: Option<&str>) -> Map<String, Value> {
+    let mut schema = Map::new();
+    match value_type {
+        AttributeType::Text => {
+            schema.insert("type".to_string(), json!("string"));
+            if let Some(pattern) = format {
+                schema.insert("pattern".to_string(), json!(pattern));
+            }
+        }
+        AttributeType::Numeric => {
+            schema.insert("type".to_string(), json!("number"));
+        }
+        AttributeType::Boolean => {
+            schema.insert("type".to_string(), json!("boolean"));
+        }
+        AttributeType::DateTime => {
+            schema.insert("type".to_string(), json!("string"));
+            if let Some(format) = date_time_format(format) {
+                schema.insert("format".to_string(), json!(format));
+            }
+        }
+        AttributeType::Binary => {
+            schema.insert("type".to_string(), json!("string"));
+            schema.insert("contentEncoding".to_string(), json!("base64"));
+            if let Some(media_type) = format {
+                schema.insert("contentMediaType".to_string(), json!(media_type));
+            }
+        }
+    }
+
+    schema
+}
+
+/// Map OCA date time format to JSON Schema `format`. Formats without
+/// a JSON Schema counterpart are left unconstrained.
+fn date_time_format(format: Option<&str>) -> Option<&'static str> {
+    match format {
+        None => Some("date-time"),
+        Some("YYYY-MM-DD") => Some("date"),
+        Some("hh:mm:ss") | Some("HH:mm:ss") => Some("time"),
+        Some(f) if f.starts_with("YYYY-MM-DDThh:mm:ss") || f.starts_with("YYYY-MM-DDTHH:mm:ss") => {
+            Some("date-time")
+        }
+        Some(_) => None,
+    }
+}
+
+fn entry_codes_enum(codes: &EntryCodes) -> Option<Value> {
+    match codes {
+        EntryCodes::Sai(_) => None,
+        EntryCodes::Array(codes) => Some(json!(codes)),
+        EntryCodes::Object(groups) => {
+            let codes: Vec<&String> = groups.values().flatten().collect();
+            Some(json!(codes))
+        }
+    }
+}
+
+/// Cardinality is either exact (`n`) or a range (`min-max`) with optional
+/// bounds.
+fn cardinality_schema(cardinality: &str) -> Map<String, Value> {
+    let mut schema = Map::new();
+    let (min, max) = match cardinality.split_once('-') {
+        Some((min, max)) => (min.trim(), max.trim()),
+        None => (cardinality.trim(), cardinality.trim()),
+    };
+    if let Ok(min) = min.parse::<u64>() {
+        schema.insert("minItems".to_string(), json!(min));
+    }
+    if let Ok(max) = max.parse::<u64>() {
+        schema.insert("maxItems".to_string(), json!(max));
+    }
+
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::build;
+
+    #[test]
+    fn export_json_schema() {
+        let address = build(
+            r#"ADD ATTRIBUTE street=Text city=Text
+ADD CONFORMANCE ATTRS street="M"
+"#,
+        );
+        let address_said = address.said.clone().unwrap();
+        let person = build(&format!(
+            r#"ADD ATTRIBUTE name=Text birthdate=DateTime tags=Array[Text] address=refs:{address_said} photo=Binary
+ADD META en PROPS name="Person" description="Person record"
+ADD LABEL en ATTRS name="Full name"
+ADD INFORMATION en ATTRS name="Name as in passport"
+ADD CONFORMANCE ATTRS name="M" address="O"
+ADD ENTRY_CODE ATTRS tags=["a", "b"]
+ADD CARDINALITY ATTRS tags="1-3"
+ADD FORMAT ATTRS name="^[A-Z]" birthdate="YYYY-MM-DD" photo="image/png"
+"#
+        ));
+
+        let schema = to_json_schema(&person, &[address], Some(Language::Eng)).unwrap();
+
+        assert_eq!(schema["$schema"], SCHEMA_DIALECT);
+        assert_eq!(schema["title"], "Person");
+        assert_eq!(schema["description"], "Person record");
+        assert_eq!(schema["required"], json!(["name"]));
+        let properties = &schema["properties"];
+        assert_eq!(
+            properties["name"],
+            json!({
+                "title": "Full name",
+                "description": "Name as in passport",
+                "type": "string",
+                "pattern": "^[A-Z]"
+            })
+        );
+        assert_eq!(
+            properties["birthdate"],
+            json!({"type": "string", "format": "date"})
+        );
+        assert_eq!(
+            properties["tags"],
+            json!({
+                "type": "array",
+                "items": {"type": "string", "enum": ["a", "b"]},
+                "minItems": 1,
+                "maxItems": 3
+            })
+        );
+        assert_eq!(properties["photo"]["contentMediaType"], "image/png");
+        assert_eq!(
+            properties["address"]["$ref"],
+            format!("#/$defs/{address_said}")
+        );
+        let def = &schema["$defs"][address_said.to_string()];
+        assert_eq!(def["required"], json!(["street"]));
+        assert_eq!(def["properties"]["city"], json!({"type": "string"}));
+    }
+
+    #[test]
+    fn missing_dependency() {
+        let address = build("ADD ATTRIBUTE street=Text\n");
+        let address_said = address.said.clone().unwrap();
+        let person = build(&format!("ADD ATTRIBUTE address=refs:{address_said}\n"));
+
+        let result = to_json_schema(&person, &[], None);
+        assert_eq!(
+            result,
+            Err(Error::Dependency(DependencyError::MissingDependency {
+                said: address_said.to_string()
+            }))
+        );
+    }
+}
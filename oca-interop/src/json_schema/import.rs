@@ -0,0 +1,635 @@
+//! Import of JSON Schema documents as OCA Bundles.
+//!
+//! Every object schema becomes a separate bundle. Nested objects are linked
+//! with `refn:` references, so the resulting OCAFILEs have to be built in
+//! order with local references enabled.
+use crate::{
+    ast::{self, identifier},
+    Error, Warning,
+};
+use indexmap::IndexMap;
+use isolang::Language;
+use oca_ast_semantics::ast::{
+    AttributeType, NestedAttrType, NestedValue, OCAAst, OverlayType, RefValue,
+};
+use oca_file_semantics::ocafile::generate_from_ast;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// Keywords without OCA counterpart which are dropped silently.
+const IGNORED_KEYWORDS: &[&str] = &["$schema", "$id", "$comment", "$defs", "definitions"];
+
+const OBJECT_KEYWORDS: &[&str] = &["type", "title", "description", "properties", "required"];
+
+const PROPERTY_KEYWORDS: &[&str] = &[
+    "type",
+    "title",
+    "description",
+    "enum",
+    "const",
+    "format",
+    "pattern",
+    "items",
+    "minItems",
+    "maxItems",
+    "contentEncoding",
+    "contentMediaType",
+    "$ref",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedBundle {
+    /// Name under which the bundle is referenced with `refn:`.
+    pub name: String,
+    pub oca_ast: OCAAst,
+    pub ocafile: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    /// Bundles ordered so that every bundle comes after the bundles it
+    /// references. The last one is the root schema.
+    pub bundles: Vec<ImportedBundle>,
+    pub warnings: Vec<Warning>,
+}
+
+impl Import {
+    pub fn root(&self) -> &ImportedBundle {
+        self.bundles.last().expect("import has root bundle")
+    }
+}
+
+/// Convert JSON Schema into OCA ASTs.
+///
+/// `name` is used for the root bundle, nested bundles are named after the
+/// property or `$defs` entry they come from. Titles and descriptions are
+/// stored as labels and information in `language`.
+pub fn from_json_schema(schema: &Value, name: &str, language: Language) -> Result<Import, Error> {
+    let lang = language
+        .to_639_1()
+        .ok_or_else(|| Error::InvalidSchema {
+            message: format!("language {} has no two letter code", language.to_639_3()),
+        })?
+        .to_string();
+    if !schema.is_object() {
+        return Err(Error::InvalidSchema {
+            message: "root schema is not an object".to_string(),
+        });
+    }
+    if !is_object_schema(schema) {
+        return Err(Error::InvalidSchema {
+            message: "root schema doesn't describe an object".to_string(),
+        });
+    }
+
+    let mut importer = Importer {
+        root: schema,
+        lang,
+        bundles: vec![],
+        names: HashSet::new(),
+        refs: HashMap::new(),
+        // The root schema can't reference itself either.
+        resolving: HashSet::from([String::new()]),
+        warnings: vec![],
+    };
+    importer.object(schema, name, "")?;
+
+    Ok(Import {
+        bundles: importer.bundles,
+        warnings: importer.warnings,
+    })
+}
+
+#[derive(Default)]
+struct Property {
+    attr_type: Option<NestedAttrType>,
+    format: Option<String>,
+    entry_codes: Option<Vec<String>>,
+    cardinality: Option<String>,
+}
+
+struct Importer<'a> {
+    root: &'a Value,
+    lang: String,
+    bundles: Vec<ImportedBundle>,
+    names: HashSet<String>,
+    /// Bundle names of already imported `$ref` object targets.
+    refs: HashMap<String, String>,
+    /// Pointers of `$ref` targets which are being resolved.
+    resolving: HashSet<String>,
+    warnings: Vec<Warning>,
+}
+
+impl Importer<'_> {
+    fn warn(&mut self, pointer: &str, message: String) {
+        self.warnings.push(Warning {
+            pointer: pointer.to_string(),
+            message,
+        });
+    }
+
+    fn warn_unsupported(&mut self, schema: &Value, pointer: &str, supported: &[&str]) {
+        if let Value::Object(schema) = schema {
+            for keyword in schema.keys() {
+                if !supported.contains(&keyword.as_str())
+                    && !IGNORED_KEYWORDS.contains(&keyword.as_str())
+                {
+                    self.warn(pointer, format!("keyword `{keyword}` is not supported"));
+                }
+            }
+        }
+    }
+
+    /// Import object schema as a bundle and return its name.
+    fn object(&mut self, schema: &Value, name_hint: &str, pointer: &str) -> Result<String, Error> {
+        let name = self.unique_name(name_hint);
+        self.warn_unsupported(schema, pointer, OBJECT_KEYWORDS);
+
+        let empty = Map::new();
+        let properties = schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .unwrap_or(&empty);
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        for key in &required {
+            if !properties.contains_key(*key) {
+                self.warn(
+                    &format!("{pointer}/required"),
+                    format!("required property `{key}` is not defined"),
+                );
+            }
+        }
+
+        let mut attributes = IndexMap::new();
+        let mut labels = IndexMap::new();
+        let mut informations = IndexMap::new();
+        let mut conformances = IndexMap::new();
+        let mut entry_codes = IndexMap::new();
+        let mut formats = IndexMap::new();
+        let mut cardinalities = IndexMap::new();
+
+        for (key, property_schema) in properties {
+            let property_pointer = format!("{pointer}/properties/{}", escape_pointer(key));
+            let attr_name = identifier(key);
+            if attr_name != *key {
+                self.warn(
+                    &property_pointer,
+                    format!("property `{key}` renamed to `{attr_name}`"),
+                );
+            }
+            if attributes.contains_key(&attr_name) {
+                self.warn(
+                    &property_pointer,
+                    format!("attribute `{attr_name}` is already defined"),
+                );
+                continue;
+            }
+            let hint = format!("{name}_{attr_name}");
+            let property = self.property(property_schema, &hint, &property_pointer)?;
+            let Some(attr_type) = property.attr_type else {
+                continue;
+            };
+
+            attributes.insert(attr_name.clone(), attr_type);
+            if let Some(title) = property_schema.get("title").and_then(Value::as_str) {
+                labels.insert(attr_name.clone(), NestedValue::Value(title.to_string()));
+            }
+            if let Some(description) = property_schema.get("description").and_then(Value::as_str) {
+                informations.insert(
+                    attr_name.clone(),
+                    NestedValue::Value(description.to_string()),
+                );
+            }
+            let conformance = if required.contains(&key.as_str()) {
+                "M"
+            } else {
+                "O"
+            };
+            conformances.insert(
+                attr_name.clone(),
+                NestedValue::Value(conformance.to_string()),
+            );
+            if let Some(codes) = property.entry_codes {
+                entry_codes.insert(
+                    attr_name.clone(),
+                    NestedValue::Array(codes.into_iter().map(NestedValue::Value).collect()),
+                );
+            }
+            if let Some(format) = property.format {
+                formats.insert(attr_name.clone(), NestedValue::Value(format));
+            }
+            if let Some(cardinality) = property.cardinality {
+                cardinalities.insert(attr_name.clone(), NestedValue::Value(cardinality));
+            }
+        }
+        if attributes.is_empty() {
+            self.warn(pointer, "object has no properties".to_string());
+        }
+
+        let mut oca_ast = OCAAst::new();
+        oca_ast.meta.insert("name".to_string(), name.clone());
+        oca_ast.commands.push(ast::attributes(attributes));
+
+        let mut meta = IndexMap::new();
+        for (keyword, meta_key) in [("title", "name"), ("description", "description")] {
+            if let Some(value) = schema.get(keyword).and_then(Value::as_str) {
+                meta.insert(meta_key.to_string(), NestedValue::Value(value.to_string()));
+            }
+        }
+        if !meta.is_empty() {
+            oca_ast.commands.push(ast::overlay(
+                OverlayType::Meta,
+                Some(&self.lang),
+                None,
+                meta,
+            ));
+        }
+        for (overlay_type, attributes, localized) in [
+            (
+                OverlayType::Label as fn(String) -> OverlayType,
+                labels,
+                true,
+            ),
+            (OverlayType::Information, informations, true),
+            (OverlayType::Conformance, conformances, false),
+            (OverlayType::EntryCode, entry_codes, false),
+            (OverlayType::Format, formats, false),
+            (OverlayType::Cardinality, cardinalities, false),
+        ] {
+            if attributes.is_empty() {
+                continue;
+            }
+            let lang = localized.then_some(self.lang.as_str());
+            oca_ast.commands.push(ast::overlay(
+                overlay_type,
+                lang,
+                Some(attributes),
+                IndexMap::new(),
+            ));
+        }
+
+        let ocafile = generate_from_ast(&oca_ast);
+        self.bundles.push(ImportedBundle {
+            name: name.clone(),
+            oca_ast,
+            ocafile,
+        });
+
+        Ok(name)
+    }
+
+    fn property(
+        &mut self,
+        schema: &Value,
+        name_hint: &str,
+        pointer: &str,
+    ) -> Result<Property, Error> {
+        if let Some(reference) = schema.get("$ref") {
+            return self.reference(reference, name_hint, pointer);
+        }
+        if !schema.is_object() {
+            self.warn(pointer, "schema is not an object".to_string());
+            return Ok(Property::default());
+        }
+        if is_object_schema(schema) {
+            let name = self.object(schema, name_hint, pointer)?;
+            return Ok(Property {
+                attr_type: Some(NestedAttrType::Reference(RefValue::Name(name))),
+                ..Default::default()
+            });
+        }
+        self.warn_unsupported(schema, pointer, PROPERTY_KEYWORDS);
+
+        let mut property = Property {
+            entry_codes: self.entry_codes(schema, pointer),
+            ..Default::default()
+        };
+        let Some(json_type) = self.json_type(schema, pointer) else {
+            return Ok(property);
+        };
+        let attr_type = match json_type.as_str() {
+            "string" => {
+                let (attr_type, format) = self.string_type(schema, pointer);
+                property.format = format;
+                attr_type
+            }
+            "number" => AttributeType::Numeric,
+            "integer" => {
+                self.warn(pointer, "integer mapped to Numeric".to_string());
+                AttributeType::Numeric
+            }
+            "boolean" => AttributeType::Boolean,
+            "array" => {
+                let items_pointer = format!("{pointer}/items");
+                let items = match schema.get("items") {
+                    Some(items) => self.property(items, name_hint, &items_pointer)?,
+                    None => {
+                        self.warn(pointer, "array without items mapped to Text".to_string());
+                        Property {
+                            attr_type: Some(NestedAttrType::Value(AttributeType::Text)),
+                            ..Default::default()
+                        }
+                    }
+                };
+                let Some(item_type) = items.attr_type else {
+                    return Ok(property);
+                };
+                property.attr_type = Some(NestedAttrType::Array(Box::new(item_type)));
+                property.format = items.format;
+                property.entry_codes = property.entry_codes.or(items.entry_codes);
+                property.cardinality = cardinality(schema);
+                return Ok(property);
+            }
+            "null" => {
+                self.warn(pointer, "null type is not supported".to_string());
+                return Ok(property);
+            }
+            other => {
+                self.warn(pointer, format!("unknown type `{other}` mapped to Text"));
+                AttributeType::Text
+            }
+        };
+        property.attr_type = Some(NestedAttrType::Value(attr_type));
+
+        Ok(property)
+    }
+
+    fn reference(
+        &mut self,
+        reference: &Value,
+        name_hint: &str,
+        pointer: &str,
+    ) -> Result<Property, Error> {
+        let target = reference
+            .as_str()
+            .and_then(|reference| reference.strip_prefix('#'))
+            .and_then(|target_pointer| {
+                self.root
+                    .pointer(target_pointer)
+                    .map(|target| (target_pointer.to_string(), target))
+            });
+        let Some((target_pointer, target)) = target else {
+            self.warn(pointer, format!("reference {reference} can't be resolved"));
+            return Ok(Property::default());
+        };
+        if let Some(name) = self.refs.get(&target_pointer) {
+            return Ok(Property {
+                attr_type: Some(NestedAttrType::Reference(RefValue::Name(name.clone()))),
+                ..Default::default()
+            });
+        }
+        if !self.resolving.insert(target_pointer.clone()) {
+            return Err(Error::InvalidSchema {
+                message: format!("{pointer}: cyclic reference {reference} is not supported"),
+            });
+        }
+
+        let property = if is_object_schema(target) {
+            let def_name = target_pointer.rsplit('/').next().unwrap_or(name_hint);
+            self.object(target, def_name, &target_pointer).map(|name| {
+                self.refs.insert(target_pointer.clone(), name.clone());
+                Property {
+                    attr_type: Some(NestedAttrType::Reference(RefValue::Name(name))),
+                    ..Default::default()
+                }
+            })
+        } else {
+            self.property(target, name_hint, &target_pointer)
+        };
+        self.resolving.remove(&target_pointer);
+
+        property
+    }
+
+    /// Single, non null JSON type of the schema.
+    fn json_type(&mut self, schema: &Value, pointer: &str) -> Option<String> {
+        match schema.get("type") {
+            Some(Value::String(json_type)) => Some(json_type.clone()),
+            Some(Value::Array(types)) => {
+                let types: Vec<&str> = types
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .filter(|json_type| *json_type != "null")
+                    .collect();
+                match types.as_slice() {
+                    [json_type] => Some(json_type.to_string()),
+                    [] => {
+                        self.warn(pointer, "null type is not supported".to_string());
+                        None
+                    }
+                    _ => {
+                        self.warn(pointer, format!("union of types {types:?} mapped to Text"));
+                        Some("string".to_string())
+                    }
+                }
+            }
+            _ if schema.get("items").is_some() => Some("array".to_string()),
+            _ if schema.get("enum").is_some() || schema.get("const").is_some() => {
+                Some("string".to_string())
+            }
+            _ => {
+                self.warn(pointer, "missing type mapped to Text".to_string());
+                Some("string".to_string())
+            }
+        }
+    }
+
+    fn string_type(&mut self, schema: &Value, pointer: &str) -> (AttributeType, Option<String>) {
+        if let Some(encoding) = schema.get("contentEncoding").and_then(Value::as_str) {
+            if encoding != "base64" {
+                self.warn(
+                    pointer,
+                    format!("content encoding `{encoding}` is not supported"),
+                );
+            }
+            let media_type = schema
+                .get("contentMediaType")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            return (AttributeType::Binary, media_type);
+        }
+        let pattern = schema
+            .get("pattern")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        match schema.get("format").and_then(Value::as_str) {
+            Some("date-time") => (AttributeType::DateTime, None),
+            Some("date") => (AttributeType::DateTime, Some("YYYY-MM-DD".to_string())),
+            Some("time") => (AttributeType::DateTime, Some("hh:mm:ss".to_string())),
+            Some(format) => {
+                self.warn(pointer, format!("format `{format}` is not supported"));
+                (AttributeType::Text, pattern)
+            }
+            None => (AttributeType::Text, pattern),
+        }
+    }
+
+    fn entry_codes(&mut self, schema: &Value, pointer: &str) -> Option<Vec<String>> {
+        let values = match (schema.get("enum"), schema.get("const")) {
+            (Some(Value::Array(values)), _) => values.clone(),
+            (_, Some(value)) => vec![value.clone()],
+            _ => return None,
+        };
+        let mut codes = vec![];
+        for value in values {
+            match value {
+                Value::String(code) => codes.push(code),
+                Value::Number(code) => codes.push(code.to_string()),
+                Value::Bool(code) => codes.push(code.to_string()),
+                other => self.warn(pointer, format!("enum value {other} is not supported")),
+            }
+        }
+
+        Some(codes)
+    }
+
+    fn unique_name(&mut self, hint: &str) -> String {
+        let base = identifier(hint);
+        let mut name = base.clone();
+        let mut i = 1;
+        while self.names.contains(&name) {
+            i += 1;
+            name = format!("{base}_{i}");
+        }
+        self.names.insert(name.clone());
+        name
+    }
+}
+
+fn is_object_schema(schema: &Value) -> bool {
+    match schema.get("type") {
+        Some(Value::String(json_type)) => json_type == "object",
+        Some(_) => false,
+        None => schema.get("properties").is_some(),
+    }
+}
+
+fn cardinality(schema: &Value) -> Option<String> {
+    let min = schema.get("minItems").and_then(Value::as_u64);
+    let max = schema.get("maxItems").and_then(Value::as_u64);
+    match (min, max) {
+        (None, None) => None,
+        (Some(min), Some(max)) if min == max => Some(min.to_string()),
+        (min, max) => Some(format!(
+            "{}-{}",
+            min.map(|v| v.to_string()).unwrap_or_default(),
+            max.map(|v| v.to_string()).unwrap_or_default()
+        )),
+    }
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oca_bundle_semantics::build::from_ast;
+    use oca_file_semantics::ocafile::parse_from_string;
+    use serde_json::json;
+
+    #[test]
+    fn import_json_schema() {
+        let schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "Person",
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "title": "Full name", "pattern": "^[A-Z]"},
+                "birthdate": {"type": "string", "format": "date"},
+                "gender": {"enum": ["F", "M"], "description": "Gender"},
+                "tags": {"type": "array", "items": {"type": "string"}, "minItems": 1},
+                "address": {
+                    "type": "object",
+                    "properties": {"street": {"type": "string"}},
+                    "required": ["street"]
+                },
+                "work": {"$ref": "#/$defs/address"},
+                "age": {"type": "integer", "minimum": 0}
+            },
+            "required": ["name"],
+            "$defs": {
+                "address": {"type": "object", "properties": {"city": {"type": "string"}}}
+            }
+        });
+
+        let import = from_json_schema(&schema, "person", Language::Eng).unwrap();
+
+        let names: Vec<&str> = import.bundles.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["person_address", "address", "person"]);
+        assert_eq!(
+            import.bundles[0].ocafile,
+            "-- name=person_address\n\
+             ADD ATTRIBUTE street=Text\n\
+             ADD CONFORMANCE ATTRS street=\"M\"\n"
+        );
+        assert_eq!(
+            import.root().ocafile,
+            "-- name=person\n\
+             ADD ATTRIBUTE name=Text birthdate=DateTime gender=Text tags=Array[Text] \
+             address=refn:person_address work=refn:address age=Numeric\n\
+             ADD META en PROPS name=\"Person\"\n\
+             ADD LABEL en ATTRS name=\"Full name\"\n\
+             ADD INFORMATION en ATTRS gender=\"Gender\"\n\
+             ADD CONFORMANCE ATTRS name=\"M\" birthdate=\"O\" gender=\"O\" tags=\"O\" \
+             address=\"O\" work=\"O\" age=\"O\"\n\
+             ADD ENTRY_CODE ATTRS gender=[\"F\", \"M\"]\n\
+             ADD FORMAT ATTRS name=\"^[A-Z]\" birthdate=\"YYYY-MM-DD\"\n\
+             ADD CARDINALITY ATTRS tags=\"1-\"\n"
+        );
+        assert_eq!(
+            import
+                .warnings
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "/properties/age: keyword `minimum` is not supported",
+                "/properties/age: integer mapped to Numeric",
+            ]
+        );
+
+        let ast = parse_from_string(import.bundles[0].ocafile.clone()).unwrap();
+        assert_eq!(ast.commands, import.bundles[0].oca_ast.commands);
+        assert!(from_ast(None, &ast).is_ok());
+    }
+
+    #[test]
+    fn reject_cyclic_reference() {
+        for schema in [
+            json!({
+                "properties": {"a": {"$ref": "#/$defs/a"}},
+                "$defs": {"a": {"$ref": "#/$defs/a"}}
+            }),
+            json!({
+                "properties": {"a": {"$ref": "#/$defs/a"}},
+                "$defs": {"a": {"type": "array", "items": {"$ref": "#/$defs/a"}}}
+            }),
+            json!({
+                "properties": {"node": {"$ref": "#/$defs/node"}},
+                "$defs": {"node": {
+                    "type": "object",
+                    "properties": {"child": {"$ref": "#/$defs/node"}}
+                }}
+            }),
+            json!({"type": "object", "properties": {"parent": {"$ref": "#"}}}),
+        ] {
+            let result = from_json_schema(&schema, "root", Language::Eng);
+            assert!(
+                matches!(&result, Err(Error::InvalidSchema { message }) if message.contains("cyclic reference")),
+                "{result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn reject_non_object_schema() {
+        let result = from_json_schema(&json!({"type": "string"}), "name", Language::Eng);
+        assert!(matches!(result, Err(Error::InvalidSchema { .. })));
+    }
+}
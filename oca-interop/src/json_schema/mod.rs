@@ -0,0 +1,9 @@
+//! Conversions between OCA Bundles and JSON Schema (draft 2020-12).
+mod export;
+mod import;
+
+pub use crate::{Error, Warning};
+pub use export::to_json_schema;
+pub use import::{from_json_schema, Import, ImportedBundle};
+
+pub const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
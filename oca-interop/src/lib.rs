@@ -0,0 +1,26 @@
+mod ast;
+pub mod avro;
+mod error;
+#[cfg(test)]
+mod fixtures;
+pub mod frictionless;
+pub mod inference;
+pub mod json_schema;
+pub mod linked_data;
+pub mod protobuf;
+pub mod spreadsheet;
+pub mod sql;
+pub mod translation;
+mod warning;
+
+pub use error::Error;
+use isolang::Language;
+pub use warning::Warning;
+
+/// Normalized ISO 639-1 code, as used by OCAFILE, of ISO 639-1 or 639-3
+/// code.
+pub(crate) fn parse_lang(code: &str) -> Option<String> {
+    let code = code.to_lowercase();
+    let lang = Language::from_639_1(&code).or_else(|| Language::from_639_3(&code))?;
+    lang.to_639_1().map(str::to_string)
+}
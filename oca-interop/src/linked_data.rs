@@ -0,0 +1,382 @@
+//! Export of OCA Bundles as linked data: JSON-LD context and SHACL shapes.
+//!
+//! Attributes are mapped to the IRIs from the Attribute Framing overlay when
+//! they are framed with `skos:exactMatch` or `skos:closeMatch`. Other
+//! attributes get IRIs in the bundle vocabulary `urn:said:<said>#`.
+//! Referenced bundles are resolved from the given dependencies, so both
+//! outputs are self-contained and don't need network access.
+use crate::Error;
+use oca_ast_semantics::ast::{AttributeType, NestedAttrType};
+use oca_bundle_semantics::state::{
+    attribute::Attribute,
+    dependencies::{said_of, Dependencies},
+    entry_codes::EntryCodes,
+    oca::{OCABox, OCABundle},
+};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+const SHACL: &str = "http://www.w3.org/ns/shacl#";
+
+/// Framing predicates which make the framed term usable as attribute IRI,
+/// in order of preference.
+const MATCH_PREDICATES: &[&str] = &["skos:exactMatch", "skos:closeMatch"];
+
+/// Generate JSON-LD 1.1 context for records of the bundle.
+///
+/// Referenced bundles get scoped contexts, so nested records expand as well.
+pub fn to_json_ld_context(bundle: &OCABundle, dependencies: &[OCABundle]) -> Result<Value, Error> {
+    let dependencies = Dependencies::new(dependencies);
+    let mut context = Map::new();
+    context.insert("@version".to_string(), json!(1.1));
+    context.insert("xsd".to_string(), json!(XSD));
+    context.extend(term_definitions(bundle, &dependencies, &mut vec![])?);
+
+    Ok(json!({ "@context": context }))
+}
+
+fn term_definitions(
+    bundle: &OCABundle,
+    dependencies: &Dependencies,
+    visited: &mut Vec<String>,
+) -> Result<Map<String, Value>, Error> {
+    let said = said_of(bundle)?;
+    visited.push(said.clone());
+
+    let mut context = Map::new();
+    context.insert("@vocab".to_string(), json!(vocabulary(&said)));
+    for attribute in &sorted_attributes(bundle) {
+        let mut term = Map::new();
+        if let Some(iri) = attribute_iri(attribute) {
+            term.insert("@id".to_string(), json!(iri));
+        }
+        match innermost_type(attribute) {
+            Some(NestedAttrType::Value(attr_type)) => {
+                if let Some(datatype) = xsd_datatype(attr_type, attribute.format.as_deref()) {
+                    term.insert("@type".to_string(), json!(format!("xsd:{datatype}")));
+                }
+            }
+            Some(NestedAttrType::Reference(reference)) => {
+                let dependency = dependencies.resolve(reference)?;
+                let dependency_said = said_of(dependency)?;
+                // Recursive references can't be expressed with scoped contexts.
+                if !visited.contains(&dependency_said) {
+                    let scoped = term_definitions(dependency, dependencies, visited)?;
+                    term.insert("@context".to_string(), Value::Object(scoped));
+                }
+            }
+            _ => {}
+        }
+        if !term.is_empty() {
+            context.insert(attribute.name.clone(), Value::Object(term));
+        }
+    }
+    visited.pop();
+
+    Ok(context)
+}
+
+/// Generate SHACL node shapes in Turtle.
+///
+/// The bundle shape targets the `urn:said:<said>` class. Shapes of referenced
+/// bundles follow it and are linked with `sh:node`.
+pub fn to_shacl(bundle: &OCABundle, dependencies: &[OCABundle]) -> Result<String, Error> {
+    let dependencies = Dependencies::new(dependencies);
+    let mut shapes = BTreeMap::new();
+    let said = said_of(bundle)?;
+    let mut pending = vec![bundle];
+    while let Some(bundle) = pending.pop() {
+        let bundle_said = said_of(bundle)?;
+        if shapes.contains_key(&bundle_said) {
+            continue;
+        }
+        let (shape, references) = node_shape(&bundle_said, bundle, &dependencies)?;
+        shapes.insert(bundle_said, shape);
+        pending.extend(references);
+    }
+
+    let mut turtle = format!("@prefix sh: <{SHACL}> .\n@prefix xsd: <{XSD}> .\n");
+    // Bundle shape first, then referenced ones.
+    if let Some(shape) = shapes.remove(&said) {
+        turtle.push('\n');
+        turtle.push_str(&shape);
+    }
+    for shape in shapes.values() {
+        turtle.push('\n');
+        turtle.push_str(shape);
+    }
+
+    Ok(turtle)
+}
+
+fn node_shape<'a>(
+    said: &str,
+    bundle: &OCABundle,
+    dependencies: &Dependencies<'a>,
+) -> Result<(String, Vec<&'a OCABundle>), Error> {
+    let mut references = vec![];
+    let mut shape = format!(
+        "<{}>\n    a sh:NodeShape ;\n    sh:targetClass <urn:said:{said}> ;\n",
+        shape_iri(said)
+    );
+    for attribute in &sorted_attributes(bundle) {
+        let path =
+            attribute_iri(attribute).unwrap_or(format!("{}{}", vocabulary(said), attribute.name));
+        let mut constraints = vec![format!("sh:path <{path}>")];
+
+        let is_array = matches!(attribute.attribute_type, Some(NestedAttrType::Array(_)));
+        let mandatory = attribute.conformance.as_deref() == Some("M");
+        let (mut min, mut max) = (mandatory.then_some(1), (!is_array).then_some(1));
+        if let (true, Some(cardinality)) = (is_array, &attribute.cardinality) {
+            let (cardinality_min, cardinality_max) = parse_cardinality(cardinality);
+            min = cardinality_min.or(min);
+            max = cardinality_max;
+        }
+
+        match innermost_type(attribute) {
+            Some(NestedAttrType::Value(attr_type)) => {
+                if let Some(datatype) = xsd_datatype(attr_type, attribute.format.as_deref()) {
+                    constraints.push(format!("sh:datatype xsd:{datatype}"));
+                } else {
+                    constraints.push("sh:nodeKind sh:Literal".to_string());
+                }
+                if let Some(values) = entry_codes(attribute, attr_type) {
+                    constraints.push(format!("sh:in ( {} )", values.join(" ")));
+                }
+                if let (AttributeType::Text, Some(pattern)) = (attr_type, &attribute.format) {
+                    constraints.push(format!("sh:pattern {}", literal(pattern)));
+                }
+            }
+            Some(NestedAttrType::Reference(reference)) => {
+                let dependency = dependencies.resolve(reference)?;
+                let dependency_said = said_of(dependency)?;
+                constraints.push(format!("sh:node <{}>", shape_iri(&dependency_said)));
+                references.push(dependency);
+            }
+            _ => {}
+        }
+        if let Some(min) = min {
+            constraints.push(format!("sh:minCount {min}"));
+        }
+        if let Some(max) = max {
+            constraints.push(format!("sh:maxCount {max}"));
+        }
+
+        shape.push_str("    sh:property [\n");
+        for constraint in constraints {
+            shape.push_str(&format!("        {constraint} ;\n"));
+        }
+        shape.push_str("    ] ;\n");
+    }
+    shape.push_str("    .\n");
+
+    Ok((shape, references))
+}
+
+fn vocabulary(said: &str) -> String {
+    format!("urn:said:{said}#")
+}
+
+fn shape_iri(said: &str) -> String {
+    format!("urn:said:{said}#Shape")
+}
+
+fn sorted_attributes(bundle: &OCABundle) -> Vec<Attribute> {
+    let oca_box = OCABox::from(bundle.clone());
+    let mut attributes: Vec<Attribute> = oca_box.attributes.into_values().collect();
+    attributes.sort_by(|a, b| a.name.cmp(&b.name));
+    attributes
+}
+
+/// Term framed with the most preferred matching predicate. Ties are broken
+/// by the term IRI, to keep the output stable.
+fn attribute_iri(attribute: &Attribute) -> Option<String> {
+    attribute
+        .framings
+        .iter()
+        .flat_map(|framings| framings.values())
+        .flat_map(|framing| framing.iter())
+        .filter_map(|(iri, scope)| {
+            MATCH_PREDICATES
+                .iter()
+                .position(|predicate| *predicate == scope.predicate_id)
+                .map(|rank| (rank, iri))
+        })
+        .min()
+        .map(|(_, iri)| iri.clone())
+}
+
+/// Type of the attribute value, or of array elements for arrays.
+fn innermost_type(attribute: &Attribute) -> Option<&NestedAttrType> {
+    let mut attr_type = attribute.attribute_type.as_ref()?;
+    while let NestedAttrType::Array(item_type) = attr_type {
+        attr_type = item_type;
+    }
+    Some(attr_type)
+}
+
+fn xsd_datatype(attr_type: &AttributeType, format: Option<&str>) -> Option<&'static str> {
+    match attr_type {
+        AttributeType::Text => Some("string"),
+        AttributeType::Numeric => Some("decimal"),
+        AttributeType::Boolean => Some("boolean"),
+        AttributeType::Binary => Some("base64Binary"),
+        AttributeType::DateTime => match format {
+            None => Some("dateTime"),
+            Some("YYYY-MM-DD") => Some("date"),
+            Some("hh:mm:ss") | Some("HH:mm:ss") => Some("time"),
+            Some(_) => None,
+        },
+    }
+}
+
+fn entry_codes(attribute: &Attribute, attr_type: &AttributeType) -> Option<Vec<String>> {
+    let codes: Vec<&String> = match attribute.entry_codes.as_ref()? {
+        EntryCodes::Sai(_) => return None,
+        EntryCodes::Array(codes) => codes.iter().collect(),
+        EntryCodes::Object(groups) => groups.values().flatten().collect(),
+    };
+    let values = codes
+        .into_iter()
+        .map(|code| match attr_type {
+            AttributeType::Numeric if code.parse::<f64>().is_ok() => code.clone(),
+            _ => literal(code),
+        })
+        .collect();
+    Some(values)
+}
+
+fn literal(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r");
+    format!("\"{escaped}\"")
+}
+
+fn parse_cardinality(cardinality: &str) -> (Option<u64>, Option<u64>) {
+    let (min, max) = cardinality
+        .split_once('-')
+        .unwrap_or((cardinality, cardinality));
+    (min.trim().parse().ok(), max.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::build;
+    use oca_bundle_semantics::state::dependencies::Error as DependencyError;
+
+    fn bundles() -> (OCABundle, OCABundle) {
+        let address = build("ADD ATTRIBUTE street=Text\n");
+        let person = build(&format!(
+            r#"ADD ATTRIBUTE name=Text age=Numeric gender=Text address=refs:{}
+ADD CONFORMANCE ATTRS name="M"
+ADD ENTRY_CODE ATTRS gender=["F", "M"]
+ADD ATTR_FRAMING id="SNOMEDCT" label="SNOMED CT" location="https://snomed.org" version="2023AA" ATTRS age={{"http://snomed.info/id/397669002": {{"predicate_id": "skos:exactMatch", "framing_justification": "semapv:ManualMappingCuration"}}}}
+"#,
+            address.said.as_ref().unwrap()
+        ));
+        (person, address)
+    }
+
+    #[test]
+    fn export_json_ld_context() {
+        let (person, address) = bundles();
+        let person_said = person.said.clone().unwrap();
+        let address_said = address.said.clone().unwrap();
+
+        let context = to_json_ld_context(&person, &[address]).unwrap();
+
+        assert_eq!(
+            context,
+            json!({
+                "@context": {
+                    "@version": 1.1,
+                    "xsd": XSD,
+                    "@vocab": format!("urn:said:{person_said}#"),
+                    "address": {
+                        "@context": {
+                            "@vocab": format!("urn:said:{address_said}#"),
+                            "street": {"@type": "xsd:string"}
+                        }
+                    },
+                    "age": {
+                        "@id": "http://snomed.info/id/397669002",
+                        "@type": "xsd:decimal"
+                    },
+                    "gender": {"@type": "xsd:string"},
+                    "name": {"@type": "xsd:string"}
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn export_shacl() {
+        let (person, address) = bundles();
+        let person_said = person.said.clone().unwrap();
+        let address_said = address.said.clone().unwrap();
+
+        let shacl = to_shacl(&person, &[address]).unwrap();
+
+        let expected = format!(
+            r#"@prefix sh: <http://www.w3.org/ns/shacl#> .
+@prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+
+<urn:said:{person_said}#Shape>
+    a sh:NodeShape ;
+    sh:targetClass <urn:said:{person_said}> ;
+    sh:property [
+        sh:path <urn:said:{person_said}#address> ;
+        sh:node <urn:said:{address_said}#Shape> ;
+        sh:maxCount 1 ;
+    ] ;
+    sh:property [
+        sh:path <http://snomed.info/id/397669002> ;
+        sh:datatype xsd:decimal ;
+        sh:maxCount 1 ;
+    ] ;
+    sh:property [
+        sh:path <urn:said:{person_said}#gender> ;
+        sh:datatype xsd:string ;
+        sh:in ( "F" "M" ) ;
+        sh:maxCount 1 ;
+    ] ;
+    sh:property [
+        sh:path <urn:said:{person_said}#name> ;
+        sh:datatype xsd:string ;
+        sh:minCount 1 ;
+        sh:maxCount 1 ;
+    ] ;
+    .
+
+<urn:said:{address_said}#Shape>
+    a sh:NodeShape ;
+    sh:targetClass <urn:said:{address_said}> ;
+    sh:property [
+        sh:path <urn:said:{address_said}#street> ;
+        sh:datatype xsd:string ;
+        sh:maxCount 1 ;
+    ] ;
+    .
+"#
+        );
+        assert_eq!(shacl, expected);
+    }
+
+    #[test]
+    fn missing_dependency() {
+        let (person, _) = bundles();
+        assert!(matches!(
+            to_json_ld_context(&person, &[]),
+            Err(Error::Dependency(DependencyError::MissingDependency { .. }))
+        ));
+        assert!(matches!(
+            to_shacl(&person, &[]),
+            Err(Error::Dependency(DependencyError::MissingDependency { .. }))
+        ));
+    }
+}
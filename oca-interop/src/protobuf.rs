@@ -0,0 +1,333 @@
+//! Export of OCA Bundles to Protocol Buffers (proto3) message definitions.
+//!
+//! Every bundle becomes a message and references use messages of the
+//! referenced bundles. Arrays become `repeated` fields, entry codes become
+//! enums and attributes which aren't mandatory are marked `optional`. Field
+//! numbers follow the order of attribute names.
+use crate::Error;
+use isolang::Language;
+use oca_ast_semantics::ast::{AttributeType, NestedAttrType};
+use oca_bundle_semantics::state::{
+    attribute::Attribute,
+    dependencies::{said_of, Dependencies},
+    entry_codes::EntryCodes,
+    naming::{pascal_case, type_name},
+    oca::OCABundle,
+    view::BundleView,
+};
+use std::collections::HashMap;
+
+/// Generate `.proto` file with messages of the bundle and of the bundles it
+/// references, in `package` when given.
+///
+/// Message names are taken from the Meta overlay in `language`, comments
+/// from the Meta, Label and Information overlays.
+pub fn to_proto(
+    bundle: &OCABundle,
+    dependencies: &[OCABundle],
+    package: Option<&str>,
+    language: Option<Language>,
+) -> Result<String, Error> {
+    let mut generator = Generator {
+        dependencies: Dependencies::new(dependencies),
+        language,
+        messages: HashMap::new(),
+        names: vec![],
+        definitions: vec![],
+        uses_struct: false,
+    };
+    generator.message(bundle)?;
+
+    let mut proto = "syntax = \"proto3\";\n".to_string();
+    if let Some(package) = package {
+        proto.push_str(&format!("\npackage {package};\n"));
+    }
+    if generator.uses_struct {
+        proto.push_str("\nimport \"google/protobuf/struct.proto\";\n");
+    }
+    for definition in generator.definitions {
+        proto.push('\n');
+        proto.push_str(&definition);
+    }
+
+    Ok(proto)
+}
+
+struct Generator<'a> {
+    dependencies: Dependencies<'a>,
+    language: Option<Language>,
+    /// Message names by bundle SAID.
+    messages: HashMap<String, String>,
+    names: Vec<String>,
+    definitions: Vec<String>,
+    /// Whether `NullValue` from `struct.proto` is used.
+    uses_struct: bool,
+}
+
+impl Generator<'_> {
+    /// Define message for the bundle, unless it's already defined, and
+    /// return its name.
+    fn message(&mut self, bundle: &OCABundle) -> Result<String, Error> {
+        let said = said_of(bundle)?;
+        if let Some(name) = self.messages.get(&said) {
+            return Ok(name.clone());
+        }
+        let view = BundleView::new(bundle);
+        let meta = view.metas_or_first(self.language);
+        let mut name = type_name(&view, self.language);
+        if self.names.contains(&name) {
+            name = format!("{name}{}", pascal_case(&said));
+        }
+        self.names.push(name.clone());
+        self.messages.insert(said.clone(), name.clone());
+        let index = self.definitions.len();
+        self.definitions.push(String::new());
+
+        let mut definition = comment("", meta.and_then(|meta| meta.get("description")));
+        definition.push_str(&format!("// OCA Bundle {said}\nmessage {name} {{\n"));
+        let attributes: Vec<&Attribute> = view
+            .attributes()
+            .into_iter()
+            .map(|attribute| attribute.attribute())
+            .collect();
+        let mut nested = vec![];
+        for (number, attribute) in attributes.into_iter().enumerate() {
+            let Some(attr_type) = &attribute.attribute_type else {
+                continue;
+            };
+            let type_prefix = format!("{name}{}", pascal_case(&attribute.name));
+            let (mut field_type, repeated) =
+                self.field_type(attr_type, attribute, &type_prefix, &mut nested)?;
+            if repeated {
+                field_type = format!("repeated {field_type}");
+            } else if attribute.conformance.as_deref() != Some("M") {
+                field_type = format!("optional {field_type}");
+            }
+            definition.push_str(&comment("  ", self.label(attribute).as_ref()));
+            definition.push_str(&format!(
+                "  {field_type} {} = {};\n",
+                field_name(&attribute.name),
+                number + 1
+            ));
+        }
+        for nested in nested {
+            definition.push('\n');
+            definition.push_str(&nested);
+        }
+        definition.push_str("}\n");
+        self.definitions[index] = definition;
+
+        Ok(name)
+    }
+
+    /// Field type and whether it is repeated. Enums and wrappers of nested
+    /// arrays are added to `nested`.
+    fn field_type(
+        &mut self,
+        attr_type: &NestedAttrType,
+        attribute: &Attribute,
+        type_prefix: &str,
+        nested: &mut Vec<String>,
+    ) -> Result<(String, bool), Error> {
+        let field_type = match attr_type {
+            NestedAttrType::Value(value_type) => match entry_codes_enum(attribute, type_prefix) {
+                Some(definition) => {
+                    nested.push(definition);
+                    type_prefix.to_string()
+                }
+                None => scalar_type(value_type).to_string(),
+            },
+            NestedAttrType::Array(item_type) => {
+                let (item_type, repeated) =
+                    self.field_type(item_type, attribute, type_prefix, nested)?;
+                if !repeated {
+                    return Ok((item_type, true));
+                }
+                // Repeated fields can't be nested, wrap inner array in
+                // a message.
+                let wrapper = format!("{type_prefix}List");
+                nested.push(format!(
+                    "  message {wrapper} {{\n    repeated {item_type} values = 1;\n  }}\n"
+                ));
+                return Ok((wrapper, true));
+            }
+            NestedAttrType::Reference(reference) => {
+                let dependency = self.dependencies.resolve(reference)?;
+                self.message(dependency)?
+            }
+            NestedAttrType::Null => {
+                self.uses_struct = true;
+                "google.protobuf.NullValue".to_string()
+            }
+        };
+
+        Ok((field_type, false))
+    }
+
+    fn label(&self, attribute: &Attribute) -> Option<String> {
+        let lang = self.language?;
+        let label = attribute.labels.as_ref().and_then(|l| l.get(&lang));
+        let information = attribute.informations.as_ref().and_then(|i| i.get(&lang));
+        match (label, information) {
+            (Some(label), Some(information)) => Some(format!("{label}\n{information}")),
+            (label, information) => label.or(information).cloned(),
+        }
+    }
+}
+
+fn scalar_type(value_type: &AttributeType) -> &'static str {
+    match value_type {
+        AttributeType::Text => "string",
+        AttributeType::Numeric => "double",
+        AttributeType::Boolean => "bool",
+        // Formatted as described by the Format overlay.
+        AttributeType::DateTime => "string",
+        AttributeType::Binary => "bytes",
+    }
+}
+
+/// Enum of entry codes. Values are prefixed with the enum name, as
+/// required by proto3 scoping, and the original codes are kept in comments.
+fn entry_codes_enum(attribute: &Attribute, name: &str) -> Option<String> {
+    let codes: Vec<&String> = match attribute.entry_codes.as_ref()? {
+        EntryCodes::Sai(_) => return None,
+        EntryCodes::Array(codes) => codes.iter().collect(),
+        EntryCodes::Object(groups) => groups.values().flatten().collect(),
+    };
+    let prefix = screaming_snake_case(name);
+    let mut definition = format!("  enum {name} {{\n    {prefix}_UNSPECIFIED = 0;\n");
+    let mut values: Vec<String> = vec![];
+    for (number, code) in codes.into_iter().enumerate() {
+        let mut value = format!("{prefix}_{}", screaming_snake_case(code));
+        while values.contains(&value) {
+            value.push('_');
+        }
+        definition.push_str(&format!("    {value} = {}; // {code}\n", number + 1));
+        values.push(value);
+    }
+    definition.push_str("  }\n");
+
+    Some(definition)
+}
+
+fn screaming_snake_case(name: &str) -> String {
+    let mut result = String::new();
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !result.is_empty() && !result.ends_with('_') {
+                result.push('_');
+            }
+        } else {
+            if c.is_ascii_uppercase()
+                && previous.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit())
+            {
+                result.push('_');
+            }
+            result.push(c.to_ascii_uppercase());
+        }
+        previous = Some(c);
+    }
+    result.trim_end_matches('_').to_string()
+}
+
+fn field_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("f_{name}")
+    }
+}
+
+fn comment(indent: &str, text: Option<&String>) -> String {
+    text.map(|text| {
+        text.lines()
+            .map(|line| {
+                if line.is_empty() {
+                    format!("{indent}//\n")
+                } else {
+                    format!("{indent}// {line}\n")
+                }
+            })
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, build};
+
+    #[test]
+    fn generate_proto() {
+        let address = address();
+        let address_said = address.said.clone().unwrap();
+        let person = build(&format!(
+            r#"ADD ATTRIBUTE name=Text sex=Text home=refs:{address_said} scores=Array[Array[Numeric]] tags=Array[Text]
+ADD META en PROPS name="Person record" description="Registered person"
+ADD LABEL en ATTRS name="Full name"
+ADD INFORMATION en ATTRS name="As in passport"
+ADD CONFORMANCE ATTRS name="M"
+ADD ENTRY_CODE ATTRS sex=["male", "female"]
+"#
+        ));
+        let person_said = person.said.clone().unwrap();
+
+        let proto = to_proto(
+            &person,
+            &[address],
+            Some("oca.example"),
+            Some(Language::Eng),
+        )
+        .unwrap();
+
+        assert_eq!(
+            proto,
+            format!(
+                r#"syntax = "proto3";
+
+package oca.example;
+
+// Registered person
+// OCA Bundle {person_said}
+message PersonRecord {{
+  optional Address home = 1;
+  // Full name
+  // As in passport
+  string name = 2;
+  repeated PersonRecordScoresList scores = 3;
+  optional PersonRecordSex sex = 4;
+  repeated string tags = 5;
+
+  message PersonRecordScoresList {{
+    repeated double values = 1;
+  }}
+
+  enum PersonRecordSex {{
+    PERSON_RECORD_SEX_UNSPECIFIED = 0;
+    PERSON_RECORD_SEX_MALE = 1; // male
+    PERSON_RECORD_SEX_FEMALE = 2; // female
+  }}
+}}
+
+// OCA Bundle {address_said}
+message Address {{
+  optional string street = 1;
+}}
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(screaming_snake_case("PersonRecordSex"), "PERSON_RECORD_SEX");
+        assert_eq!(screaming_snake_case("not applicable"), "NOT_APPLICABLE");
+        assert_eq!(field_name("1st-name"), "f_1st_name");
+    }
+}
@@ -0,0 +1,796 @@
+//! Spreadsheet templates for authoring OCA Bundles.
+//!
+//! A template consists of:
+//!
+//! - `Main` sheet with one row per attribute: name, type in OCAFILE syntax
+//!   (`Text`, `Array[Numeric]`, `refs:…`), flagged (`Y`), conformance,
+//!   format, cardinality, unit and entry codes,
+//! - `Meta` sheet with bundle metadata: language, property and value; the
+//!   `classification` property has no language,
+//! - one sheet per language, named with the ISO 639-1 code (ISO 639-3 codes
+//!   are accepted too), with labels, information and entries of attributes.
+//!
+//! Multiple values in a cell are separated with `|`, entries are written as
+//! `code=label` and entry code groups as `group=code,code`. `\` escapes
+//! these separators in values. Columns are matched by header, so their
+//! order doesn't matter and unknown columns and sheets are ignored.
+//!
+//! Templates can be exchanged as XLSX workbooks or as a set of CSV files,
+//! one per sheet.
+use crate::{ast, parse_lang, Error};
+use calamine::{open_workbook_from_rs, Reader, Xlsx};
+use indexmap::IndexMap;
+use isolang::Language;
+use oca_ast_semantics::ast::{
+    AttributeType, CaptureContent, NestedAttrType, NestedValue, OCAAst, OverlayType, RefValue,
+};
+use oca_bundle_semantics::state::{
+    attribute::Attribute,
+    entries::EntriesElement,
+    entry_codes::EntryCodes,
+    naming::lang_code,
+    oca::{OCABox, OCABundle},
+};
+use rust_xlsxwriter::{Format, Workbook};
+use std::{collections::HashMap, io::Cursor, str::FromStr};
+
+pub const MAIN_SHEET: &str = "Main";
+pub const META_SHEET: &str = "Meta";
+
+const MAIN_HEADER: &[&str] = &[
+    "Attribute",
+    "Type",
+    "Flagged",
+    "Conformance",
+    "Format",
+    "Cardinality",
+    "Unit",
+    "Entry codes",
+];
+const LANGUAGE_HEADER: &[&str] = &["Attribute", "Label", "Information", "Entries"];
+const META_HEADER: &[&str] = &["Language", "Property", "Value"];
+
+const SEPARATOR: char = '|';
+const KEY_SEPARATOR: char = '=';
+const GROUP_SEPARATOR: char = ',';
+const ESCAPE: char = '\\';
+
+type OverlayConstructor = fn(String) -> OverlayType;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sheet {
+    pub name: String,
+    /// Rows of cells, the first one is the header.
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Fill the template with the bundle.
+pub fn to_sheets(bundle: &OCABundle) -> Vec<Sheet> {
+    let oca_box = OCABox::from(bundle.clone());
+    let mut attributes: Vec<&Attribute> = oca_box.attributes.values().collect();
+    attributes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut main = header(MAIN_HEADER);
+    for attribute in &attributes {
+        let entry_codes = match &attribute.entry_codes {
+            Some(EntryCodes::Array(codes)) => join(codes.iter().map(|code| escape(code))),
+            Some(EntryCodes::Object(groups)) => join(groups.iter().map(|(group, codes)| {
+                let codes: Vec<String> = codes.iter().map(|code| escape(code)).collect();
+                format!(
+                    "{}{KEY_SEPARATOR}{}",
+                    escape(group),
+                    codes.join(&GROUP_SEPARATOR.to_string())
+                )
+            })),
+            Some(EntryCodes::Sai(_)) | None => String::new(),
+        };
+        main.push(vec![
+            attribute.name.clone(),
+            attribute
+                .attribute_type
+                .as_ref()
+                .map(format_type)
+                .unwrap_or_default(),
+            if attribute.is_flagged { "Y" } else { "" }.to_string(),
+            attribute.conformance.clone().unwrap_or_default(),
+            attribute.format.clone().unwrap_or_default(),
+            attribute.cardinality.clone().unwrap_or_default(),
+            attribute.unit.clone().unwrap_or_default(),
+            entry_codes,
+        ]);
+    }
+
+    let mut meta = header(META_HEADER);
+    if let Some(classification) = oca_box.classification.as_ref().filter(|c| !c.is_empty()) {
+        meta.push(vec![
+            String::new(),
+            "classification".to_string(),
+            classification.clone(),
+        ]);
+    }
+    let mut languages: Vec<Language> = vec![];
+    if let Some(bundle_meta) = &oca_box.meta {
+        let mut meta_languages: Vec<_> = bundle_meta.keys().collect();
+        meta_languages.sort();
+        for lang in meta_languages {
+            languages.push(*lang);
+            let mut properties: Vec<_> = bundle_meta[lang].iter().collect();
+            properties.sort();
+            for (property, value) in properties {
+                meta.push(vec![lang_code(lang), property.clone(), value.clone()]);
+            }
+        }
+    }
+
+    for attribute in &attributes {
+        for values in [&attribute.labels, &attribute.informations] {
+            languages.extend(values.iter().flat_map(|values| values.keys()));
+        }
+        languages.extend(attribute.entries.iter().flat_map(|entries| entries.keys()));
+    }
+    languages.sort();
+    languages.dedup();
+
+    let mut sheets = vec![
+        Sheet {
+            name: MAIN_SHEET.to_string(),
+            rows: main,
+        },
+        Sheet {
+            name: META_SHEET.to_string(),
+            rows: meta,
+        },
+    ];
+    for lang in languages {
+        let mut rows = header(LANGUAGE_HEADER);
+        for attribute in &attributes {
+            let localized = |values: &Option<HashMap<Language, String>>| {
+                values
+                    .as_ref()
+                    .and_then(|values| values.get(&lang))
+                    .cloned()
+                    .unwrap_or_default()
+            };
+            let entries = match attribute.entries.as_ref().and_then(|e| e.get(&lang)) {
+                Some(EntriesElement::Object(entries)) => format_entries(attribute, entries),
+                Some(EntriesElement::Sai(_)) | None => String::new(),
+            };
+            rows.push(vec![
+                attribute.name.clone(),
+                localized(&attribute.labels),
+                localized(&attribute.informations),
+                entries,
+            ]);
+        }
+        sheets.push(Sheet {
+            name: lang_code(&lang),
+            rows,
+        });
+    }
+
+    sheets
+}
+
+/// Read the template into OCA AST. OCAFILE can be obtained with
+/// `oca_file_semantics::ocafile::generate_from_ast`.
+pub fn from_sheets(sheets: &[Sheet]) -> Result<OCAAst, Error> {
+    let main = sheets
+        .iter()
+        .find(|sheet| sheet.name.eq_ignore_ascii_case(MAIN_SHEET))
+        .ok_or_else(|| Error::InvalidTemplate {
+            sheet: MAIN_SHEET.to_string(),
+            row: 0,
+            message: "sheet is missing".to_string(),
+        })?;
+    let table = Table::new(main, &["Attribute", "Type"])?;
+
+    let mut attributes = IndexMap::new();
+    let mut flagged = vec![];
+    let mut overlays: Vec<(OverlayConstructor, &str, IndexMap<_, _>)> = vec![
+        (OverlayType::Conformance, "Conformance", IndexMap::new()),
+        (OverlayType::Format, "Format", IndexMap::new()),
+        (OverlayType::Cardinality, "Cardinality", IndexMap::new()),
+        (OverlayType::Unit, "Unit", IndexMap::new()),
+    ];
+    let mut entry_codes = IndexMap::new();
+    for (row, name) in table.rows() {
+        if attributes.contains_key(&name) {
+            return Err(table.error(row, format!("attribute `{name}` is already defined")));
+        }
+        let attr_type = parse_type(table.cell(row, "Type"))
+            .ok_or_else(|| table.error(row, format!("invalid type of `{name}`")))?;
+        attributes.insert(name.clone(), attr_type);
+        if matches!(
+            table.cell(row, "Flagged").to_lowercase().as_str(),
+            "y" | "yes" | "true"
+        ) {
+            flagged.push(name.clone());
+        }
+        for (_, column, values) in overlays.iter_mut() {
+            let value = table.cell(row, column);
+            if !value.is_empty() {
+                values.insert(name.clone(), NestedValue::Value(value.to_string()));
+            }
+        }
+        if let Some(codes) = parse_entry_codes(table.cell(row, "Entry codes"))
+            .map_err(|message| table.error(row, message))?
+        {
+            entry_codes.insert(name.clone(), codes);
+        }
+    }
+
+    let mut oca_ast = OCAAst::new();
+    oca_ast.commands.push(ast::attributes(attributes));
+
+    let mut meta: IndexMap<String, IndexMap<String, NestedValue>> = IndexMap::new();
+    if let Some(sheet) = sheets
+        .iter()
+        .find(|sheet| sheet.name.eq_ignore_ascii_case(META_SHEET))
+    {
+        let table = Table::new(sheet, &["Language", "Property", "Value"])?;
+        for row in 1..sheet.rows.len() {
+            let property = table.cell(row, "Property");
+            if property.is_empty() {
+                continue;
+            }
+            let value = table.cell(row, "Value").to_string();
+            let lang = table.cell(row, "Language");
+            if property == "classification" && lang.is_empty() {
+                oca_ast.commands.push(ast::capture_base(CaptureContent {
+                    attributes: None,
+                    properties: Some(IndexMap::from([(
+                        "classification".to_string(),
+                        NestedValue::Value(value),
+                    )])),
+                    flagged_attributes: None,
+                }));
+                continue;
+            }
+            let lang = parse_lang(lang).ok_or_else(|| table.error(row, "invalid language"))?;
+            meta.entry(lang)
+                .or_default()
+                .insert(property.to_string(), NestedValue::Value(value));
+        }
+    }
+    if !flagged.is_empty() {
+        oca_ast.commands.push(ast::capture_base(CaptureContent {
+            attributes: None,
+            properties: None,
+            flagged_attributes: Some(flagged),
+        }));
+    }
+    for (lang, properties) in meta {
+        oca_ast.commands.push(ast::overlay(
+            OverlayType::Meta,
+            Some(&lang),
+            None,
+            properties,
+        ));
+    }
+
+    let defined: Vec<String> = table.rows().map(|(_, name)| name).collect();
+    for sheet in sheets {
+        let Some(lang) = parse_lang(&sheet.name) else {
+            if Language::from_639_3(&sheet.name.to_lowercase()).is_some() {
+                return Err(Error::InvalidTemplate {
+                    sheet: sheet.name.clone(),
+                    row: 0,
+                    message: "language has no ISO 639-1 code".to_string(),
+                });
+            }
+            continue;
+        };
+        let table = Table::new(sheet, &["Attribute"])?;
+        let mut labels = IndexMap::new();
+        let mut informations = IndexMap::new();
+        let mut entries = IndexMap::new();
+        for (row, name) in table.rows() {
+            if !defined.contains(&name) {
+                return Err(table.error(row, format!("attribute `{name}` is not defined")));
+            }
+            for (values, column) in [(&mut labels, "Label"), (&mut informations, "Information")] {
+                let value = table.cell(row, column);
+                if !value.is_empty() {
+                    values.insert(name.clone(), NestedValue::Value(value.to_string()));
+                }
+            }
+            let mut labelled = IndexMap::new();
+            for entry in split(table.cell(row, "Entries")) {
+                let (code, label) = split_once(entry, KEY_SEPARATOR).ok_or_else(|| {
+                    table.error(row, format!("entry `{}` has no label", unescape(entry)))
+                })?;
+                labelled.insert(unescape(code), NestedValue::Value(unescape(label)));
+            }
+            if !labelled.is_empty() {
+                entries.insert(name.clone(), NestedValue::Object(labelled));
+            }
+        }
+        for (overlay_type, attributes) in [
+            (OverlayType::Label as OverlayConstructor, labels),
+            (OverlayType::Information, informations),
+            (OverlayType::Entry, entries),
+        ] {
+            if !attributes.is_empty() {
+                oca_ast.commands.push(ast::overlay(
+                    overlay_type,
+                    Some(&lang),
+                    Some(attributes),
+                    IndexMap::new(),
+                ));
+            }
+        }
+    }
+
+    for (overlay_type, attributes) in overlays
+        .into_iter()
+        .map(|(overlay_type, _, attributes)| (overlay_type, attributes))
+        .chain([(OverlayType::EntryCode as OverlayConstructor, entry_codes)])
+    {
+        if !attributes.is_empty() {
+            oca_ast.commands.push(ast::overlay(
+                overlay_type,
+                None,
+                Some(attributes),
+                IndexMap::new(),
+            ));
+        }
+    }
+
+    Ok(oca_ast)
+}
+
+/// Fill the template with the bundle and write it as XLSX workbook.
+pub fn to_xlsx(bundle: &OCABundle) -> Result<Vec<u8>, Error> {
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    for sheet in to_sheets(bundle) {
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(&sheet.name).map_err(spreadsheet_error)?;
+        for (row, cells) in sheet.rows.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                let (row, col) = (row as u32, col as u16);
+                if row == 0 {
+                    worksheet.write_string_with_format(row, col, cell, &bold)
+                } else {
+                    worksheet.write_string(row, col, cell)
+                }
+                .map_err(spreadsheet_error)?;
+            }
+        }
+        worksheet
+            .set_freeze_panes(1, 0)
+            .map_err(spreadsheet_error)?;
+        worksheet.autofit();
+    }
+
+    workbook.save_to_buffer().map_err(spreadsheet_error)
+}
+
+/// Read the template from XLSX workbook into OCA AST.
+pub fn from_xlsx(bytes: &[u8]) -> Result<OCAAst, Error> {
+    let mut workbook: Xlsx<_> =
+        open_workbook_from_rs(Cursor::new(bytes)).map_err(spreadsheet_error)?;
+    let mut sheets = vec![];
+    for name in workbook.sheet_names() {
+        let range = workbook.worksheet_range(&name).map_err(spreadsheet_error)?;
+        // Range starts with the first used cell, keep rows and columns
+        // aligned with the sheet.
+        let (first_row, first_col) = range.start().unwrap_or_default();
+        let mut rows = vec![vec![]; first_row as usize];
+        rows.extend(range.rows().map(|cells| {
+            std::iter::repeat_n(String::new(), first_col as usize)
+                .chain(cells.iter().map(|cell| cell.to_string()))
+                .collect()
+        }));
+        sheets.push(Sheet { name, rows });
+    }
+
+    from_sheets(&sheets)
+}
+
+/// Write the sheet as CSV.
+pub fn sheet_to_csv(sheet: &Sheet) -> Result<String, Error> {
+    let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(vec![]);
+    for row in &sheet.rows {
+        writer.write_record(row).map_err(spreadsheet_error)?;
+    }
+    let bytes = writer.into_inner().map_err(spreadsheet_error)?;
+
+    String::from_utf8(bytes).map_err(spreadsheet_error)
+}
+
+/// Read the sheet from CSV, `name` is the name of the sheet it holds
+/// (`Main`, `Meta` or language code).
+pub fn sheet_from_csv(name: &str, csv: &str) -> Result<Sheet, Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(csv.as_bytes());
+    let rows = reader
+        .records()
+        .map(|record| {
+            record
+                .map(|record| record.iter().map(str::to_string).collect())
+                .map_err(spreadsheet_error)
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Sheet {
+        name: name.to_string(),
+        rows,
+    })
+}
+
+/// Sheet with columns looked up by header.
+struct Table<'a> {
+    sheet: &'a Sheet,
+    columns: HashMap<String, usize>,
+}
+
+impl<'a> Table<'a> {
+    fn new(sheet: &'a Sheet, required: &[&str]) -> Result<Self, Error> {
+        let columns: HashMap<String, usize> = sheet
+            .rows
+            .first()
+            .map(|header| {
+                header
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| (column.trim().to_lowercase(), i))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let table = Table { sheet, columns };
+        for column in required {
+            if !table.columns.contains_key(&column.to_lowercase()) {
+                return Err(table.error(0, format!("column `{column}` is missing")));
+            }
+        }
+
+        Ok(table)
+    }
+
+    fn cell(&self, row: usize, column: &str) -> &str {
+        self.columns
+            .get(&column.to_lowercase())
+            .and_then(|col| self.sheet.rows.get(row)?.get(*col))
+            .map(|cell| cell.trim())
+            .unwrap_or_default()
+    }
+
+    /// Rows with non empty attribute name.
+    fn rows(&self) -> impl Iterator<Item = (usize, String)> + '_ {
+        (1..self.sheet.rows.len()).filter_map(|row| {
+            let name = self.cell(row, "Attribute");
+            (!name.is_empty()).then(|| (row, name.to_string()))
+        })
+    }
+
+    fn error(&self, row: usize, message: impl Into<String>) -> Error {
+        Error::InvalidTemplate {
+            sheet: self.sheet.name.clone(),
+            row: row + 1,
+            message: message.into(),
+        }
+    }
+}
+
+fn header(columns: &[&str]) -> Vec<Vec<String>> {
+    vec![columns.iter().map(|column| column.to_string()).collect()]
+}
+
+fn join(values: impl IntoIterator<Item = String>) -> String {
+    values
+        .into_iter()
+        .collect::<Vec<_>>()
+        .join(&SEPARATOR.to_string())
+}
+
+/// Non empty values of the cell, still escaped.
+fn split(cell: &str) -> Vec<&str> {
+    split_escaped(cell, SEPARATOR)
+        .into_iter()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+/// Split on `separator` unless it is escaped.
+fn split_escaped(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == ESCAPE {
+            escaped = true;
+        } else if c == separator {
+            parts.push(&value[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+/// Split on the first `separator` which is not escaped.
+fn split_once(value: &str, separator: char) -> Option<(&str, &str)> {
+    let key = split_escaped(value, separator)[0];
+    let rest = value.get(key.len() + separator.len_utf8()..)?;
+    Some((key.trim(), rest.trim()))
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::new();
+    for c in value.chars() {
+        if [ESCAPE, SEPARATOR, KEY_SEPARATOR, GROUP_SEPARATOR].contains(&c) {
+            escaped.push(ESCAPE);
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ESCAPE => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Entry codes as array or, when written as `group=code,code`, as groups.
+fn parse_entry_codes(cell: &str) -> Result<Option<NestedValue>, String> {
+    let values = split(cell);
+    if values.is_empty() {
+        return Ok(None);
+    }
+    let groups: Vec<Option<(&str, &str)>> = values
+        .iter()
+        .map(|value| split_once(value, KEY_SEPARATOR))
+        .collect();
+    if groups.iter().all(Option::is_none) {
+        return Ok(Some(NestedValue::Array(
+            values
+                .into_iter()
+                .map(|code| NestedValue::Value(unescape(code)))
+                .collect(),
+        )));
+    }
+
+    let mut grouped = IndexMap::new();
+    for (value, group) in values.into_iter().zip(groups) {
+        let (group, codes) =
+            group.ok_or_else(|| format!("entry code `{}` has no group", unescape(value)))?;
+        let codes = split_escaped(codes, GROUP_SEPARATOR)
+            .into_iter()
+            .map(str::trim)
+            .filter(|code| !code.is_empty())
+            .map(|code| NestedValue::Value(unescape(code)))
+            .collect();
+        grouped.insert(unescape(group), NestedValue::Array(codes));
+    }
+
+    Ok(Some(NestedValue::Object(grouped)))
+}
+
+/// Entries in order of entry codes.
+fn format_entries(attribute: &Attribute, entries: &HashMap<String, String>) -> String {
+    let mut codes: Vec<&String> = match &attribute.entry_codes {
+        Some(EntryCodes::Array(codes)) => codes.iter().collect(),
+        Some(EntryCodes::Object(groups)) => groups.values().flatten().collect(),
+        Some(EntryCodes::Sai(_)) | None => vec![],
+    };
+    let mut rest: Vec<&String> = entries
+        .keys()
+        .filter(|code| !codes.contains(code))
+        .collect();
+    rest.sort();
+    codes.extend(rest);
+
+    codes
+        .into_iter()
+        .filter_map(|code| {
+            let label = entries.get(code)?;
+            Some(format!("{}{KEY_SEPARATOR}{}", escape(code), escape(label)))
+        })
+        .collect::<Vec<_>>()
+        .join(&SEPARATOR.to_string())
+}
+
+fn format_type(attr_type: &NestedAttrType) -> String {
+    match attr_type {
+        NestedAttrType::Value(value_type) => value_type.to_string(),
+        NestedAttrType::Reference(reference) => reference.to_string(),
+        NestedAttrType::Array(item_type) => format!("Array[{}]", format_type(item_type)),
+        NestedAttrType::Null => "Null".to_string(),
+    }
+}
+
+fn parse_type(cell: &str) -> Option<NestedAttrType> {
+    if let Some(item_type) = cell
+        .strip_prefix("Array[")
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        return Some(NestedAttrType::Array(Box::new(parse_type(
+            item_type.trim(),
+        )?)));
+    }
+    if cell.starts_with("refs:") || cell.starts_with("refn:") {
+        return RefValue::from_str(cell).ok().map(NestedAttrType::Reference);
+    }
+    AttributeType::from_str(cell)
+        .ok()
+        .map(NestedAttrType::Value)
+}
+
+fn spreadsheet_error(error: impl std::fmt::Display) -> Error {
+    Error::Spreadsheet {
+        message: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::build;
+    use oca_bundle_semantics::build::from_ast;
+    use oca_file_semantics::ocafile::generate_from_ast;
+
+    const OCAFILE: &str = r#"ADD ATTRIBUTE name=Text age=Numeric sex=Text tags=Array[Text]
+ADD CLASSIFICATION GICS:35102020
+ADD FLAGGED_ATTRIBUTES name
+ADD META en PROPS name="Patient" description="Patient record"
+ADD META pl PROPS name="Pacjent"
+ADD LABEL en ATTRS name="Full name" age="Age" sex="Sex"
+ADD LABEL pl ATTRS name="Imię i nazwisko" age="Wiek"
+ADD INFORMATION en ATTRS name="As in passport"
+ADD ENTRY_CODE ATTRS sex=["m", "f"]
+ADD ENTRY en ATTRS sex={"m": "Male", "f": "Female"}
+ADD ENTRY pl ATTRS sex={"m": "Mężczyzna", "f": "Kobieta"}
+ADD CONFORMANCE ATTRS name="M" age="O"
+ADD FORMAT ATTRS name="^[A-Za-z ]+$"
+ADD CARDINALITY ATTRS tags="1-3"
+ADD UNIT ATTRS age="year"
+"#;
+
+    #[test]
+    fn xlsx_roundtrip() {
+        let bundle = build(OCAFILE);
+        let sheets = to_sheets(&bundle);
+        assert_eq!(
+            sheets.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+            vec!["Main", "Meta", "en", "pl"]
+        );
+        let row = |sheet: &Sheet, name: &str| {
+            sheet
+                .rows
+                .iter()
+                .find(|row| row[0] == name)
+                .cloned()
+                .unwrap()
+        };
+        assert_eq!(
+            row(&sheets[0], "name"),
+            vec!["name", "Text", "Y", "M", "^[A-Za-z ]+$", "", "", ""]
+        );
+        assert_eq!(
+            row(&sheets[3], "sex"),
+            vec!["sex", "", "", "m=Mężczyzna|f=Kobieta"]
+        );
+
+        let xlsx = to_xlsx(&bundle).unwrap();
+        let oca_ast = from_xlsx(&xlsx).unwrap();
+        let imported = from_ast(None, &oca_ast).unwrap().oca_bundle;
+        assert_eq!(imported.said, bundle.said);
+
+        let ocafile = generate_from_ast(&oca_ast);
+        assert_eq!(build(&ocafile).said, bundle.said);
+    }
+
+    #[test]
+    fn csv_template() {
+        let main = sheet_from_csv(
+            "Main",
+            "Attribute,Type,Conformance,Entry codes\nkind,Text,M,a | b\nitems,Array[Numeric],,\n",
+        )
+        .unwrap();
+        let en = sheet_from_csv("en", "Attribute,Label,Entries\nkind,Kind,a=A|b=B\n").unwrap();
+        let oca_ast = from_sheets(&[main.clone(), en]).unwrap();
+        let bundle = from_ast(None, &oca_ast).unwrap().oca_bundle;
+
+        assert_eq!(
+            bundle.said,
+            build(
+                r#"ADD ATTRIBUTE kind=Text items=Array[Numeric]
+ADD LABEL en ATTRS kind="Kind"
+ADD ENTRY_CODE ATTRS kind=["a", "b"]
+ADD ENTRY en ATTRS kind={"a": "A", "b": "B"}
+ADD CONFORMANCE ATTRS kind="M"
+"#
+            )
+            .said
+        );
+        assert_eq!(
+            sheet_from_csv("Main", &sheet_to_csv(&main).unwrap()).unwrap(),
+            main
+        );
+    }
+
+    #[test]
+    fn grouped_entry_codes() {
+        let bundle = build(
+            r#"ADD ATTRIBUTE kind=Text
+ADD ENTRY_CODE ATTRS kind={"g1": ["a"], "g2": ["b|c", "d"]}
+ADD ENTRY en ATTRS kind={"a": "A=1", "b|c": "B|C", "d": "D"}
+"#,
+        );
+        let sheets = to_sheets(&bundle);
+        assert_eq!(sheets[0].rows[1][7], r"g1=a|g2=b\|c,d");
+        assert_eq!(sheets[2].rows[1][3], r"a=A\=1|b\|c=B\|C|d=D");
+
+        let imported = from_ast(None, &from_sheets(&sheets).unwrap())
+            .unwrap()
+            .oca_bundle;
+        assert_eq!(imported.said, bundle.said);
+    }
+
+    #[test]
+    fn iso_639_3_sheet_names() {
+        let main = sheet_from_csv(
+            "Main",
+            "Attribute,Type
+kind,Text
+",
+        )
+        .unwrap();
+        let pol = sheet_from_csv(
+            "pol",
+            "Attribute,Label
+kind,Rodzaj
+",
+        )
+        .unwrap();
+        let bundle = from_ast(None, &from_sheets(&[main.clone(), pol]).unwrap())
+            .unwrap()
+            .oca_bundle;
+        assert_eq!(
+            bundle.said,
+            build("ADD ATTRIBUTE kind=Text\nADD LABEL pl ATTRS kind=\"Rodzaj\"\n").said
+        );
+
+        let csb = sheet_from_csv(
+            "csb",
+            "Attribute,Label
+kind,Zort
+",
+        )
+        .unwrap();
+        assert_eq!(
+            from_sheets(&[main, csb]).unwrap_err(),
+            Error::InvalidTemplate {
+                sheet: "csb".to_string(),
+                row: 0,
+                message: "language has no ISO 639-1 code".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_template() {
+        let main = sheet_from_csv("Main", "Attribute,Type\nkind,Txt\n").unwrap();
+        assert_eq!(
+            from_sheets(&[main]).unwrap_err(),
+            Error::InvalidTemplate {
+                sheet: "Main".to_string(),
+                row: 2,
+                message: "invalid type of `kind`".to_string(),
+            }
+        );
+    }
+}
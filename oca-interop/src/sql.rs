@@ -0,0 +1,482 @@
+//! Generation of SQL `CREATE TABLE` statements from OCA Bundles.
+//!
+//! Every bundle becomes a table with a surrogate `_oca_id` primary key.
+//! References become foreign keys to the tables of referenced bundles and
+//! arrays become child tables with `_oca_parent_id`, `_oca_position` and
+//! `_oca_value` columns. The `_oca_` prefix is reserved for these columns.
+use crate::Error;
+use isolang::Language;
+use oca_ast_semantics::ast::{AttributeType, NestedAttrType};
+use oca_bundle_semantics::state::{
+    attribute::Attribute,
+    dependencies::{said_of, Dependencies},
+    entry_codes::EntryCodes,
+    oca::OCABundle,
+    view::BundleView,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Prefix of generated columns, attribute names can't start with it.
+const RESERVED_PREFIX: &str = "_oca_";
+const KEY: &str = "_oca_id";
+const PARENT_KEY: &str = "_oca_parent_id";
+const POSITION: &str = "_oca_position";
+const VALUE: &str = "_oca_value";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    Sqlite,
+    Postgres,
+}
+
+impl Dialect {
+    fn primary_key(&self) -> &'static str {
+        match self {
+            Dialect::Sqlite => "INTEGER PRIMARY KEY",
+            Dialect::Postgres => "BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY",
+        }
+    }
+
+    fn foreign_key(&self) -> &'static str {
+        match self {
+            Dialect::Sqlite => "INTEGER",
+            Dialect::Postgres => "BIGINT",
+        }
+    }
+
+    fn column_type(&self, attr_type: &AttributeType, format: Option<&str>) -> &'static str {
+        match (self, attr_type) {
+            (_, AttributeType::Text) => "TEXT",
+            (_, AttributeType::Numeric) => "NUMERIC",
+            (_, AttributeType::Boolean) => "BOOLEAN",
+            (Dialect::Sqlite, AttributeType::Binary) => "BLOB",
+            (Dialect::Postgres, AttributeType::Binary) => "BYTEA",
+            // SQLite has no date types, ISO 8601 strings are used instead.
+            (Dialect::Sqlite, AttributeType::DateTime) => "TEXT",
+            (Dialect::Postgres, AttributeType::DateTime) => match format {
+                Some("YYYY-MM-DD") => "DATE",
+                Some("hh:mm:ss") | Some("HH:mm:ss") => "TIME",
+                _ => "TIMESTAMPTZ",
+            },
+        }
+    }
+}
+
+/// Generate `CREATE TABLE` statements for the bundle and the bundles it
+/// references.
+///
+/// Tables are named after the `name` from the Meta overlay, or after the
+/// bundle SAID when there is none, with a numeric suffix when the name is
+/// already taken. Statements are ordered so that referenced
+/// tables are created first. Labels in `language` become column comments.
+pub fn to_sql(
+    bundle: &OCABundle,
+    dependencies: &[OCABundle],
+    dialect: Dialect,
+    language: Option<Language>,
+) -> Result<String, Error> {
+    let mut generator = Generator {
+        dependencies: Dependencies::new(dependencies),
+        dialect,
+        language,
+        tables: HashMap::new(),
+        names: HashSet::new(),
+        statements: vec![],
+    };
+    generator.table(bundle)?;
+
+    Ok(generator.statements.join("\n"))
+}
+
+struct Column {
+    name: String,
+    definition: String,
+    comment: Option<String>,
+}
+
+struct Table {
+    name: String,
+    comment: Option<String>,
+    columns: Vec<Column>,
+}
+
+struct Generator<'a> {
+    dependencies: Dependencies<'a>,
+    dialect: Dialect,
+    language: Option<Language>,
+    /// Table names by bundle SAID.
+    tables: HashMap<String, String>,
+    /// Names of all created tables, child tables included.
+    names: HashSet<String>,
+    statements: Vec<String>,
+}
+
+impl Generator<'_> {
+    /// Create table for the bundle, unless it already exists, and return
+    /// its name.
+    fn table(&mut self, bundle: &OCABundle) -> Result<String, Error> {
+        let said = said_of(bundle)?;
+        if let Some(name) = self.tables.get(&said) {
+            return Ok(name.clone());
+        }
+        let view = BundleView::new(bundle);
+        let meta = view.metas_or_first(self.language);
+        let name = meta
+            .and_then(|meta| meta.get("name"))
+            .map(|name| identifier(name))
+            .unwrap_or_else(|| format!("oca_{said}"));
+        let name = self.unique_name(name);
+        self.tables.insert(said, name.clone());
+
+        let mut table = Table {
+            name: name.clone(),
+            comment: meta.and_then(|meta| meta.get("description")).cloned(),
+            columns: vec![Column {
+                name: KEY.to_string(),
+                definition: self.dialect.primary_key().to_string(),
+                comment: None,
+            }],
+        };
+        let attributes: Vec<&Attribute> = view
+            .attributes()
+            .into_iter()
+            .map(|attribute| attribute.attribute())
+            .collect();
+        let mut child_tables = vec![];
+        for attribute in attributes {
+            if attribute.name.starts_with(RESERVED_PREFIX) {
+                return Err(Error::ReservedName {
+                    name: attribute.name.clone(),
+                });
+            }
+            let Some(attr_type) = &attribute.attribute_type else {
+                continue;
+            };
+            let comment = self.label(attribute);
+            if let NestedAttrType::Array(item_type) = attr_type {
+                let child = self.unique_name(format!("{name}_{}", identifier(&attribute.name)));
+                child_tables.push((child, name.clone(), item_type.as_ref(), attribute));
+                continue;
+            }
+            if let Some(mut definition) = self.value_definition(attr_type, attribute)? {
+                if attribute.conformance.as_deref() == Some("M") {
+                    definition.push_str(" NOT NULL");
+                }
+                if let Some(check) = check(&attribute.name, attr_type, attribute) {
+                    definition.push_str(&format!(" {check}"));
+                }
+                table.columns.push(Column {
+                    name: attribute.name.clone(),
+                    definition,
+                    comment,
+                });
+            }
+        }
+        self.create(table);
+
+        for (child, parent, item_type, attribute) in child_tables {
+            self.child_table(child, &parent, item_type, attribute)?;
+        }
+
+        Ok(name)
+    }
+
+    fn child_table(
+        &mut self,
+        name: String,
+        parent: &str,
+        item_type: &NestedAttrType,
+        attribute: &Attribute,
+    ) -> Result<(), Error> {
+        let mut table = Table {
+            name: name.clone(),
+            comment: self.label(attribute),
+            columns: vec![
+                Column {
+                    name: KEY.to_string(),
+                    definition: self.dialect.primary_key().to_string(),
+                    comment: None,
+                },
+                Column {
+                    name: PARENT_KEY.to_string(),
+                    definition: format!(
+                        "{} NOT NULL REFERENCES {} ({}) ON DELETE CASCADE",
+                        self.dialect.foreign_key(),
+                        quote(parent),
+                        quote(KEY)
+                    ),
+                    comment: None,
+                },
+                Column {
+                    name: POSITION.to_string(),
+                    definition: "INTEGER NOT NULL".to_string(),
+                    comment: None,
+                },
+            ],
+        };
+        let nested = match item_type {
+            NestedAttrType::Array(nested) => Some(nested.as_ref()),
+            _ => {
+                if let Some(mut definition) = self.value_definition(item_type, attribute)? {
+                    definition.push_str(" NOT NULL");
+                    if let Some(check) = check(VALUE, item_type, attribute) {
+                        definition.push_str(&format!(" {check}"));
+                    }
+                    table.columns.push(Column {
+                        name: VALUE.to_string(),
+                        definition,
+                        comment: None,
+                    });
+                }
+                None
+            }
+        };
+        self.create(table);
+        if let Some(nested) = nested {
+            let child = self.unique_name(format!("{name}_item"));
+            self.child_table(child, &name, nested, attribute)?;
+        }
+
+        Ok(())
+    }
+
+    /// Column type with foreign key constraint for references.
+    fn value_definition(
+        &mut self,
+        attr_type: &NestedAttrType,
+        attribute: &Attribute,
+    ) -> Result<Option<String>, Error> {
+        let definition = match attr_type {
+            NestedAttrType::Value(value_type) => Some(
+                self.dialect
+                    .column_type(value_type, attribute.format.as_deref())
+                    .to_string(),
+            ),
+            NestedAttrType::Reference(reference) => {
+                let dependency = self.dependencies.resolve(reference)?;
+                let referenced = self.table(dependency)?;
+                Some(format!(
+                    "{} REFERENCES {} ({})",
+                    self.dialect.foreign_key(),
+                    quote(&referenced),
+                    quote(KEY)
+                ))
+            }
+            NestedAttrType::Array(_) | NestedAttrType::Null => None,
+        };
+
+        Ok(definition)
+    }
+
+    /// Register table name, adding a numeric suffix if it is already taken.
+    fn unique_name(&mut self, name: String) -> String {
+        let mut unique = name.clone();
+        let mut suffix = 2;
+        while !self.names.insert(unique.clone()) {
+            unique = format!("{name}_{suffix}");
+            suffix += 1;
+        }
+        unique
+    }
+
+    fn label(&self, attribute: &Attribute) -> Option<String> {
+        let lang = self.language?;
+        attribute.labels.as_ref()?.get(&lang).cloned()
+    }
+
+    fn create(&mut self, table: Table) {
+        let mut statement = String::new();
+        if let (Dialect::Sqlite, Some(comment)) = (self.dialect, &table.comment) {
+            statement.push_str(&format!("-- {}\n", single_line(comment)));
+        }
+        statement.push_str(&format!("CREATE TABLE {} (\n", quote(&table.name)));
+        let last = table.columns.len() - 1;
+        for (i, column) in table.columns.iter().enumerate() {
+            statement.push_str(&format!("  {} {}", quote(&column.name), column.definition));
+            if i != last {
+                statement.push(',');
+            }
+            if let (Dialect::Sqlite, Some(comment)) = (self.dialect, &column.comment) {
+                statement.push_str(&format!(" -- {}", single_line(comment)));
+            }
+            statement.push('\n');
+        }
+        statement.push_str(");\n");
+
+        if self.dialect == Dialect::Postgres {
+            if let Some(comment) = &table.comment {
+                statement.push_str(&format!(
+                    "COMMENT ON TABLE {} IS {};\n",
+                    quote(&table.name),
+                    string(comment)
+                ));
+            }
+            for column in &table.columns {
+                if let Some(comment) = &column.comment {
+                    statement.push_str(&format!(
+                        "COMMENT ON COLUMN {}.{} IS {};\n",
+                        quote(&table.name),
+                        quote(&column.name),
+                        string(comment)
+                    ));
+                }
+            }
+        }
+        self.statements.push(statement);
+    }
+}
+
+/// CHECK constraint limiting column to entry codes.
+fn check(column: &str, attr_type: &NestedAttrType, attribute: &Attribute) -> Option<String> {
+    let codes: Vec<&String> = match attribute.entry_codes.as_ref()? {
+        EntryCodes::Sai(_) => return None,
+        EntryCodes::Array(codes) => codes.iter().collect(),
+        EntryCodes::Object(groups) => groups.values().flatten().collect(),
+    };
+    let numeric = matches!(attr_type, NestedAttrType::Value(AttributeType::Numeric));
+    let values: Vec<String> = codes
+        .into_iter()
+        .map(|code| {
+            if numeric && code.parse::<f64>().is_ok() {
+                code.clone()
+            } else {
+                string(code)
+            }
+        })
+        .collect();
+
+    Some(format!(
+        "CHECK ({} IN ({}))",
+        quote(column),
+        values.join(", ")
+    ))
+}
+
+fn identifier(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn single_line(comment: &str) -> String {
+    comment.replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, build};
+
+    fn bundles() -> (OCABundle, OCABundle) {
+        let address = address();
+        let person = build(&format!(
+            r#"ADD ATTRIBUTE name=Text born=DateTime tags=Array[Text] address=refs:{}
+ADD META en PROPS name="Person" description="Registered person"
+ADD LABEL en ATTRS name="Full name"
+ADD CONFORMANCE ATTRS name="M"
+ADD ENTRY_CODE ATTRS tags=["a", "b"]
+"#,
+            address.said.as_ref().unwrap()
+        ));
+        (person, address)
+    }
+
+    #[test]
+    fn generate_sqlite() {
+        let (person, address) = bundles();
+        let sql = to_sql(&person, &[address], Dialect::Sqlite, Some(Language::Eng)).unwrap();
+
+        assert_eq!(
+            sql,
+            r#"CREATE TABLE "address" (
+  "_oca_id" INTEGER PRIMARY KEY,
+  "street" TEXT
+);
+
+-- Registered person
+CREATE TABLE "person" (
+  "_oca_id" INTEGER PRIMARY KEY,
+  "address" INTEGER REFERENCES "address" ("_oca_id"),
+  "born" TEXT,
+  "name" TEXT NOT NULL -- Full name
+);
+
+CREATE TABLE "person_tags" (
+  "_oca_id" INTEGER PRIMARY KEY,
+  "_oca_parent_id" INTEGER NOT NULL REFERENCES "person" ("_oca_id") ON DELETE CASCADE,
+  "_oca_position" INTEGER NOT NULL,
+  "_oca_value" TEXT NOT NULL CHECK ("_oca_value" IN ('a', 'b'))
+);
+"#
+        );
+    }
+
+    #[test]
+    fn generate_postgres() {
+        let (person, address) = bundles();
+        let sql = to_sql(&person, &[address], Dialect::Postgres, Some(Language::Eng)).unwrap();
+
+        assert!(sql.contains(r#"  "born" TIMESTAMPTZ,"#));
+        assert!(sql.contains(r#"  "address" BIGINT REFERENCES "address" ("_oca_id"),"#));
+        assert!(sql.contains(r#"COMMENT ON TABLE "person" IS 'Registered person';"#));
+        assert!(sql.contains(r#"COMMENT ON COLUMN "person"."name" IS 'Full name';"#));
+        assert!(sql.find(r#"CREATE TABLE "address""#) < sql.find(r#"CREATE TABLE "person""#));
+    }
+
+    #[test]
+    fn avoid_name_collisions() {
+        let tags = build(
+            r#"ADD ATTRIBUTE id=Text
+ADD META en PROPS name="Person tags"
+"#,
+        );
+        let person = build(&format!(
+            r#"ADD ATTRIBUTE id=Numeric tags=Array[Text] extra=refs:{}
+ADD META en PROPS name="Person"
+"#,
+            tags.said.as_ref().unwrap()
+        ));
+        let sql = to_sql(&person, &[tags], Dialect::Sqlite, None).unwrap();
+
+        assert!(sql.contains(
+            r#"CREATE TABLE "person_tags" (
+  "_oca_id" INTEGER PRIMARY KEY,
+  "id" TEXT
+);"#
+        ));
+        assert!(sql.contains(
+            r#"CREATE TABLE "person" (
+  "_oca_id" INTEGER PRIMARY KEY,
+  "extra" INTEGER REFERENCES "person_tags" ("_oca_id"),
+  "id" NUMERIC
+);"#
+        ));
+        assert!(sql.contains(r#"CREATE TABLE "person_tags_2" ("#));
+    }
+
+    #[test]
+    fn reject_reserved_name() {
+        let bundle = build("ADD ATTRIBUTE _oca_id=Text\n");
+        assert_eq!(
+            to_sql(&bundle, &[], Dialect::Sqlite, None),
+            Err(Error::ReservedName {
+                name: "_oca_id".to_string()
+            })
+        );
+    }
+}
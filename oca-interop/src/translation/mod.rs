@@ -0,0 +1,390 @@
+//! Exchange of translations with CAT tools as XLIFF 2.0 or gettext PO files.
+//!
+//! Exported files hold translation units of Meta properties, labels,
+//! information, entries and category labels in the source language. Unit
+//! ids name what is translated: `meta/name`, `label/age`,
+//! `information/age`, `entry/sex/m` or `category/<key>`. Imported
+//! translations become OCAFILE commands applied on top of the bundle with
+//! `FROM`.
+mod po;
+mod xliff;
+
+pub use crate::{Error, Warning};
+pub use po::{from_po, to_po};
+pub use xliff::{from_xliff, to_xliff};
+
+use crate::{ast, parse_lang};
+use indexmap::IndexMap;
+use isolang::Language;
+use oca_ast_semantics::ast::{
+    BundleContent, Command, CommandType, NestedValue, OCAAst, ObjectKind, OverlayType, RefValue,
+    ReferenceAttrType,
+};
+use oca_bundle_semantics::state::{
+    dependencies, entry_codes::EntryCodes, oca::OCABundle, view::BundleView,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Translation {
+    /// ISO 639-1 code of the target language.
+    pub language: String,
+    /// Commands adding the translations, starting with `FROM` the bundle.
+    pub oca_ast: OCAAst,
+    pub ocafile: String,
+    pub warnings: Vec<Warning>,
+}
+
+/// Translatable text of the bundle.
+struct Unit {
+    id: String,
+    source: String,
+    /// Existing translation in the target language.
+    target: Option<String>,
+}
+
+/// Units with text in `source` language, in order of attribute names. Texts
+/// are unescaped, as translators see them.
+fn units(bundle: &OCABundle, source: Language, target: Language) -> Vec<Unit> {
+    let view = BundleView::new(bundle);
+    let mut units = vec![];
+    let mut push = |id: String, source: Option<&str>, target: Option<&str>| {
+        if let Some(source) = source.filter(|source| !source.is_empty()) {
+            units.push(Unit {
+                id,
+                source: unescape(source),
+                target: target.map(unescape),
+            });
+        }
+    };
+
+    if let Some(meta) = view.metas(source) {
+        let mut keys: Vec<&String> = meta.keys().collect();
+        keys.sort();
+        for key in keys {
+            push(
+                format!("meta/{key}"),
+                view.meta(source, key),
+                view.meta(target, key),
+            );
+        }
+    }
+    for attribute in view.attributes() {
+        let name = attribute.name();
+        push(
+            format!("label/{name}"),
+            attribute.label(source),
+            attribute.label(target),
+        );
+        push(
+            format!("information/{name}"),
+            attribute.information(source),
+            attribute.information(target),
+        );
+        let Some(entries) = attribute.entries(source) else {
+            continue;
+        };
+        let mut codes: Vec<&String> = match attribute.entry_codes() {
+            Some(EntryCodes::Array(codes)) => codes.iter().collect(),
+            Some(EntryCodes::Object(groups)) => groups.values().flatten().collect(),
+            Some(EntryCodes::Sai(_)) | None => vec![],
+        };
+        let mut other: Vec<&String> = entries.keys().filter(|c| !codes.contains(c)).collect();
+        other.sort();
+        codes.extend(other);
+        for code in codes {
+            push(
+                format!("entry/{name}/{code}"),
+                attribute.entry(source, code),
+                attribute.entry(target, code),
+            );
+        }
+    }
+    if let Some(categories) = view.categories(source) {
+        let mut keys: Vec<&String> = categories.keys().collect();
+        keys.sort();
+        for key in keys {
+            let target = view.categories(target).and_then(|c| c.get(key));
+            push(
+                format!("category/{key}"),
+                Some(categories[key].as_str()),
+                target.map(String::as_str),
+            );
+        }
+    }
+
+    units
+}
+
+/// Build commands adding translated units, given as unit id and target
+/// text, in `language`.
+fn import(
+    bundle: &OCABundle,
+    language: &str,
+    translations: Vec<(String, String)>,
+    mut warnings: Vec<Warning>,
+) -> Result<Translation, Error> {
+    let said = bundle
+        .said
+        .clone()
+        .ok_or(dependencies::Error::MissingSaid)?;
+    // Region, e.g. `pt_BR` or `pt-BR`, can't be expressed in OCA.
+    let language = language.split(['_', '-']).next().unwrap_or_default();
+    let language = parse_lang(language).ok_or_else(|| Error::InvalidTranslation {
+        message: format!("unsupported target language `{language}`"),
+    })?;
+    let lang = Language::from_639_1(&language).unwrap();
+    let view = BundleView::new(bundle);
+
+    let mut meta = IndexMap::new();
+    let mut labels = IndexMap::new();
+    let mut informations = IndexMap::new();
+    let mut entries: IndexMap<String, IndexMap<String, NestedValue>> = IndexMap::new();
+    for (id, target) in translations {
+        if target.is_empty() {
+            continue;
+        }
+        let mut warn = |message: String| {
+            warnings.push(Warning {
+                pointer: id.clone(),
+                message,
+            })
+        };
+        let parts: Vec<&str> = id.splitn(3, '/').collect();
+        match parts[..] {
+            ["meta", key] => {
+                if view.meta(lang, key).map(unescape) != Some(target.clone()) {
+                    meta.insert(key.to_string(), NestedValue::Value(escape(&target)));
+                }
+            }
+            ["label" | "information", name] => {
+                let Some(attribute) = view.attribute(name) else {
+                    warn(format!("attribute `{name}` is not defined"));
+                    continue;
+                };
+                let (values, existing) = if parts[0] == "label" {
+                    (&mut labels, attribute.label(lang))
+                } else {
+                    (&mut informations, attribute.information(lang))
+                };
+                if existing.map(unescape) != Some(target.clone()) {
+                    values.insert(name.to_string(), NestedValue::Value(escape(&target)));
+                }
+            }
+            ["entry", name, code] => {
+                let Some(attribute) = view.attribute(name) else {
+                    warn(format!("attribute `{name}` is not defined"));
+                    continue;
+                };
+                let known = match attribute.entry_codes() {
+                    Some(EntryCodes::Array(codes)) => codes.iter().any(|c| c == code),
+                    Some(EntryCodes::Object(groups)) => {
+                        groups.values().flatten().any(|c| c == code)
+                    }
+                    Some(EntryCodes::Sai(_)) | None => false,
+                };
+                if !known {
+                    warn(format!("`{code}` is not an entry code of `{name}`"));
+                } else if attribute.entry(lang, code).map(unescape) != Some(target.clone()) {
+                    entries
+                        .entry(name.to_string())
+                        .or_default()
+                        .insert(code.to_string(), NestedValue::Value(escape(&target)));
+                }
+            }
+            ["category", _] => {
+                warn("category labels can't be expressed in OCAFILE".to_string());
+            }
+            _ => warn("unknown translation unit".to_string()),
+        }
+    }
+
+    let mut oca_ast = OCAAst::new();
+    oca_ast.commands.push(Command {
+        kind: CommandType::From,
+        object_kind: ObjectKind::OCABundle(BundleContent {
+            said: ReferenceAttrType::Reference(RefValue::Said(said)),
+        }),
+    });
+    if !meta.is_empty() {
+        oca_ast
+            .commands
+            .push(ast::overlay(OverlayType::Meta, Some(&language), None, meta));
+    }
+    let entries = entries
+        .into_iter()
+        .map(|(name, entries)| (name, NestedValue::Object(entries)))
+        .collect();
+    for (overlay_type, attributes) in [
+        (OverlayType::Label as fn(String) -> OverlayType, labels),
+        (OverlayType::Information, informations),
+        (OverlayType::Entry, entries),
+    ] {
+        if !attributes.is_empty() {
+            oca_ast.commands.push(ast::overlay(
+                overlay_type,
+                Some(&language),
+                Some(attributes),
+                IndexMap::new(),
+            ));
+        }
+    }
+    let ocafile = oca_file_semantics::ocafile::generate_from_ast(&oca_ast);
+
+    Ok(Translation {
+        language,
+        oca_ast,
+        ocafile,
+        warnings,
+    })
+}
+
+/// Text of OCAFILE string, which bundles keep as written, e.g. `Say \"hi\"`.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        let (escaped, len) = match chars.clone().next() {
+            Some(c @ ('"' | '\\' | '\'')) => (Some(c), 1),
+            Some('n') => (Some('\n'), 1),
+            Some('r') => (Some('\r'), 1),
+            Some('t') => (Some('\t'), 1),
+            Some('b') => (Some('\u{8}'), 1),
+            Some('f') => (Some('\u{c}'), 1),
+            Some(u @ ('u' | 'U')) => {
+                let digits = if u == 'u' { 4 } else { 8 };
+                let hex: String = chars.clone().skip(1).take(digits).collect();
+                let c = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                (c.filter(|_| hex.len() == digits), 1 + digits)
+            }
+            _ => (None, 0),
+        };
+        match escaped {
+            Some(escaped) => {
+                chars.nth(len - 1);
+                unescaped.push(escaped);
+            }
+            None => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Text as written in OCAFILE strings.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use oca_bundle_semantics::build::from_ast;
+    use oca_file_semantics::ocafile::parse_from_string;
+
+    pub(crate) fn bundle() -> OCABundle {
+        let ocafile = r#"ADD ATTRIBUTE age=Numeric sex=Text
+ADD META en PROPS name="Person" description="Person data"
+ADD LABEL en ATTRS age="Age" sex="Sex"
+ADD LABEL pl ATTRS age="Wiek"
+ADD INFORMATION en ATTRS age="Age in years"
+ADD ENTRY_CODE ATTRS sex=["m", "f"]
+ADD ENTRY en ATTRS sex={"m": "Male", "f": "Female"}
+"#;
+        let ast = parse_from_string(ocafile.to_string()).unwrap();
+        from_ast(None, &ast).unwrap().oca_bundle
+    }
+
+    /// Apply the translation on top of the bundle.
+    pub(crate) fn apply(bundle: &OCABundle, translation: &Translation) -> OCABundle {
+        let mut oca_ast = translation.oca_ast.clone();
+        oca_ast.commands.remove(0);
+        from_ast(Some(bundle.clone()), &oca_ast).unwrap().oca_bundle
+    }
+
+    #[test]
+    fn round_trip_escaped_texts() {
+        let ocafile = r#"ADD ATTRIBUTE age=Numeric sex=Text
+ADD LABEL en ATTRS age="Say \"hi\"" sex="C:\\data"
+ADD LABEL pl ATTRS age="Powiedz \"hi\"" sex="C:\\dane"
+"#;
+        let ast = parse_from_string(ocafile.to_string()).unwrap();
+        let bundle = from_ast(None, &ast).unwrap().oca_bundle;
+        let said = bundle.said.as_ref().unwrap();
+
+        let xliff = to_xliff(&bundle, Language::Eng, Language::Pol).unwrap();
+        assert!(xliff.contains("<target>Powiedz &quot;hi&quot;</target>"));
+        let po = to_po(&bundle, Language::Eng, Language::Pol).unwrap();
+        assert!(po.contains(r#"msgstr "C:\\dane""#));
+        for translation in [
+            from_xliff(&bundle, &xliff).unwrap(),
+            from_po(&bundle, &po).unwrap(),
+        ] {
+            assert_eq!(translation.ocafile, format!("FROM {said}\n"));
+        }
+
+        let translation = from_xliff(
+            &bundle,
+            &xliff.replace("Powiedz &quot;hi&quot;", "Powiedz &quot;cześć&quot;"),
+        )
+        .unwrap();
+        assert_eq!(
+            translation.ocafile,
+            format!("FROM {said}\nADD LABEL pl ATTRS age=\"Powiedz \\\"cześć\\\"\"\n")
+        );
+        parse_from_string(translation.ocafile.clone()).unwrap();
+        let translated = apply(&bundle, &translation);
+        let view = BundleView::new(&translated);
+        let age = view.attribute("age").unwrap();
+        assert_eq!(age.label(Language::Pol), Some(r#"Powiedz \"cześć\""#));
+        assert_eq!(unescape(r"a\u0041\n\x"), "aA\n\\x");
+        assert_eq!(escape("a\"b\\c\n"), r#"a\"b\\c\n"#);
+    }
+
+    #[test]
+    fn import_skips_unchanged_and_unknown_units() {
+        let bundle = bundle();
+        let translation = import(
+            &bundle,
+            "pl",
+            vec![
+                ("label/age".to_string(), "Wiek".to_string()),
+                ("label/sex".to_string(), "Płeć".to_string()),
+                ("entry/sex/x".to_string(), "Inna".to_string()),
+                ("category/_cat-1_".to_string(), "Dane".to_string()),
+                ("meta/name".to_string(), String::new()),
+            ],
+            vec![],
+        )
+        .unwrap();
+
+        assert_eq!(
+            translation.ocafile,
+            format!(
+                "FROM {}\nADD LABEL pl ATTRS sex=\"Płeć\"\n",
+                bundle.said.as_ref().unwrap()
+            )
+        );
+        assert_eq!(
+            translation
+                .warnings
+                .iter()
+                .map(|warning| warning.pointer.as_str())
+                .collect::<Vec<_>>(),
+            vec!["entry/sex/x", "category/_cat-1_"]
+        );
+    }
+}
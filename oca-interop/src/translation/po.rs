@@ -0,0 +1,290 @@
+use super::{import, units, Translation};
+use crate::{Error, Warning};
+use isolang::Language;
+use oca_bundle_semantics::state::{dependencies::said_of, naming::lang_code, oca::OCABundle};
+
+/// Export translatable texts of the bundle in `source` language as gettext
+/// PO file, with unit ids as `msgctxt`. Existing translations in `target`
+/// language are filled in.
+pub fn to_po(bundle: &OCABundle, source: Language, target: Language) -> Result<String, Error> {
+    let said = said_of(bundle)?;
+    let mut po = format!(
+        "# Translation of OCA Bundle {said}\n\
+         msgid \"\"\n\
+         msgstr \"\"\n\
+         \"Language: {}\\n\"\n\
+         \"MIME-Version: 1.0\\n\"\n\
+         \"Content-Type: text/plain; charset=UTF-8\\n\"\n\
+         \"Content-Transfer-Encoding: 8bit\\n\"\n\
+         \"X-Source-Language: {}\\n\"\n\
+         \"X-OCA-Bundle: {said}\\n\"\n",
+        lang_code(&target),
+        lang_code(&source),
+    );
+    for unit in units(bundle, source, target) {
+        po.push_str(&format!(
+            "\nmsgctxt {}\nmsgid {}\nmsgstr {}\n",
+            quote(&unit.id),
+            quote(&unit.source),
+            quote(unit.target.as_deref().unwrap_or_default())
+        ));
+    }
+
+    Ok(po)
+}
+
+/// Read translated PO file into commands adding the translations, in the
+/// language of its `Language` header, on top of the bundle. Fuzzy entries
+/// are skipped.
+pub fn from_po(bundle: &OCABundle, po: &str) -> Result<Translation, Error> {
+    let mut language = None;
+    let mut warnings = vec![];
+    let mut translations = vec![];
+    for entry in entries(po)? {
+        if entry.msgid.is_empty() && entry.msgctxt.is_none() {
+            for (key, value) in entry.msgstr.lines().filter_map(|l| l.split_once(':')) {
+                match key.trim() {
+                    "Language" => language = Some(value.trim().to_string()),
+                    "X-OCA-Bundle" => {
+                        let said = bundle.said.as_ref().map(|said| said.to_string());
+                        if said.as_deref() != Some(value.trim()) {
+                            warnings.push(Warning {
+                                pointer: String::new(),
+                                message: format!(
+                                    "file was exported from another bundle {}",
+                                    value.trim()
+                                ),
+                            });
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            continue;
+        }
+        let id = entry.msgctxt.unwrap_or_default();
+        if entry.fuzzy {
+            if !entry.msgstr.is_empty() {
+                warnings.push(Warning {
+                    pointer: id,
+                    message: "fuzzy translation is skipped".to_string(),
+                });
+            }
+            continue;
+        }
+        translations.push((id, entry.msgstr));
+    }
+    let language = language
+        .filter(|language| !language.is_empty())
+        .ok_or_else(|| Error::InvalidTranslation {
+            message: "Language header is missing".to_string(),
+        })?;
+
+    import(bundle, &language, translations, warnings)
+}
+
+#[derive(Default)]
+struct Entry {
+    msgctxt: Option<String>,
+    msgid: String,
+    msgstr: String,
+    fuzzy: bool,
+}
+
+enum Field {
+    Context,
+    Id,
+    Str,
+    /// Plural forms aren't used by OCA.
+    Other,
+}
+
+fn entries(po: &str) -> Result<Vec<Entry>, Error> {
+    let mut entries = vec![];
+    let mut entry = Entry::default();
+    let mut field = None;
+    let mut started = false;
+    for (number, line) in po.lines().enumerate() {
+        let line = line.trim();
+        let error = |message: &str| Error::InvalidTranslation {
+            message: format!("line {}: {message}", number + 1),
+        };
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            // Obsolete entries are commented out with `#~`.
+            if comment.starts_with(',') && comment.contains("fuzzy") {
+                if started {
+                    entries.push(std::mem::take(&mut entry));
+                    started = false;
+                }
+                entry.fuzzy = true;
+            }
+            continue;
+        }
+        let (keyword, rest) = match line.split_once(char::is_whitespace) {
+            Some((keyword, rest)) if !line.starts_with('"') => (Some(keyword), rest.trim()),
+            _ => (None, line),
+        };
+        let value = unquote(rest).ok_or_else(|| error("invalid string"))?;
+        match keyword {
+            Some("msgctxt") | Some("msgid") if started && matches!(field, Some(Field::Str)) => {
+                entries.push(std::mem::take(&mut entry));
+            }
+            _ => (),
+        }
+        match keyword {
+            Some("msgctxt") => {
+                entry.msgctxt = Some(value);
+                field = Some(Field::Context);
+            }
+            Some("msgid") => {
+                entry.msgid = value;
+                field = Some(Field::Id);
+            }
+            Some("msgstr") | Some("msgstr[0]") => {
+                entry.msgstr = value;
+                field = Some(Field::Str);
+            }
+            Some(keyword) if keyword.starts_with("msg") => field = Some(Field::Other),
+            Some(keyword) => return Err(error(&format!("unknown keyword `{keyword}`"))),
+            None => match field {
+                Some(Field::Context) => entry
+                    .msgctxt
+                    .get_or_insert_with(String::new)
+                    .push_str(&value),
+                Some(Field::Id) => entry.msgid.push_str(&value),
+                Some(Field::Str) => entry.msgstr.push_str(&value),
+                Some(Field::Other) => (),
+                None => return Err(error("string outside of an entry")),
+            },
+        }
+        started = true;
+    }
+    if started {
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+fn quote(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\t', "\\t")
+        .replace('\r', "\\r");
+    match escaped.split_inclusive('\n').collect::<Vec<_>>()[..] {
+        [] => "\"\"".to_string(),
+        [line] if !line.ends_with('\n') => format!("\"{line}\""),
+        ref lines => std::iter::once("\"\"".to_string())
+            .chain(
+                lines
+                    .iter()
+                    .map(|line| format!("\"{}\"", line.replace('\n', "\\n"))),
+            )
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+fn unquote(text: &str) -> Option<String> {
+    let inner = text.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            't' => value.push('\t'),
+            'r' => value.push('\r'),
+            c => value.push(c),
+        }
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translation::tests::{apply, bundle};
+    use oca_bundle_semantics::state::view::BundleView;
+
+    #[test]
+    fn export_po() {
+        let bundle = bundle();
+        let said = bundle.said.clone().unwrap();
+        let po = to_po(&bundle, Language::Eng, Language::Pol).unwrap();
+
+        assert!(po.starts_with(&format!(
+            "# Translation of OCA Bundle {said}\nmsgid \"\"\nmsgstr \"\"\n\"Language: pl\\n\"\n"
+        )));
+        assert!(po.contains("\nmsgctxt \"information/age\"\nmsgid \"Age in years\"\nmsgstr \"\"\n"));
+        assert!(po.contains("\nmsgctxt \"label/age\"\nmsgid \"Age\"\nmsgstr \"Wiek\"\n"));
+    }
+
+    #[test]
+    fn import_po() {
+        let bundle = bundle();
+        let po = to_po(&bundle, Language::Eng, Language::Pol)
+            .unwrap()
+            .replace(
+                "msgid \"Sex\"\nmsgstr \"\"",
+                "msgid \"Sex\"\nmsgstr \"\"\n\"Płe\"\n\"ć\"",
+            )
+            .replace("Language: pl", "Language: pl_PL")
+            .replace(
+                "msgctxt \"entry/sex/f\"\nmsgid \"Female\"\nmsgstr \"\"",
+                "#, fuzzy\nmsgctxt \"entry/sex/f\"\nmsgid \"Female\"\nmsgstr \"Kobieta\"",
+            )
+            .replace(
+                "msgctxt \"entry/sex/m\"\nmsgid \"Male\"\nmsgstr \"\"",
+                "msgctxt \"entry/sex/m\"\nmsgid \"Male\"\nmsgstr \"\\\"M\\\"\"",
+            );
+
+        let translation = from_po(&bundle, &po).unwrap();
+        assert_eq!(translation.language, "pl");
+        assert_eq!(
+            translation
+                .warnings
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["entry/sex/f: fuzzy translation is skipped"]
+        );
+
+        let translated = apply(&bundle, &translation);
+        let view = BundleView::new(&translated);
+        let sex = view.attribute("sex").unwrap();
+        assert_eq!(sex.label(Language::Pol), Some("Płeć"));
+        // Quotes are escaped as in OCAFILE strings.
+        assert_eq!(sex.entry(Language::Pol, "m"), Some("\\\"M\\\""));
+        assert_eq!(sex.entry(Language::Pol, "f"), None);
+    }
+
+    #[test]
+    fn quoting() {
+        for text in ["", "a \"b\"", "line\nnext\n", "tab\tend\\"] {
+            let quoted = quote(text);
+            let value: String = quoted.lines().map(|line| unquote(line).unwrap()).collect();
+            assert_eq!(value, text);
+        }
+    }
+
+    #[test]
+    fn missing_language() {
+        assert_eq!(
+            from_po(
+                &bundle(),
+                "msgctxt \"label/age\"\nmsgid \"Age\"\nmsgstr \"Wiek\"\n"
+            ),
+            Err(Error::InvalidTranslation {
+                message: "Language header is missing".to_string()
+            })
+        );
+    }
+}
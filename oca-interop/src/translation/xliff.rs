@@ -0,0 +1,272 @@
+use super::{import, units, Translation};
+use crate::{Error, Warning};
+use isolang::Language;
+use oca_bundle_semantics::state::{dependencies::said_of, naming::lang_code, oca::OCABundle};
+use quick_xml::{
+    escape::escape,
+    events::{BytesStart, Event},
+    Reader,
+};
+
+const NAMESPACE: &str = "urn:oasis:names:tc:xliff:document:2.0";
+
+/// Export translatable texts of the bundle in `source` language as XLIFF
+/// 2.0 document with a file identified by the bundle SAID. Existing
+/// translations in `target` language are filled in.
+pub fn to_xliff(bundle: &OCABundle, source: Language, target: Language) -> Result<String, Error> {
+    let said = said_of(bundle)?;
+    let mut xliff = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <xliff xmlns=\"{NAMESPACE}\" version=\"2.0\" srcLang=\"{}\" trgLang=\"{}\">\n  \
+         <file id=\"{said}\">\n",
+        lang_code(&source),
+        lang_code(&target),
+    );
+    for unit in units(bundle, source, target) {
+        let state = if unit.target.is_some() {
+            "translated"
+        } else {
+            "initial"
+        };
+        xliff.push_str(&format!(
+            "    <unit id=\"{}\">\n      <segment state=\"{state}\">\n        \
+             <source>{}</source>\n",
+            escape(&unit.id),
+            escape(&unit.source)
+        ));
+        if let Some(target) = unit.target {
+            xliff.push_str(&format!("        <target>{}</target>\n", escape(&target)));
+        }
+        xliff.push_str("      </segment>\n    </unit>\n");
+    }
+    xliff.push_str("  </file>\n</xliff>\n");
+
+    Ok(xliff)
+}
+
+/// Read translated XLIFF 2.0 document into commands adding the targets, in
+/// its `trgLang`, on top of the bundle. Units without target are skipped.
+pub fn from_xliff(bundle: &OCABundle, xliff: &str) -> Result<Translation, Error> {
+    let mut reader = Reader::from_str(xliff);
+    let mut language = None;
+    let mut warnings = vec![];
+    let mut translations = vec![];
+    let mut unit: Option<(String, String)> = None;
+    let mut in_target = false;
+    loop {
+        let event = reader.read_event().map_err(|e| Error::InvalidTranslation {
+            message: format!("at {}: {e}", reader.buffer_position()),
+        })?;
+        // Empty `<target/>` has no end event and no text.
+        let start = matches!(event, Event::Start(_));
+        match event {
+            Event::Start(element) | Event::Empty(element) => match element.local_name().as_ref() {
+                b"xliff" => language = attribute(&reader, &element, "trgLang")?,
+                b"file" => {
+                    let id = attribute(&reader, &element, "id")?;
+                    let said = bundle.said.as_ref().map(|said| said.to_string());
+                    if id.is_some() && id != said {
+                        warnings.push(Warning {
+                            pointer: String::new(),
+                            message: format!(
+                                "file {} was exported from another bundle",
+                                id.unwrap_or_default()
+                            ),
+                        });
+                    }
+                }
+                b"unit" => {
+                    let id = attribute(&reader, &element, "id")?.ok_or_else(|| {
+                        Error::InvalidTranslation {
+                            message: "unit without id".to_string(),
+                        }
+                    })?;
+                    unit = Some((id, String::new()));
+                }
+                b"target" => in_target = start,
+                _ => (),
+            },
+            Event::End(element) => match element.local_name().as_ref() {
+                b"unit" => translations.extend(unit.take()),
+                b"target" => in_target = false,
+                _ => (),
+            },
+            Event::Text(text) if in_target => {
+                let text = text.unescape().map_err(|e| Error::InvalidTranslation {
+                    message: e.to_string(),
+                })?;
+                if let Some((_, target)) = unit.as_mut() {
+                    target.push_str(&text);
+                }
+            }
+            Event::CData(text) if in_target => {
+                if let Some((_, target)) = unit.as_mut() {
+                    target.push_str(&String::from_utf8_lossy(&text));
+                }
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+    let language = language.ok_or_else(|| Error::InvalidTranslation {
+        message: "trgLang is missing".to_string(),
+    })?;
+
+    import(bundle, &language, translations, warnings)
+}
+
+fn attribute(
+    reader: &Reader<&[u8]>,
+    element: &BytesStart,
+    name: &str,
+) -> Result<Option<String>, Error> {
+    let error = |e: quick_xml::Error| Error::InvalidTranslation {
+        message: e.to_string(),
+    };
+    match element.try_get_attribute(name).map_err(error)? {
+        Some(attribute) => Ok(Some(
+            attribute
+                .decode_and_unescape_value(reader)
+                .map_err(error)?
+                .to_string(),
+        )),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translation::tests::{apply, bundle};
+    use oca_bundle_semantics::state::view::BundleView;
+
+    #[test]
+    fn export_xliff() {
+        let bundle = bundle();
+        let xliff = to_xliff(&bundle, Language::Eng, Language::Pol).unwrap();
+
+        assert_eq!(
+            xliff,
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff xmlns="urn:oasis:names:tc:xliff:document:2.0" version="2.0" srcLang="en" trgLang="pl">
+  <file id="{}">
+    <unit id="meta/description">
+      <segment state="initial">
+        <source>Person data</source>
+      </segment>
+    </unit>
+    <unit id="meta/name">
+      <segment state="initial">
+        <source>Person</source>
+      </segment>
+    </unit>
+    <unit id="label/age">
+      <segment state="translated">
+        <source>Age</source>
+        <target>Wiek</target>
+      </segment>
+    </unit>
+    <unit id="information/age">
+      <segment state="initial">
+        <source>Age in years</source>
+      </segment>
+    </unit>
+    <unit id="label/sex">
+      <segment state="initial">
+        <source>Sex</source>
+      </segment>
+    </unit>
+    <unit id="entry/sex/m">
+      <segment state="initial">
+        <source>Male</source>
+      </segment>
+    </unit>
+    <unit id="entry/sex/f">
+      <segment state="initial">
+        <source>Female</source>
+      </segment>
+    </unit>
+  </file>
+</xliff>
+"#,
+                bundle.said.as_ref().unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn import_xliff() {
+        let bundle = bundle();
+        let xliff = to_xliff(&bundle, Language::Eng, Language::Pol)
+            .unwrap()
+            .replace(
+                "<source>Person</source>",
+                "<source>Person</source><target>Osoba</target>",
+            )
+            .replace(
+                "<source>Age in years</source>",
+                "<source/><target>Wiek w &quot;latach&quot;</target>",
+            )
+            .replace(
+                "<source>Male</source>",
+                "<source/><target><![CDATA[Mężczyzna]]></target>",
+            );
+
+        let translation = from_xliff(&bundle, &xliff).unwrap();
+        assert_eq!(translation.language, "pl");
+        assert!(translation.warnings.is_empty());
+        assert_eq!(
+            translation.ocafile,
+            format!(
+                r#"FROM {}
+ADD META pl PROPS name="Osoba"
+ADD INFORMATION pl ATTRS age="Wiek w \"latach\""
+ADD ENTRY pl ATTRS sex={{"m": "Mężczyzna"}}
+"#,
+                bundle.said.as_ref().unwrap()
+            )
+        );
+
+        let translated = apply(&bundle, &translation);
+        let view = BundleView::new(&translated);
+        assert_eq!(view.meta(Language::Pol, "name"), Some("Osoba"));
+        let sex = view.attribute("sex").unwrap();
+        assert_eq!(sex.entry(Language::Pol, "m"), Some("Mężczyzna"));
+    }
+
+    #[test]
+    fn import_xliff_with_empty_targets() {
+        let bundle = bundle();
+        let xliff = to_xliff(&bundle, Language::Eng, Language::Pol)
+            .unwrap()
+            .replace(
+                "</source>\n      </segment>",
+                "</source>\n        <target/>\n      </segment>",
+            )
+            .replace("<target>Wiek</target>", "<target/>")
+            .replace(
+                "<source>Sex</source>\n        <target/>",
+                "<source>Sex</source>\n        <target>Płeć</target>",
+            )
+            .replace("trgLang=\"pl\"", "trgLang=\"pl-PL\"");
+
+        let translation = from_xliff(&bundle, &xliff).unwrap();
+        assert_eq!(translation.language, "pl");
+        assert_eq!(
+            translation.ocafile,
+            format!(
+                "FROM {}\nADD LABEL pl ATTRS sex=\"Płeć\"\n",
+                bundle.said.as_ref().unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_xliff() {
+        assert!(matches!(
+            from_xliff(&bundle(), "<xliff><file></xliff>"),
+            Err(Error::InvalidTranslation { .. })
+        ));
+    }
+}
@@ -0,0 +1,21 @@
+use serde::Serialize;
+use std::fmt;
+
+/// Part of the schema which couldn't be mapped to OCA.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Warning {
+    /// JSON Pointer to the schema location.
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pointer = if self.pointer.is_empty() {
+            "#"
+        } else {
+            &self.pointer
+        };
+        write!(f, "{}: {}", pointer, self.message)
+    }
+}
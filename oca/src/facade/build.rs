@@ -14,9 +14,11 @@ use log::debug;
 use oca_ast_semantics::ast::{OCAAst, ObjectKind, RefValue, ReferenceAttrType};
 use oca_bundle_semantics::build::{OCABuild, OCABuildStep};
 use oca_bundle_semantics::state::oca::OCABundle;
+#[cfg(feature = "local-references")]
+use oca_bundle_semantics::state::said_options::SaidOptions;
+use oca_bundle_semantics::state::validator::Validator;
 use oca_bundle_semantics::Encode;
 use oca_dag_semantics::build_core_db_model;
-use said::derivation::HashFunctionCode;
 use said::sad::SerializationFormats;
 
 #[derive(thiserror::Error, Debug, serde::Serialize)]
@@ -37,6 +39,8 @@ pub enum ValidationError {
     OCABundleBuild(#[from] oca_bundle_semantics::build::Error),
     #[error(transparent)]
     TransformationBuild(#[from] transformation_file::build::Error),
+    #[error(transparent)]
+    OCABundleSemantics(#[from] Box<oca_bundle_semantics::state::validator::Error>),
     #[error("Error at line {line_number} ({raw_line}): {message}")]
     InvalidCommand {
         #[serde(rename = "ln")]
@@ -92,11 +96,15 @@ impl Facade {
     #[cfg(not(feature = "local-references"))]
     pub fn validate_ocafile(&self, ocafile: String) -> Result<OCABuild, Vec<ValidationError>> {
         let (base, oca_ast) = Self::parse_and_check_base(self.storage(), ocafile)?;
-        oca_bundle_semantics::build::from_ast(base, &oca_ast).map_err(|e| {
-            e.iter()
-                .map(|e| ValidationError::OCABundleBuild(e.clone()))
-                .collect::<Vec<_>>()
-        })
+        let oca_build =
+            oca_bundle_semantics::build::from_ast_with(base, &oca_ast, &self.said_options)
+                .map_err(|e| {
+                    e.iter()
+                        .map(|e| ValidationError::OCABundleBuild(e.clone()))
+                        .collect::<Vec<_>>()
+                })?;
+        self.validate_semantics(&oca_build)?;
+        Ok(oca_build)
     }
 
     /// Validate ocafile using external references for dereferencing `refn`.  It
@@ -109,7 +117,14 @@ impl Facade {
         references: &mut R,
     ) -> Result<OCABuild, Vec<ValidationError>> {
         let (base, oca_ast) = Self::parse_and_check_base(self.storage(), ocafile)?;
-        Self::oca_ast_to_oca_build_with_references(base, oca_ast, references)
+        let oca_build = Self::oca_ast_to_oca_build_with_references(
+            base,
+            oca_ast,
+            references,
+            &self.said_options,
+        )?;
+        self.validate_semantics(&oca_build)?;
+        Ok(oca_build)
     }
 
     /// Validate ocafile using internal references for dereferencing `refn`.
@@ -118,7 +133,14 @@ impl Facade {
     #[cfg(feature = "local-references")]
     pub fn validate_ocafile(&mut self, ocafile: String) -> Result<OCABuild, Vec<ValidationError>> {
         let (base, oca_ast) = Self::parse_and_check_base(self.storage(), ocafile)?;
-        Self::oca_ast_to_oca_build_with_references(base, oca_ast, &mut self.db)
+        let oca_build = Self::oca_ast_to_oca_build_with_references(
+            base,
+            oca_ast,
+            &mut self.db,
+            &self.said_options,
+        )?;
+        self.validate_semantics(&oca_build)?;
+        Ok(oca_build)
     }
 
     pub fn build(&mut self, oca_build: &OCABuild) -> Result<OCABundle, Error> {
@@ -153,6 +175,20 @@ impl Facade {
         }
     }
 
+    /// Run semantic validation on the built bundle, keeping the typed errors
+    /// so callers can point at the exact attribute, overlay or SAID.
+    fn validate_semantics(&self, oca_build: &OCABuild) -> Result<(), Vec<ValidationError>> {
+        Validator::new()
+            .with_said_options(self.said_options.clone())
+            .validate(&oca_build.oca_bundle)
+            .map_err(|errors| {
+                errors
+                    .into_iter()
+                    .map(|e| ValidationError::OCABundleSemantics(Box::new(e)))
+                    .collect()
+            })
+    }
+
     fn parse_and_check_base(
         storage: &dyn DataStorage,
         ocafile: String,
@@ -220,22 +256,23 @@ impl Facade {
         base: Option<OCABundle>,
         mut oca_ast: OCAAst,
         references: &mut R,
+        said_options: &SaidOptions,
     ) -> Result<OCABuild, Vec<ValidationError>> {
         // Dereference (refn -> refs) the AST before it start processing bundle steps, otherwise the SAID would
         // not match.
         local_references::replace_refn_with_refs(&mut oca_ast, references).map_err(|e| vec![e])?;
 
-        let oca_build = oca_bundle_semantics::build::from_ast(base, &oca_ast).map_err(|e| {
-            e.iter()
-                .map(|e| ValidationError::OCABundleBuild(e.clone()))
-                .collect::<Vec<_>>()
-        })?;
+        let oca_build = oca_bundle_semantics::build::from_ast_with(base, &oca_ast, said_options)
+            .map_err(|e| {
+                e.iter()
+                    .map(|e| ValidationError::OCABundleBuild(e.clone()))
+                    .collect::<Vec<_>>()
+            })?;
 
         let schema_name = oca_ast.meta.get("name");
         debug!("Schema name found: {:?}", schema_name);
 
-        if schema_name.is_some() {
-            let schema_name = schema_name.unwrap();
+        if let Some(schema_name) = schema_name {
             let said = oca_build.oca_bundle.said.clone().unwrap().to_string();
             references.save(schema_name, said.clone());
         };
@@ -244,7 +281,7 @@ impl Facade {
 
     fn build_cache(&self, oca_bundle: &OCABundle) {
         let oca_bundle_cache_repo = OCABundleCacheRepo::new(self.connection());
-        let oca_bundle_cache_record = OCABundleCacheRecord::new(oca_bundle);
+        let oca_bundle_cache_record = OCABundleCacheRecord::new(oca_bundle, &self.said_options);
         oca_bundle_cache_repo.insert(oca_bundle_cache_record);
 
         let capture_base_cache_repo = CaptureBaseCacheRepo::new(self.connection());
@@ -307,13 +344,13 @@ impl Facade {
             )
             .unwrap();
 
-        let code = HashFunctionCode::Blake3_256;
+        let code = &self.said_options.code;
         let format = SerializationFormats::JSON;
         self.db_cache
             .insert(
                 Namespace::OCABundlesJSON,
                 &result_bundle.said.clone().unwrap().to_string(),
-                &result_bundle.encode(&code, &format).unwrap(),
+                &result_bundle.encode(code, &format).unwrap(),
             )
             .unwrap();
         self.db_cache
@@ -1,6 +1,8 @@
 use std::io::Read;
 
+use oca_bundle_semantics::state::integrity::{DigestCheck, IntegrityReport};
 use oca_bundle_semantics::state::oca::OCABundle as StructuralBundle;
+use oca_bundle_semantics::state::said_options::SaidOptions;
 use said::derivation::HashFunctionCode;
 use said::version::SerializationInfo;
 use said::{sad::SerializationFormats, sad::SAD};
@@ -8,11 +10,44 @@ use serde::{Deserialize, Serialize};
 
 pub type GenericError = Box<dyn std::error::Error + Sync + Send>;
 
+#[derive(thiserror::Error, Debug)]
+pub enum LoadError {
+    #[error("Failed to parse bundle: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("Bundle integrity check failed")]
+    Integrity(Box<BundleIntegrityReport>),
+}
+
 pub fn load_oca(source: &mut dyn Read) -> Result<Bundle, GenericError> {
     let oca: Bundle = serde_json::from_reader(source)?;
     Ok(oca)
 }
 
+/// Load bundle and verify SAIDs of the bundle and all its elements.
+pub fn load_oca_strict(source: &mut dyn Read) -> Result<Bundle, LoadError> {
+    let oca: Bundle = serde_json::from_reader(source)?;
+    let report = oca.verify();
+    if !report.is_valid() {
+        return Err(LoadError::Integrity(Box::new(report)));
+    }
+    Ok(oca)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BundleIntegrityReport {
+    pub bundle: DigestCheck,
+    pub structural: Option<IntegrityReport>,
+    pub transformations: Vec<DigestCheck>,
+}
+
+impl BundleIntegrityReport {
+    pub fn is_valid(&self) -> bool {
+        self.bundle.is_valid()
+            && self.structural.as_ref().is_none_or(|s| s.is_valid())
+            && self.transformations.iter().all(|t| t.is_valid())
+    }
+}
+
 #[derive(Debug)]
 pub enum BundleElement {
     Structural(StructuralBundle),
@@ -58,9 +93,47 @@ impl Bundle {
     }
 
     pub fn fill_said(&mut self) {
-        let code = HashFunctionCode::Blake3_256;
-        let format = SerializationFormats::JSON;
-        self.compute_digest(&code, &format);
+        self.fill_said_with(&SaidOptions::default());
+    }
+
+    pub fn fill_said_with(&mut self, options: &SaidOptions) {
+        self.compute_digest(&options.code, &options.format);
+    }
+
+    /// Verify SAIDs assuming the digest algorithm of the bundle SAID and
+    /// JSON serialization.
+    pub fn verify(&self) -> BundleIntegrityReport {
+        let options = match &self.said {
+            Some(said) => {
+                SaidOptions::new(said.derivation.clone().into(), SerializationFormats::JSON)
+            }
+            None => SaidOptions::default(),
+        };
+        self.verify_with(&options)
+    }
+
+    pub fn verify_with(&self, options: &SaidOptions) -> BundleIntegrityReport {
+        let mut recalculated_bundle = self.clone();
+        recalculated_bundle.fill_said_with(options);
+
+        let transformations = self
+            .transformations
+            .iter()
+            .map(|transformation| {
+                let mut recalculated_transformation = transformation.clone();
+                recalculated_transformation.fill_said_with(&options.code, &options.format);
+                DigestCheck::new(
+                    transformation.said.clone(),
+                    recalculated_transformation.said,
+                )
+            })
+            .collect();
+
+        BundleIntegrityReport {
+            bundle: DigestCheck::new(self.said.clone(), recalculated_bundle.said),
+            structural: self.structural.as_ref().map(|s| s.verify_with(options)),
+            transformations,
+        }
     }
 
     pub fn encode(&self) -> Result<String, serde_json::Error> {
@@ -98,3 +171,40 @@ impl Default for Bundle {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use transformation_file::state::Transformation;
+
+    fn bundle_json() -> String {
+        let structural = crate::facade::build::build_from_ocafile(
+            "ADD ATTRIBUTE name=Text\nADD LABEL en ATTRS name=\"Name\"".to_string(),
+        )
+        .unwrap();
+        let mut transformation = Transformation::new();
+        transformation.set_source(structural.said.clone().unwrap().to_string());
+        transformation.fill_said();
+
+        let mut bundle = Bundle::new();
+        bundle.add(BundleElement::Structural(structural));
+        bundle.add(BundleElement::Transformation(transformation));
+        bundle.fill_said();
+        serde_json::to_string(&bundle).unwrap()
+    }
+
+    #[test]
+    fn verify_bundle_on_load() {
+        let data = bundle_json();
+        let bundle = load_oca_strict(&mut data.as_bytes()).unwrap();
+        assert!(bundle.verify().is_valid());
+
+        let data = data.replace(r#""Name""#, r#""Surname""#);
+        let Err(LoadError::Integrity(report)) = load_oca_strict(&mut data.as_bytes()) else {
+            panic!("expected integrity error");
+        };
+        assert!(!report.bundle.is_valid());
+        assert!(!report.structural.unwrap().is_valid());
+        assert!(report.transformations[0].is_valid());
+    }
+}
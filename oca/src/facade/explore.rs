@@ -1,11 +1,51 @@
 use crate::data_storage::Namespace;
-use oca_ast_semantics::ast::{BundleContent, CaptureContent, Content, ObjectKind, RefValue};
+use oca_ast_semantics::ast::{
+    BundleContent, CaptureContent, Content, ObjectKind, OverlayType, RefValue,
+};
 use oca_bundle_semantics::state::oca::OCABundle;
 use serde::{ser::SerializeStruct, Serialize};
 use std::collections::HashSet;
 
 use super::Facade;
 
+/// Code of custom overlays, which is followed by the length and the
+/// overlay type, e.g. `spec/overlays/consent/1.1`.
+const CUSTOM_OVERLAY: u8 = 23;
+
+/// Stored object kind. Custom overlays keep their overlay type, as the code
+/// alone doesn't convert back.
+pub(crate) fn kind_to_bytes(kind: &ObjectKind) -> Vec<u8> {
+    match kind {
+        ObjectKind::Overlay(overlay_type @ OverlayType::Custom { .. }, _) => {
+            let overlay_type = serde_json::to_value(overlay_type).unwrap();
+            let overlay_type = overlay_type.as_str().unwrap().as_bytes();
+            let mut bytes = vec![CUSTOM_OVERLAY, overlay_type.len().try_into().unwrap()];
+            bytes.extend(overlay_type);
+            bytes
+        }
+        kind => vec![kind.clone().into()],
+    }
+}
+
+/// Object kind stored at the beginning of `bytes`, with the number of bytes
+/// it takes.
+pub(crate) fn kind_from_bytes(bytes: &[u8]) -> (ObjectKind, usize) {
+    match bytes[0] {
+        CUSTOM_OVERLAY => {
+            let len = bytes[1] as usize;
+            let overlay_type = String::from_utf8(bytes[2..2 + len].to_vec()).unwrap();
+            let overlay_type: OverlayType =
+                serde_json::from_value(serde_json::Value::String(overlay_type)).unwrap();
+            let content = Content {
+                attributes: None,
+                properties: None,
+            };
+            (ObjectKind::Overlay(overlay_type, content), 2 + len)
+        }
+        code => (code.into(), 1),
+    }
+}
+
 impl Facade {
     pub fn explore(&self, said: String) -> Option<Relationship> {
         let relations_u8_res = self.db.get(Namespace::OCARelations, &said);
@@ -26,35 +66,32 @@ impl Facade {
         self.db.insert(
             Namespace::OCARelations,
             &format!("{}.metadata", oca_bundle.said.clone().unwrap()),
-            &[ObjectKind::OCABundle(BundleContent {
+            &kind_to_bytes(&ObjectKind::OCABundle(BundleContent {
                 said: oca_ast_semantics::ast::ReferenceAttrType::Reference(RefValue::Name(
                     "".to_string(),
                 )),
-            })
-            .into()],
+            })),
         )?;
         self.db.insert(
             Namespace::OCARelations,
             &format!("{}.metadata", oca_bundle.capture_base.said.clone().unwrap()),
-            &[ObjectKind::CaptureBase(CaptureContent {
+            &kind_to_bytes(&ObjectKind::CaptureBase(CaptureContent {
                 attributes: None,
                 properties: None,
                 flagged_attributes: None,
-            })
-            .into()],
+            })),
         )?;
         oca_bundle.overlays.iter().for_each(|overlay| {
             let _ = self.db.insert(
                 Namespace::OCARelations,
                 &format!("{}.metadata", overlay.said().clone().unwrap()),
-                &[ObjectKind::Overlay(
+                &kind_to_bytes(&ObjectKind::Overlay(
                     overlay.overlay_type().clone(),
                     Content {
                         attributes: None,
                         properties: None,
                     },
-                )
-                .into()],
+                )),
             );
         });
 
@@ -129,7 +166,7 @@ impl Facade {
             .get(Namespace::OCARelations, &format!("{}.metadata", said))
             .unwrap();
 
-        (*object_type.unwrap().first().unwrap()).into()
+        kind_from_bytes(&object_type.unwrap()).0
     }
 }
 
@@ -157,7 +194,7 @@ impl From<Relationship> for Vec<u8> {
         let mut result: Vec<u8> = Vec::new();
 
         val.relations.iter().for_each(|object| {
-            result.push(object.object_type.clone().into());
+            result.extend(kind_to_bytes(&object.object_type));
             result.push(object.said.len().try_into().unwrap());
             result.extend(object.said.as_bytes());
         });
@@ -177,30 +214,21 @@ impl From<Vec<u8>> for Relationship {
             }),
         });
 
-        let mut tmp_val = val.clone();
+        let mut tmp_val = val.as_slice();
         while !tmp_val.is_empty() {
-            let said_len = tmp_val[1];
-            let (o_u8, split_val) = tmp_val.split_at(2 + said_len as usize);
-            result.add_relation(OCAObject::from(o_u8.to_vec()));
-            tmp_val = split_val.to_vec();
+            let (object_type, kind_len) = kind_from_bytes(tmp_val);
+            let said_len = tmp_val[kind_len] as usize;
+            let said_start = kind_len + 1;
+            let said =
+                String::from_utf8(tmp_val[said_start..said_start + said_len].to_vec()).unwrap();
+            result.add_relation(OCAObject { said, object_type });
+            tmp_val = &tmp_val[said_start + said_len..];
         }
 
         result
     }
 }
 
-impl From<Vec<u8>> for OCAObject {
-    fn from(val: Vec<u8>) -> Self {
-        let object_type = val[0];
-        let said_len = val[1];
-        let said = String::from_utf8(val[2..2 + said_len as usize].to_vec()).unwrap();
-        Self {
-            said,
-            object_type: object_type.into(),
-        }
-    }
-}
-
 #[derive(Eq, PartialEq, Hash, Clone, Debug)]
 pub struct OCAObject {
     pub said: String,
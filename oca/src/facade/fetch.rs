@@ -1,4 +1,4 @@
-use super::Facade;
+use super::{explore::kind_from_bytes, Facade};
 use crate::data_storage::Namespace;
 #[cfg(feature = "local-references")]
 use crate::local_references;
@@ -8,6 +8,7 @@ use crate::{
 };
 use oca_ast_semantics::ast::{self, OCAAst, ObjectKind, RefValue};
 use oca_bundle_semantics::build::OCABuildStep;
+use oca_bundle_semantics::state::merge::{merge_with, Merge};
 use oca_bundle_semantics::state::oca::{capture_base::CaptureBase, DynOverlay, OCABundle};
 use said::{
     derivation::HashFunctionCode,
@@ -232,7 +233,7 @@ impl Facade {
                     errors.push(e.to_string());
                     errors.clone()
                 })?;
-            let o_type: ObjectKind = (*r_type.unwrap().first().unwrap()).into();
+            let (o_type, _) = kind_from_bytes(&r_type.unwrap());
             match o_type {
                 ObjectKind::CaptureBase(_) => result.push(OCAObject::CaptureBase(
                     serde_json::from_str::<CaptureBase>(&object_str).map_err(|e| {
@@ -361,6 +362,55 @@ impl Facade {
         let oca_ast = bundle.to_ast();
         Ok(oca_file_semantics::ocafile::generate_from_ast(&oca_ast))
     }
+
+    /// Generate OCAFILE which, applied on `from` bundle, builds `to` bundle
+    pub fn get_oca_bundle_patch(
+        &self,
+        from: SelfAddressingIdentifier,
+        to: SelfAddressingIdentifier,
+    ) -> Result<String, Vec<String>> {
+        let from = get_oca_bundle(self.storage(), from, false)?.bundle;
+        let to = get_oca_bundle(self.storage(), to, false)?.bundle;
+        let oca_ast =
+            oca_bundle_semantics::state::patch::patch_with(&from, &to, &self.said_options)
+                .map_err(|errors| errors.iter().map(|e| e.to_string()).collect::<Vec<_>>())?;
+        Ok(oca_file_semantics::ocafile::generate_from_ast(&oca_ast))
+    }
+
+    /// Find the latest bundle both given bundles were built from
+    pub fn find_common_ancestor(
+        &self,
+        ours: SelfAddressingIdentifier,
+        theirs: SelfAddressingIdentifier,
+    ) -> Result<Option<SelfAddressingIdentifier>, Vec<String>> {
+        let ours_history = self
+            .get_oca_bundle_steps(ours)?
+            .into_iter()
+            .filter_map(|step| step.result.said)
+            .collect::<Vec<_>>();
+        let theirs_history = self.get_oca_bundle_steps(theirs)?;
+        Ok(theirs_history
+            .into_iter()
+            .rev()
+            .filter_map(|step| step.result.said)
+            .find(|said| ours_history.contains(said)))
+    }
+
+    /// Merge `theirs` bundle into `ours` using their common ancestor
+    pub fn merge_oca_bundles(
+        &self,
+        ours: SelfAddressingIdentifier,
+        theirs: SelfAddressingIdentifier,
+    ) -> Result<Merge, Vec<String>> {
+        let base = self
+            .find_common_ancestor(ours.clone(), theirs.clone())?
+            .ok_or_else(|| vec![format!("No common ancestor of {} and {}", ours, theirs)])?;
+        let base = get_oca_bundle(self.storage(), base, false)?.bundle;
+        let ours = get_oca_bundle(self.storage(), ours, false)?.bundle;
+        let theirs = get_oca_bundle(self.storage(), theirs, false)?.bundle;
+        merge_with(&base, &ours, &theirs, &self.said_options)
+            .map_err(|errors| errors.iter().map(|e| e.to_string()).collect::<Vec<_>>())
+    }
 }
 
 pub fn get_oca_bundle(
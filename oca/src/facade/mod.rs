@@ -2,6 +2,7 @@ use rusqlite::Params;
 
 use crate::data_storage::DataStorage;
 use crate::repositories::SQLiteConfig;
+use oca_bundle_semantics::state::said_options::SaidOptions;
 use std::borrow::Borrow;
 use std::sync::{Arc, Mutex};
 
@@ -37,6 +38,7 @@ pub struct Facade {
     db: Box<dyn DataStorage>,
     db_cache: Box<dyn DataStorage>,
     connection: Connection,
+    said_options: SaidOptions,
 }
 
 impl Facade {
@@ -63,9 +65,21 @@ impl Facade {
             db,
             db_cache,
             connection: Connection::new(&cache_path),
+            said_options: SaidOptions::default(),
         }
     }
 
+    /// Set digest algorithm and serialization format used for SAIDs of
+    /// bundles built by this facade. Stored bundles are always JSON encoded.
+    pub fn with_said_options(mut self, said_options: SaidOptions) -> Self {
+        self.said_options = said_options;
+        self
+    }
+
+    pub fn said_options(&self) -> &SaidOptions {
+        &self.said_options
+    }
+
     pub(crate) fn connection(&self) -> Connection {
         self.connection.clone()
     }
@@ -2,6 +2,7 @@ pub mod data_storage;
 pub mod facade;
 pub mod repositories;
 pub use facade::Facade;
+pub use oca_bundle_semantics::state::said_options::SaidOptions;
 pub use oca_bundle_semantics::{Encode as EncodeBundle, HashFunctionCode, SerializationFormats};
 #[cfg(feature = "local-references")]
 pub(crate) mod local_references;
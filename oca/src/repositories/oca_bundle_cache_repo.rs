@@ -1,5 +1,6 @@
+use oca_bundle_semantics::state::said_options::SaidOptions;
 use oca_bundle_semantics::{state::oca::OCABundle, Encode};
-use said::{derivation::HashFunctionCode, sad::SerializationFormats};
+use said::sad::SerializationFormats;
 
 use crate::facade::Connection;
 
@@ -10,12 +11,12 @@ pub struct OCABundleCacheRecord {
 }
 
 impl OCABundleCacheRecord {
-    pub fn new(oca_bundle: &OCABundle) -> Self {
-        let code = HashFunctionCode::Blake3_256;
+    pub fn new(oca_bundle: &OCABundle, said_options: &SaidOptions) -> Self {
         let format = SerializationFormats::JSON;
         Self {
             said: oca_bundle.said.clone().unwrap().to_string(),
-            oca_bundle: String::from_utf8(oca_bundle.encode(&code, &format).unwrap()).unwrap(),
+            oca_bundle: String::from_utf8(oca_bundle.encode(&said_options.code, &format).unwrap())
+                .unwrap(),
         }
     }
 }
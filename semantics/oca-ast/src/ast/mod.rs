@@ -159,6 +159,9 @@ pub enum OverlayType {
     Sensitivity(String),
     Link(String),
     AttributeFraming(String),
+    /// Overlay not defined by the OCA specification, e.g. community one
+    /// handled by a plugin.
+    Custom { name: String, version: String },
 }
 
 impl Serialize for OverlayType {
@@ -222,6 +225,8 @@ impl Serialize for OverlayType {
             }
             OverlayType::AttributeFraming(v) => serializer
                 .serialize_str(&format!("spec/overlays/attribute_framing/{v}")),
+            OverlayType::Custom { name, version } => serializer
+                .serialize_str(&format!("spec/overlays/{name}/{version}")),
         }
     }
 }
@@ -308,6 +313,7 @@ impl fmt::Display for OverlayType {
             OverlayType::Sensitivity(_) => write!(f, "Sensitivity"),
             OverlayType::Link(_) => write!(f, "Link"),
             OverlayType::AttributeFraming(_) => write!(f, "AttributeFraming"),
+            OverlayType::Custom { name, .. } => write!(f, "{name}"),
         }
     }
 }
@@ -348,7 +354,10 @@ impl<'de> Deserialize<'de> for OverlayType {
                 "sensitivity" => Ok(OverlayType::Sensitivity(v)),
                 "link" => Ok(OverlayType::Link(v)),
                 "attribute_framing" => Ok(OverlayType::AttributeFraming(v)),
-                _ => Err(serde::de::Error::custom("Unknown overlay type")),
+                name => Ok(OverlayType::Custom {
+                    name: name.to_string(),
+                    version: v,
+                }),
             }
         } else {
             Err(serde::de::Error::custom("Invalid overlay type format"))
@@ -865,6 +874,9 @@ impl From<ObjectKind> for u8 {
             ObjectKind::Overlay(OverlayType::Sensitivity(_), _) => 20,
             ObjectKind::Overlay(OverlayType::Link(_), _) => 21,
             ObjectKind::Overlay(OverlayType::AttributeFraming(_), _) => 22,
+            // Name of custom overlay is lost, so it doesn't convert back;
+            // storage follows the code with the overlay type.
+            ObjectKind::Overlay(OverlayType::Custom { .. }, _) => 23,
         }
     }
 }
@@ -112,7 +112,9 @@ fn validate_1_0_0(ast: &OCAAst, command: Command) -> Result<bool, Error> {
 }
 
 /// Check rule for remove command
-/// Rule would be valid if attributes which commands tries to remove exist in the stack
+/// Rule would be valid if attributes which commands tries to remove exist in the stack.
+/// When the stack starts from another bundle, its content is unknown here and
+/// the rule is checked while building on top of that bundle instead.
 ///
 /// # Arguments
 /// * `ast` - valid OCA AST
@@ -123,6 +125,14 @@ fn validate_1_0_0(ast: &OCAAst, command: Command) -> Result<bool, Error> {
 fn rule_remove_attr_if_exist(ast: &OCAAst, command_to_validate: Command) -> Result<bool, Error> {
     let mut errors = Vec::new();
 
+    if ast
+        .commands
+        .first()
+        .is_some_and(|command| command.kind == CommandType::From)
+    {
+        return Ok(true);
+    }
+
     let attributes = extract_attributes(ast);
     let properties = extract_properties(ast);
 
@@ -11,8 +11,10 @@ use crate::state::oca::overlay::information::Information;
 use crate::state::oca::overlay::label::Labels;
 use crate::state::oca::overlay::link::Links;
 use crate::state::oca::overlay::meta::Metas;
+use crate::state::oca::overlay::plugin::{check_name, registered_overlay};
 use crate::state::oca::overlay::unit::Units;
 use crate::state::oca::OCABundle;
+use crate::state::said_options::SaidOptions;
 use crate::state::{
     attribute::Attribute, encoding::Encoding, entries::EntriesElement,
     entry_codes::EntryCodes as EntryCodesValue, oca::OCABox,
@@ -59,6 +61,15 @@ pub enum Error {
 pub fn from_ast(
     from_oca: Option<OCABundle>,
     oca_ast: &ast::OCAAst,
+) -> Result<OCABuild, Vec<Error>> {
+    from_ast_with(from_oca, oca_ast, &SaidOptions::default())
+}
+
+/// Build OCA Bundle from AST computing SAIDs with given options.
+pub fn from_ast_with(
+    from_oca: Option<OCABundle>,
+    oca_ast: &ast::OCAAst,
+    options: &SaidOptions,
 ) -> Result<OCABuild, Vec<Error>> {
     let mut errors = vec![];
     let mut steps = vec![];
@@ -84,7 +95,7 @@ pub fn from_ast(
         match apply_command(base.clone(), command.clone()) {
             Ok(oca_box) => {
                 let mut oca_box_mut = oca_box.clone();
-                let oca_bundle = oca_box_mut.generate_bundle();
+                let oca_bundle = oca_box_mut.generate_bundle_with(options);
                 /* if oca_bundle.said == parent_said {
                     errors.push(Error::FromASTError {
                         line_number: command_meta.line_number,
@@ -112,7 +123,7 @@ pub fn from_ast(
     }
     if errors.is_empty() {
         Ok(OCABuild {
-            oca_bundle: base.unwrap().generate_bundle(),
+            oca_bundle: base.unwrap().generate_bundle_with(options),
             steps,
         })
     } else {
@@ -122,10 +133,7 @@ pub fn from_ast(
 
 pub fn apply_command(base: Option<OCABox>, op: ast::Command) -> Result<OCABox, Vec<String>> {
     let mut errors = vec![];
-    let mut oca: OCABox = match base {
-        Some(oca) => oca,
-        None => OCABox::new(),
-    };
+    let mut oca: OCABox = base.unwrap_or_default();
 
     match (op.kind, op.object_kind) {
         (ast::CommandType::From, _) => {
@@ -142,6 +150,14 @@ pub fn apply_command(base: Option<OCABox>, op: ast::Command) -> Result<OCABox, V
                     oca.add_attribute(attribute);
                 }
             }
+            if let Some(ref flagged_attributes) = content.flagged_attributes {
+                for attr_name in flagged_attributes {
+                    match oca.attributes.get_mut(attr_name) {
+                        Some(attribute) => attribute.set_flagged(),
+                        None => errors.push(format!("Undefined attribute: {attr_name}")),
+                    }
+                }
+            }
             if let Some(ref properties) = content.properties {
                 // TODO handle other properties
                 for (prop_name, prop_value) in properties {
@@ -536,6 +552,16 @@ pub fn apply_command(base: Option<OCABox>, op: ast::Command) -> Result<OCABox, V
                         }
                     }
                 }
+                ast::OverlayType::Custom { ref name, .. } => {
+                    match check_name(name).map(|_| registered_overlay(name)) {
+                        Ok(Some(plugin)) => match plugin.build(&overlay_type, &content) {
+                            Ok(overlay) => oca.add_custom_overlay(overlay),
+                            Err(e) => errors.push(format!("{name} overlay: {e}")),
+                        },
+                        Ok(None) => errors.push(format!("Unsupported overlay type: {name}")),
+                        Err(e) => errors.push(e.to_string()),
+                    }
+                }
                 _ => (),
             }
         }
@@ -543,6 +569,10 @@ pub fn apply_command(base: Option<OCABox>, op: ast::Command) -> Result<OCABox, V
         (ast::CommandType::Remove, ast::ObjectKind::CaptureBase(content)) => {
             if let Some(ref attributes) = content.attributes {
                 for (attr_name, _) in attributes {
+                    if oca.get_attribute_by_name(attr_name).is_none() {
+                        errors.push(format!("Undefined attribute: {attr_name}"));
+                        continue;
+                    }
                     oca.remove_attribute(attr_name);
                 }
             }
@@ -571,6 +601,7 @@ pub fn apply_command(base: Option<OCABox>, op: ast::Command) -> Result<OCABox, V
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state::spec_version::SpecVersion;
     use indexmap::IndexMap;
     use oca_ast_semantics::ast::{AttributeType, CaptureContent};
     use said::{derivation::HashFunctionCode, sad::SerializationFormats, version::Encode};
@@ -614,7 +645,7 @@ mod tests {
             "description".to_string(),
             ast::NestedValue::Value("Entrance credential".to_string()),
         );
-        let overlay_version = "1.1".to_string();
+        let overlay_version = SpecVersion::CURRENT.to_string();
         commands.push(ast::Command {
             kind: ast::CommandType::Add,
             object_kind: ast::ObjectKind::Overlay(
@@ -788,7 +819,7 @@ mod tests {
             "description".to_string(),
             ast::NestedValue::Value("Entrance credential".to_string()),
         );
-        let overlay_version = "1.1".to_string();
+        let overlay_version = SpecVersion::CURRENT.to_string();
         commands.push(ast::Command {
             kind: ast::CommandType::Add,
             object_kind: ast::ObjectKind::Overlay(
@@ -949,4 +980,26 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn remove_undefined_attribute() {
+        let mut base = OCABox::new();
+        let mut attribute = Attribute::new("name".to_string());
+        attribute.set_attribute_type(ast::NestedAttrType::Value(AttributeType::Text));
+        base.add_attribute(attribute);
+
+        let mut attributes = IndexMap::new();
+        attributes.insert("nmae".to_string(), ast::NestedAttrType::Null);
+        let command = ast::Command {
+            kind: ast::CommandType::Remove,
+            object_kind: ast::ObjectKind::CaptureBase(CaptureContent {
+                attributes: Some(attributes),
+                properties: None,
+                flagged_attributes: None,
+            }),
+        };
+
+        let errors = apply_command(Some(base), command).err();
+        assert_eq!(errors, Some(vec!["Undefined attribute: nmae".to_string()]));
+    }
 }
@@ -1,15 +1,35 @@
 use std::io::Read;
 
-use crate::state::oca::OCABundle;
+use crate::state::{integrity::IntegrityReport, oca::OCABundle};
 
 pub type GenericError = Box<dyn std::error::Error + Sync + Send>;
 pub type GenericResult<T> = Result<T, GenericError>;
 
+#[derive(thiserror::Error, Debug)]
+pub enum LoadError {
+    #[error("Failed to parse OCA Bundle: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("{0}")]
+    Integrity(Box<IntegrityReport>),
+}
+
 pub fn load_oca(source: &mut dyn Read) -> GenericResult<OCABundle> {
     let oca: OCABundle = serde_json::from_reader(source)?;
 
     Ok(oca)
 }
+
+/// Load OCA Bundle and verify its integrity. Fails with the full
+/// [`IntegrityReport`] if any SAID does not match the content.
+pub fn load_oca_strict(source: &mut dyn Read) -> Result<OCABundle, LoadError> {
+    let oca: OCABundle = serde_json::from_reader(source)?;
+    let report = oca.verify();
+    if !report.is_valid() {
+        return Err(LoadError::Integrity(Box::new(report)));
+    }
+
+    Ok(oca)
+}
 /*
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,40 @@
+//! Values shared by tests.
+use crate::state::{
+    attribute::{Attribute, AttributeType},
+    oca::{
+        overlay::{label::Labels, AttributeMapping, Opaque},
+        DynOverlay,
+    },
+};
+use isolang::Language;
+use oca_ast_semantics::ast::NestedAttrType;
+
+pub(crate) fn attribute(name: &str, attribute_type: AttributeType, label: &str) -> Attribute {
+    cascade! {
+        Attribute::new(name.to_string());
+        ..set_attribute_type(NestedAttrType::Value(attribute_type));
+        ..set_label(Language::Eng, label.to_string());
+    }
+}
+
+/// Overlay of a type without registered plugin.
+pub(crate) fn consent(value: &str) -> DynOverlay {
+    let overlay = Opaque::from_value(
+        serde_json::json!({
+            "d": "",
+            "capture_base": "",
+            "type": "spec/overlays/community_consent/1.0",
+            "name": value
+        }),
+        false,
+    );
+    Box::new(overlay.unwrap())
+}
+
+pub(crate) fn mapping() -> AttributeMapping {
+    let mut mapping = *AttributeMapping::new();
+    mapping
+        .attribute_mapping
+        .insert("name".to_string(), "full_name".to_string());
+    mapping
+}
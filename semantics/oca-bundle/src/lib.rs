@@ -4,6 +4,8 @@ extern crate cascade;
 
 pub mod build;
 pub mod controller;
+#[cfg(test)]
+mod fixtures;
 mod io;
 pub mod state;
 
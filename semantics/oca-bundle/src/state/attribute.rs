@@ -76,6 +76,24 @@ impl Attribute {
         self.attribute_type = Some(attribute_type);
     }
 
+    /// Condition with each `${i}` replaced with `value` of the i-th
+    /// dependency.
+    pub fn condition_with(&self, value: impl Fn(&str) -> String) -> Option<String> {
+        let condition = self.condition.as_ref()?;
+        let dependencies = self.dependencies.as_deref().unwrap_or_default();
+        Some(dependencies.iter().enumerate().fold(
+            condition.clone(),
+            |condition, (i, dependency)| {
+                condition.replace(&format!("${{{i}}}"), &value(dependency))
+            },
+        ))
+    }
+
+    /// Condition with dependencies referenced by name, e.g. `${age} < 18`.
+    pub fn named_condition(&self) -> Option<String> {
+        self.condition_with(|dependency| format!("${{{dependency}}}"))
+    }
+
     // Merge assumption is that if `other` is not None then it would overwrite `self` or would be concatenated with `self`
     pub fn merge(&mut self, other: &Attribute) {
         if self.name != other.name {
@@ -148,46 +166,48 @@ impl Attribute {
     }
 
     fn merge_entries(&mut self, other: &Attribute) {
-        if self.entries.is_none() {
-            self.entries.clone_from(&other.entries);
-        } else if let Some(entries) = &other.entries {
-            for (lang, entry) in entries {
-                self.entries.as_mut().unwrap().insert(*lang, entry.clone());
+        match (&mut self.entries, &other.entries) {
+            (None, _) => self.entries.clone_from(&other.entries),
+            (Some(self_entries), Some(entries)) => {
+                for (lang, entry) in entries {
+                    self_entries.insert(*lang, entry.clone());
+                }
             }
+            (Some(_), None) => {}
         }
     }
 
     fn merge_category_labels(&mut self, other: &Attribute) {
-        if self.category_labels.is_none() {
-            self.category_labels.clone_from(&other.category_labels);
-        } else if let Some(category_labels) = &other.category_labels {
-            for (lang, category_label) in category_labels {
-                self.category_labels
-                    .as_mut()
-                    .unwrap()
-                    .insert(*lang, category_label.clone());
+        match (&mut self.category_labels, &other.category_labels) {
+            (None, _) => self.category_labels.clone_from(&other.category_labels),
+            (Some(self_category_labels), Some(category_labels)) => {
+                for (lang, category_label) in category_labels {
+                    self_category_labels.insert(*lang, category_label.clone());
+                }
             }
+            (Some(_), None) => {}
         }
     }
     fn merge_information(&mut self, other: &Attribute) {
-        if self.informations.is_none() {
-            self.informations.clone_from(&other.informations);
-        } else if let Some(informations) = &other.informations {
-            for (lang, information) in informations {
-                self.informations
-                    .as_mut()
-                    .unwrap()
-                    .insert(*lang, information.clone());
+        match (&mut self.informations, &other.informations) {
+            (None, _) => self.informations.clone_from(&other.informations),
+            (Some(self_informations), Some(informations)) => {
+                for (lang, information) in informations {
+                    self_informations.insert(*lang, information.clone());
+                }
             }
+            (Some(_), None) => {}
         }
     }
     fn merge_labels(&mut self, other: &Attribute) {
-        if self.labels.is_none() {
-            self.labels.clone_from(&other.labels)
-        } else if let Some(labels) = &other.labels {
-            for (lang, label) in labels {
-                self.labels.as_mut().unwrap().insert(*lang, label.clone());
+        match (&mut self.labels, &other.labels) {
+            (None, _) => self.labels.clone_from(&other.labels),
+            (Some(self_labels), Some(labels)) => {
+                for (lang, label) in labels {
+                    self_labels.insert(*lang, label.clone());
+                }
             }
+            (Some(_), None) => {}
         }
     }
 
@@ -0,0 +1,301 @@
+//! Translation coverage of bundles.
+//!
+//! A value is expected in every language once it is given in any language
+//! of the bundle: Meta properties, labels and information of attributes and
+//! entries of entry codes. Coverage is the share of expected values which
+//! are present.
+use crate::state::{oca::OCABundle, view::BundleView};
+use isolang::Language;
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranslationCoverage {
+    /// Coverage of every language found in the bundles, by language code.
+    pub languages: Vec<LanguageCoverage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LanguageCoverage {
+    pub language: Language,
+    pub translated: usize,
+    pub expected: usize,
+    pub bundles: Vec<BundleCoverage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BundleCoverage {
+    pub said: String,
+    pub translated: usize,
+    pub expected: usize,
+    pub missing_meta: Vec<String>,
+    pub missing_labels: Vec<String>,
+    pub missing_informations: Vec<String>,
+    /// Entry codes without entries, by attribute name.
+    pub missing_entries: BTreeMap<String, Vec<String>>,
+}
+
+impl LanguageCoverage {
+    pub fn percentage(&self) -> f64 {
+        percentage(self.translated, self.expected)
+    }
+}
+
+impl BundleCoverage {
+    pub fn percentage(&self) -> f64 {
+        percentage(self.translated, self.expected)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.translated == self.expected
+    }
+}
+
+fn percentage(translated: usize, expected: usize) -> f64 {
+    if expected == 0 {
+        100.0
+    } else {
+        translated as f64 * 100.0 / expected as f64
+    }
+}
+
+/// Report translation coverage of the bundle and its dependencies for each
+/// language used by any of them.
+pub fn translation_coverage(bundle: &OCABundle, dependencies: &[OCABundle]) -> TranslationCoverage {
+    let mut views: Vec<(String, BundleView)> = vec![];
+    for bundle in std::iter::once(bundle).chain(dependencies) {
+        let said = bundle
+            .said
+            .as_ref()
+            .map(|said| said.to_string())
+            .unwrap_or_default();
+        if views.iter().all(|(existing, _)| *existing != said) {
+            views.push((said, BundleView::new(bundle)));
+        }
+    }
+    let mut languages: Vec<Language> = views
+        .iter()
+        .flat_map(|(_, view)| view.languages())
+        .collect();
+    languages.sort_by_key(|lang| lang.to_639_3());
+    languages.dedup();
+
+    let languages = languages
+        .into_iter()
+        .map(|lang| {
+            let bundles: Vec<BundleCoverage> = views
+                .iter()
+                .map(|(said, view)| bundle_coverage(said, view, lang))
+                .collect();
+            LanguageCoverage {
+                language: lang,
+                translated: bundles.iter().map(|b| b.translated).sum(),
+                expected: bundles.iter().map(|b| b.expected).sum(),
+                bundles,
+            }
+        })
+        .collect();
+
+    TranslationCoverage { languages }
+}
+
+fn bundle_coverage(said: &str, view: &BundleView, lang: Language) -> BundleCoverage {
+    let languages = view.languages();
+    let mut coverage = BundleCoverage {
+        said: said.to_string(),
+        translated: 0,
+        expected: 0,
+        missing_meta: vec![],
+        missing_labels: vec![],
+        missing_informations: vec![],
+        missing_entries: BTreeMap::new(),
+    };
+    let mut check = |present: bool| {
+        coverage.expected += 1;
+        coverage.translated += usize::from(present);
+        present
+    };
+
+    let mut keys: Vec<&String> = languages
+        .iter()
+        .filter_map(|lang| view.metas(*lang))
+        .flat_map(|meta| meta.keys())
+        .collect();
+    keys.sort();
+    keys.dedup();
+    let mut missing_meta = vec![];
+    for key in keys {
+        if !check(view.metas(lang).is_some_and(|meta| meta.contains_key(key))) {
+            missing_meta.push(key.clone());
+        }
+    }
+
+    let mut missing_labels = vec![];
+    let mut missing_informations = vec![];
+    let mut missing_entries = BTreeMap::new();
+    for attribute in view.attributes() {
+        let name = attribute.name().to_string();
+        if languages.iter().any(|l| attribute.label(*l).is_some())
+            && !check(attribute.label(lang).is_some())
+        {
+            missing_labels.push(name.clone());
+        }
+        if languages
+            .iter()
+            .any(|l| attribute.information(*l).is_some())
+            && !check(attribute.information(lang).is_some())
+        {
+            missing_informations.push(name.clone());
+        }
+        let mut codes: Vec<&String> = languages
+            .iter()
+            .filter_map(|l| attribute.entries(*l))
+            .flat_map(|entries| entries.keys())
+            .collect();
+        codes.sort();
+        codes.dedup();
+        let missing: Vec<String> = codes
+            .into_iter()
+            .filter(|code| !check(attribute.entry(lang, code).is_some()))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            missing_entries.insert(name, missing);
+        }
+    }
+
+    coverage.missing_meta = missing_meta;
+    coverage.missing_labels = missing_labels;
+    coverage.missing_informations = missing_informations;
+    coverage.missing_entries = missing_entries;
+    coverage
+}
+
+/// Plain text report, one section per language.
+impl fmt::Display for TranslationCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for language in &self.languages {
+            writeln!(
+                f,
+                "{}: {:.1}% ({}/{})",
+                language.language.to_639_3(),
+                language.percentage(),
+                language.translated,
+                language.expected
+            )?;
+            for bundle in &language.bundles {
+                writeln!(
+                    f,
+                    "  {}: {:.1}% ({}/{})",
+                    bundle.said,
+                    bundle.percentage(),
+                    bundle.translated,
+                    bundle.expected
+                )?;
+                for (what, missing) in [
+                    ("meta", &bundle.missing_meta),
+                    ("labels", &bundle.missing_labels),
+                    ("information", &bundle.missing_informations),
+                ] {
+                    if !missing.is_empty() {
+                        writeln!(f, "    missing {what}: {}", missing.join(", "))?;
+                    }
+                }
+                if !bundle.missing_entries.is_empty() {
+                    let entries: Vec<String> = bundle
+                        .missing_entries
+                        .iter()
+                        .map(|(name, codes)| format!("{name} ({})", codes.join(", ")))
+                        .collect();
+                    writeln!(f, "    missing entries: {}", entries.join(", "))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{
+        attribute::{Attribute, AttributeType},
+        entries::EntriesElement,
+        oca::{
+            overlay::{entry::Entries, information::Information, label::Labels, meta::Metas},
+            OCABox,
+        },
+    };
+    use oca_ast_semantics::ast::NestedAttrType;
+    use std::collections::HashMap;
+
+    fn attribute(name: &str) -> Attribute {
+        let mut attribute = Attribute::new(name.to_string());
+        attribute.set_attribute_type(NestedAttrType::Value(AttributeType::Text));
+        attribute
+    }
+
+    #[test]
+    fn coverage_of_bundle_and_dependencies() {
+        let mut address = OCABox::new();
+        let mut street = attribute("street");
+        street.set_label(Language::Eng, "Street".to_string());
+        address.add_attribute(street);
+        let address = address.generate_bundle();
+
+        let mut person = OCABox::new();
+        person.add_meta(Language::Eng, "name".to_string(), "Person".to_string());
+        person.add_meta(Language::Eng, "description".to_string(), "Data".to_string());
+        person.add_meta(Language::Pol, "name".to_string(), "Osoba".to_string());
+        let mut name = attribute("name");
+        name.set_label(Language::Eng, "Name".to_string());
+        name.set_label(Language::Pol, "Imię".to_string());
+        name.set_information(Language::Eng, "Full name".to_string());
+        person.add_attribute(name);
+        let mut sex = attribute("sex");
+        sex.set_entry(
+            Language::Eng,
+            EntriesElement::Object(HashMap::from([
+                ("m".to_string(), "Male".to_string()),
+                ("f".to_string(), "Female".to_string()),
+            ])),
+        );
+        sex.set_entry(
+            Language::Pol,
+            EntriesElement::Object(HashMap::from([("m".to_string(), "Mężczyzna".to_string())])),
+        );
+        person.add_attribute(sex);
+        let person = person.generate_bundle();
+
+        let coverage = translation_coverage(&person, std::slice::from_ref(&address));
+        let languages: Vec<_> = coverage.languages.iter().map(|l| l.language).collect();
+        assert_eq!(languages, vec![Language::Eng, Language::Pol]);
+
+        let eng = &coverage.languages[0];
+        assert_eq!((eng.translated, eng.expected), (7, 7));
+
+        let pol = &coverage.languages[1];
+        let person_coverage = &pol.bundles[0];
+        assert_eq!(person_coverage.missing_meta, vec!["description"]);
+        assert!(person_coverage.missing_labels.is_empty());
+        assert_eq!(person_coverage.missing_informations, vec!["name"]);
+        assert_eq!(
+            person_coverage.missing_entries,
+            BTreeMap::from([("sex".to_string(), vec!["f".to_string()])])
+        );
+        assert_eq!(
+            (person_coverage.translated, person_coverage.expected),
+            (3, 6)
+        );
+        // Address isn't translated to Polish at all.
+        assert_eq!(pol.bundles[1].missing_labels, vec!["street"]);
+        assert_eq!(pol.percentage(), 3.0 * 100.0 / 7.0);
+
+        let report = coverage.to_string();
+        assert!(report.contains("pol: 42.9% (3/7)\n"));
+        assert!(report.contains(
+            "    missing meta: description\n    missing information: name\n    \
+             missing entries: sex (f)\n"
+        ));
+    }
+}
@@ -0,0 +1,57 @@
+//! Resolution of bundles referenced by attributes.
+use crate::state::oca::OCABundle;
+use oca_ast_semantics::ast::RefValue;
+use std::collections::HashMap;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum Error {
+    #[error("Bundle has no SAID")]
+    MissingSaid,
+    #[error("Referenced bundle {said} not found in dependencies")]
+    MissingDependency { said: String },
+    #[error("Reference by name `{name}` can't be resolved, use SAID reference")]
+    UnresolvedReference { name: String },
+}
+
+/// Bundles which attributes can reference, indexed by SAID.
+pub struct Dependencies<'a> {
+    bundles: HashMap<String, &'a OCABundle>,
+}
+
+impl<'a> Dependencies<'a> {
+    pub fn new(bundles: &'a [OCABundle]) -> Self {
+        Self {
+            bundles: bundles
+                .iter()
+                .filter_map(|bundle| bundle.said.as_ref().map(|said| (said.to_string(), bundle)))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, said: &str) -> Option<&'a OCABundle> {
+        self.bundles.get(said).copied()
+    }
+
+    /// Bundle referenced by SAID. References by name can't be resolved.
+    pub fn resolve(&self, reference: &RefValue) -> Result<&'a OCABundle, Error> {
+        match reference {
+            RefValue::Said(said) => {
+                self.get(&said.to_string())
+                    .ok_or_else(|| Error::MissingDependency {
+                        said: said.to_string(),
+                    })
+            }
+            RefValue::Name(name) => Err(Error::UnresolvedReference { name: name.clone() }),
+        }
+    }
+}
+
+/// SAID of the bundle as string.
+pub fn said_of(bundle: &OCABundle) -> Result<String, Error> {
+    bundle
+        .said
+        .as_ref()
+        .map(ToString::to_string)
+        .ok_or(Error::MissingSaid)
+}
@@ -0,0 +1,517 @@
+use crate::state::oca::{DynOverlay, OCABundle};
+use isolang::Language;
+use oca_ast_semantics::ast::{NestedAttrType, OverlayType};
+use said::SelfAddressingIdentifier;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// Overlay fields which identify an overlay rather than carry its content.
+const OVERLAY_HEADER_FIELDS: [&str; 4] = ["d", "capture_base", "type", "language"];
+
+/// Structural difference between two OCA Bundles.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BundleDiff {
+    pub from: Option<SelfAddressingIdentifier>,
+    pub to: Option<SelfAddressingIdentifier>,
+    pub capture_base: CaptureBaseDiff,
+    pub overlays: Vec<OverlayDiff>,
+}
+
+impl BundleDiff {
+    pub fn is_empty(&self) -> bool {
+        self.capture_base.changes.is_empty() && self.overlays.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CaptureBaseDiff {
+    pub from: Option<SelfAddressingIdentifier>,
+    pub to: Option<SelfAddressingIdentifier>,
+    pub changes: Vec<CaptureBaseChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum CaptureBaseChange {
+    AttributeAdded {
+        name: String,
+        attribute_type: NestedAttrType,
+    },
+    AttributeRemoved {
+        name: String,
+        attribute_type: NestedAttrType,
+    },
+    AttributeRetyped {
+        name: String,
+        from: NestedAttrType,
+        to: NestedAttrType,
+    },
+    FlaggedAttributeAdded {
+        name: String,
+    },
+    FlaggedAttributeRemoved {
+        name: String,
+    },
+    ClassificationChanged {
+        from: String,
+        to: String,
+    },
+}
+
+/// Changes of a single overlay, identified by its type and language. `from`
+/// is `None` for an added overlay and `to` is `None` for a removed one.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OverlayDiff {
+    pub overlay_type: OverlayType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<Language>,
+    pub from: Option<SelfAddressingIdentifier>,
+    pub to: Option<SelfAddressingIdentifier>,
+    pub changes: Vec<OverlayChange>,
+}
+
+/// Change of an overlay field. For map fields (e.g. `attribute_labels`) `key`
+/// holds the affected entry, usually an attribute name.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum OverlayChange {
+    Added {
+        field: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        key: Option<String>,
+        value: Value,
+    },
+    Removed {
+        field: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        key: Option<String>,
+        value: Value,
+    },
+    Modified {
+        field: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        key: Option<String>,
+        from: Value,
+        to: Value,
+    },
+}
+
+impl OverlayChange {
+    pub fn field(&self) -> &str {
+        match self {
+            OverlayChange::Added { field, .. }
+            | OverlayChange::Removed { field, .. }
+            | OverlayChange::Modified { field, .. } => field,
+        }
+    }
+
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            OverlayChange::Added { key, .. }
+            | OverlayChange::Removed { key, .. }
+            | OverlayChange::Modified { key, .. } => key.as_deref(),
+        }
+    }
+}
+
+/// Compute structural difference between `a` and `b`, describing how to get
+/// from `a` to `b`.
+pub fn diff(a: &OCABundle, b: &OCABundle) -> BundleDiff {
+    BundleDiff {
+        from: a.said.clone(),
+        to: b.said.clone(),
+        capture_base: diff_capture_base(a, b),
+        overlays: diff_overlays(&a.overlays, &b.overlays),
+    }
+}
+
+fn diff_capture_base(a: &OCABundle, b: &OCABundle) -> CaptureBaseDiff {
+    let (a, b) = (&a.capture_base, &b.capture_base);
+    let mut changes = vec![];
+
+    let mut attributes = a.attributes.iter().collect::<Vec<_>>();
+    attributes.sort_by(|x, y| x.0.cmp(y.0));
+    for (name, attribute_type) in attributes {
+        match b.attributes.get(name) {
+            None => changes.push(CaptureBaseChange::AttributeRemoved {
+                name: name.clone(),
+                attribute_type: attribute_type.clone(),
+            }),
+            Some(new_type) if new_type != attribute_type => {
+                changes.push(CaptureBaseChange::AttributeRetyped {
+                    name: name.clone(),
+                    from: attribute_type.clone(),
+                    to: new_type.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    let mut attributes = b.attributes.iter().collect::<Vec<_>>();
+    attributes.sort_by(|x, y| x.0.cmp(y.0));
+    for (name, attribute_type) in attributes {
+        if !a.attributes.contains_key(name) {
+            changes.push(CaptureBaseChange::AttributeAdded {
+                name: name.clone(),
+                attribute_type: attribute_type.clone(),
+            });
+        }
+    }
+
+    for name in &a.flagged_attributes {
+        if !b.flagged_attributes.contains(name) {
+            changes.push(CaptureBaseChange::FlaggedAttributeRemoved { name: name.clone() });
+        }
+    }
+    for name in &b.flagged_attributes {
+        if !a.flagged_attributes.contains(name) {
+            changes.push(CaptureBaseChange::FlaggedAttributeAdded { name: name.clone() });
+        }
+    }
+
+    if a.classification != b.classification {
+        changes.push(CaptureBaseChange::ClassificationChanged {
+            from: a.classification.clone(),
+            to: b.classification.clone(),
+        });
+    }
+
+    CaptureBaseDiff {
+        from: a.said.clone(),
+        to: b.said.clone(),
+        changes,
+    }
+}
+
+type OverlayKey = (OverlayType, Option<Language>);
+
+fn overlay_key(overlay: &DynOverlay) -> OverlayKey {
+    (overlay.overlay_type().clone(), overlay.language().copied())
+}
+
+/// Overlays are paired by type and language. When a bundle holds several
+/// overlays with the same key (e.g. links), they are paired in order.
+fn diff_overlays(a: &[DynOverlay], b: &[DynOverlay]) -> Vec<OverlayDiff> {
+    let mut keys: Vec<OverlayKey> = vec![];
+    for key in a.iter().chain(b.iter()).map(overlay_key) {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    let mut diffs = vec![];
+    for key in keys {
+        let old = a
+            .iter()
+            .filter(|o| overlay_key(o) == key)
+            .collect::<Vec<_>>();
+        let new = b
+            .iter()
+            .filter(|o| overlay_key(o) == key)
+            .collect::<Vec<_>>();
+        for i in 0..old.len().max(new.len()) {
+            let (old, new) = (old.get(i), new.get(i));
+            let (from, to) = (
+                old.and_then(|o| o.said().clone()),
+                new.and_then(|o| o.said().clone()),
+            );
+            if old.is_some() && new.is_some() && from == to {
+                continue;
+            }
+            let changes = diff_overlay_content(&overlay_content(old), &overlay_content(new));
+            if old.is_some() && new.is_some() && changes.is_empty() {
+                continue;
+            }
+            diffs.push(OverlayDiff {
+                overlay_type: key.0.clone(),
+                language: key.1,
+                from,
+                to,
+                changes,
+            });
+        }
+    }
+    diffs
+}
+
+fn overlay_content(overlay: Option<&&DynOverlay>) -> Map<String, Value> {
+    let mut content = match overlay.map(serde_json::to_value) {
+        Some(Ok(Value::Object(content))) => content,
+        _ => Map::new(),
+    };
+    content.retain(|field, _| !OVERLAY_HEADER_FIELDS.contains(&field.as_str()));
+    content
+}
+
+fn diff_overlay_content(a: &Map<String, Value>, b: &Map<String, Value>) -> Vec<OverlayChange> {
+    let mut changes = vec![];
+    let mut fields = a.keys().collect::<Vec<_>>();
+    fields.extend(b.keys().filter(|field| !a.contains_key(*field)));
+
+    for field in fields {
+        match (a.get(field), b.get(field)) {
+            (Some(Value::Object(old)), Some(Value::Object(new))) => {
+                diff_entries(field, old, new, &mut changes)
+            }
+            (Some(Value::Object(old)), None) => diff_entries(field, old, &Map::new(), &mut changes),
+            (None, Some(Value::Object(new))) => diff_entries(field, &Map::new(), new, &mut changes),
+            (old, new) => push_change(field, None, old, new, &mut changes),
+        }
+    }
+    changes
+}
+
+fn diff_entries(
+    field: &str,
+    a: &Map<String, Value>,
+    b: &Map<String, Value>,
+    changes: &mut Vec<OverlayChange>,
+) {
+    let mut keys = a.keys().collect::<Vec<_>>();
+    keys.extend(b.keys().filter(|key| !a.contains_key(*key)));
+    for key in keys {
+        push_change(field, Some(key), a.get(key), b.get(key), changes);
+    }
+}
+
+fn push_change(
+    field: &str,
+    key: Option<&String>,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    changes: &mut Vec<OverlayChange>,
+) {
+    let field = field.to_string();
+    let key = key.cloned();
+    match (old, new) {
+        (None, Some(value)) => changes.push(OverlayChange::Added {
+            field,
+            key,
+            value: value.clone(),
+        }),
+        (Some(value), None) => changes.push(OverlayChange::Removed {
+            field,
+            key,
+            value: value.clone(),
+        }),
+        (Some(from), Some(to)) if from != to => changes.push(OverlayChange::Modified {
+            field,
+            key,
+            from: from.clone(),
+            to: to.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn fmt_said(said: &Option<SelfAddressingIdentifier>) -> String {
+    said.as_ref()
+        .map(|said| said.to_string())
+        .unwrap_or_else(|| "none".to_string())
+}
+
+fn fmt_type(attribute_type: &NestedAttrType) -> String {
+    match serde_json::to_value(attribute_type) {
+        Ok(Value::String(t)) => t,
+        Ok(t) => t.to_string(),
+        Err(_) => format!("{:?}", attribute_type),
+    }
+}
+
+fn fmt_path(field: &str, key: Option<&str>) -> String {
+    match key {
+        Some(key) => format!("{field}.{key}"),
+        None => field.to_string(),
+    }
+}
+
+impl fmt::Display for CaptureBaseChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureBaseChange::AttributeAdded {
+                name,
+                attribute_type,
+            } => write!(f, "+ attribute {name}: {}", fmt_type(attribute_type)),
+            CaptureBaseChange::AttributeRemoved {
+                name,
+                attribute_type,
+            } => write!(f, "- attribute {name}: {}", fmt_type(attribute_type)),
+            CaptureBaseChange::AttributeRetyped { name, from, to } => write!(
+                f,
+                "~ attribute {name}: {} -> {}",
+                fmt_type(from),
+                fmt_type(to)
+            ),
+            CaptureBaseChange::FlaggedAttributeAdded { name } => write!(f, "+ flagged {name}"),
+            CaptureBaseChange::FlaggedAttributeRemoved { name } => write!(f, "- flagged {name}"),
+            CaptureBaseChange::ClassificationChanged { from, to } => {
+                write!(f, "~ classification: {from:?} -> {to:?}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for OverlayChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = fmt_path(self.field(), self.key());
+        match self {
+            OverlayChange::Added { value, .. } => write!(f, "+ {path}: {value}"),
+            OverlayChange::Removed { value, .. } => write!(f, "- {path}: {value}"),
+            OverlayChange::Modified { from, to, .. } => write!(f, "~ {path}: {from} -> {to}"),
+        }
+    }
+}
+
+impl fmt::Display for BundleDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "OCA Bundle {} -> {}",
+            fmt_said(&self.from),
+            fmt_said(&self.to)
+        )?;
+        if self.is_empty() {
+            return writeln!(f, "  no changes");
+        }
+        if !self.capture_base.changes.is_empty() {
+            writeln!(
+                f,
+                "Capture base {} -> {}",
+                fmt_said(&self.capture_base.from),
+                fmt_said(&self.capture_base.to)
+            )?;
+            for change in &self.capture_base.changes {
+                writeln!(f, "  {change}")?;
+            }
+        }
+        for overlay in &self.overlays {
+            write!(f, "{}", overlay.overlay_type)?;
+            if let Some(language) = overlay.language {
+                write!(f, " ({})", language.to_639_3())?;
+            }
+            let status = match (&overlay.from, &overlay.to) {
+                (None, _) => " added",
+                (_, None) => " removed",
+                _ => "",
+            };
+            writeln!(
+                f,
+                "{status} {} -> {}",
+                fmt_said(&overlay.from),
+                fmt_said(&overlay.to)
+            )?;
+            for change in &overlay.changes {
+                writeln!(f, "  {change}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::attribute;
+    use crate::state::{
+        attribute::AttributeType, oca::overlay::conformance::Conformances, oca::OCABox,
+    };
+
+    #[test]
+    fn diff_bundles() {
+        let mut oca = OCABox::new();
+        oca.add_attribute(attribute("name", AttributeType::Text, "Name"));
+        oca.add_attribute(attribute("age", AttributeType::Text, "Age"));
+        oca.add_attribute(attribute("email", AttributeType::Text, "Email"));
+        let a = oca.generate_bundle();
+
+        let mut oca = OCABox::new();
+        oca.add_classification("GICS:35102020".to_string());
+        let mut name = attribute("name", AttributeType::Text, "Full name");
+        name.set_flagged();
+        oca.add_attribute(name);
+        let mut age = attribute("age", AttributeType::Numeric, "Age");
+        age.set_conformance("M".to_string());
+        oca.add_attribute(age);
+        oca.add_attribute(attribute("phone", AttributeType::Text, "Phone"));
+        let b = oca.generate_bundle();
+
+        let result = diff(&a, &b);
+        assert_eq!(result.from, a.said);
+        assert_eq!(result.to, b.said);
+        assert_eq!(result.capture_base.from, a.capture_base.said);
+        assert_eq!(
+            result.capture_base.changes,
+            vec![
+                CaptureBaseChange::AttributeRetyped {
+                    name: "age".to_string(),
+                    from: NestedAttrType::Value(AttributeType::Text),
+                    to: NestedAttrType::Value(AttributeType::Numeric),
+                },
+                CaptureBaseChange::AttributeRemoved {
+                    name: "email".to_string(),
+                    attribute_type: NestedAttrType::Value(AttributeType::Text),
+                },
+                CaptureBaseChange::AttributeAdded {
+                    name: "phone".to_string(),
+                    attribute_type: NestedAttrType::Value(AttributeType::Text),
+                },
+                CaptureBaseChange::FlaggedAttributeAdded {
+                    name: "name".to_string()
+                },
+                CaptureBaseChange::ClassificationChanged {
+                    from: "".to_string(),
+                    to: "GICS:35102020".to_string()
+                },
+            ]
+        );
+
+        let label = result
+            .overlays
+            .iter()
+            .find(|o| o.overlay_type.to_string() == "Label")
+            .unwrap();
+        assert_eq!(label.language, Some(Language::Eng));
+        assert!(label.from.is_some() && label.to.is_some());
+        assert!(label.changes.contains(&OverlayChange::Modified {
+            field: "attribute_labels".to_string(),
+            key: Some("name".to_string()),
+            from: Value::String("Name".to_string()),
+            to: Value::String("Full name".to_string()),
+        }));
+        assert!(label.changes.contains(&OverlayChange::Removed {
+            field: "attribute_labels".to_string(),
+            key: Some("email".to_string()),
+            value: Value::String("Email".to_string()),
+        }));
+
+        let conformance = result
+            .overlays
+            .iter()
+            .find(|o| o.overlay_type.to_string() == "Conformance")
+            .unwrap();
+        assert!(conformance.from.is_none());
+        assert_eq!(conformance.changes.len(), 1);
+
+        let rendered = result.to_string();
+        assert!(rendered.contains("~ attribute age: Text -> Numeric"));
+        assert!(rendered.contains("~ attribute_labels.name: \"Name\" -> \"Full name\""));
+        assert!(rendered.contains("Conformance added"));
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(
+            json["capture_base"]["changes"][0]["change"],
+            "attribute_retyped"
+        );
+    }
+
+    #[test]
+    fn diff_same_bundle() {
+        let mut oca = OCABox::new();
+        oca.add_attribute(attribute("name", AttributeType::Text, "Name"));
+        let a = oca.generate_bundle();
+
+        let result = diff(&a, &a);
+        assert!(result.is_empty());
+    }
+}
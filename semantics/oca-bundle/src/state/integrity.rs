@@ -0,0 +1,212 @@
+use crate::state::oca::OCABundle;
+use crate::state::said_options::SaidOptions;
+use crate::state::validator::Error;
+use isolang::Language;
+use oca_ast_semantics::ast::OverlayType;
+use said::SelfAddressingIdentifier;
+use serde::Serialize;
+
+/// Result of comparing a stored SAID with the one recomputed from content.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DigestCheck {
+    pub said: Option<SelfAddressingIdentifier>,
+    pub expected: Option<SelfAddressingIdentifier>,
+}
+
+impl DigestCheck {
+    pub fn new(
+        said: Option<SelfAddressingIdentifier>,
+        expected: Option<SelfAddressingIdentifier>,
+    ) -> Self {
+        Self { said, expected }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.said.is_some() && self.said == self.expected
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OverlayIntegrity {
+    pub overlay_type: OverlayType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<Language>,
+    pub digest: DigestCheck,
+    /// Capture base SAID the overlay points to.
+    pub capture_base: Option<SelfAddressingIdentifier>,
+    pub capture_base_matches: bool,
+}
+
+impl OverlayIntegrity {
+    pub fn is_valid(&self) -> bool {
+        self.digest.is_valid() && self.capture_base_matches
+    }
+}
+
+/// Detailed integrity report of an OCA Bundle: every SAID is recomputed from
+/// the content and every overlay is checked to point to the bundled capture
+/// base.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IntegrityReport {
+    pub bundle: DigestCheck,
+    pub capture_base: DigestCheck,
+    pub overlays: Vec<OverlayIntegrity>,
+}
+
+impl IntegrityReport {
+    pub fn new(oca_bundle: &OCABundle, options: &SaidOptions) -> Self {
+        let mut recalculated_oca_bundle = oca_bundle.clone();
+        recalculated_oca_bundle.fill_said_with(options);
+
+        let capture_base = &oca_bundle.capture_base;
+        let mut recalculated_capture_base = capture_base.clone();
+        recalculated_capture_base.sign_with(options);
+
+        let overlays = oca_bundle
+            .overlays
+            .iter()
+            .map(|o| {
+                let mut recalculated_overlay = o.clone();
+                recalculated_overlay.fill_said_with(options);
+                OverlayIntegrity {
+                    overlay_type: o.overlay_type().clone(),
+                    language: o.language().copied(),
+                    digest: DigestCheck::new(o.said().clone(), recalculated_overlay.said().clone()),
+                    capture_base: o.capture_base().clone(),
+                    capture_base_matches: o.capture_base().eq(&capture_base.said),
+                }
+            })
+            .collect();
+
+        Self {
+            bundle: DigestCheck::new(oca_bundle.said.clone(), recalculated_oca_bundle.said),
+            capture_base: DigestCheck::new(
+                capture_base.said.clone(),
+                recalculated_capture_base.said,
+            ),
+            overlays,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.bundle.is_valid()
+            && self.capture_base.is_valid()
+            && self.overlays.iter().all(|o| o.is_valid())
+    }
+
+    /// Integrity problems expressed as semantic validation errors.
+    pub fn errors(&self) -> Vec<Error> {
+        let mut errors = vec![];
+        if !self.bundle.is_valid() {
+            errors.push(Error::MalformedBundleSaid {
+                said: self.bundle.said.clone(),
+            });
+        }
+        if !self.capture_base.is_valid() {
+            errors.push(Error::MalformedCaptureBaseSaid {
+                said: self.capture_base.said.clone(),
+            });
+        }
+        for overlay in &self.overlays {
+            if !overlay.digest.is_valid() {
+                errors.push(Error::MalformedOverlaySaid {
+                    overlay_type: overlay.overlay_type.clone(),
+                    language: overlay.language,
+                    said: overlay.digest.said.clone(),
+                });
+            }
+            if !overlay.capture_base_matches {
+                errors.push(Error::CaptureBaseMismatch {
+                    overlay_type: overlay.overlay_type.clone(),
+                    language: overlay.language,
+                    said: overlay.digest.said.clone(),
+                    capture_base: overlay.capture_base.clone(),
+                });
+            }
+        }
+        errors
+    }
+}
+
+impl std::fmt::Display for IntegrityReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let errors = self.errors();
+        if errors.is_empty() {
+            return write!(f, "OCA Bundle integrity verified");
+        }
+        write!(f, "OCA Bundle integrity check failed: ")?;
+        let messages = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::{load_oca_strict, LoadError};
+    use crate::state::{
+        attribute::{Attribute, AttributeType},
+        oca::overlay::label::Labels,
+        oca::OCABox,
+    };
+    use isolang::Language;
+    use oca_ast_semantics::ast::NestedAttrType;
+
+    fn oca_bundle_json() -> String {
+        let mut oca = OCABox::new();
+        let attribute = cascade! {
+            Attribute::new("name".to_string());
+            ..set_attribute_type(NestedAttrType::Value(AttributeType::Text));
+            ..set_label(Language::Eng, "Name: ".to_string());
+        };
+        oca.add_attribute(attribute);
+
+        serde_json::to_string(&oca.generate_bundle()).unwrap()
+    }
+
+    #[test]
+    fn load_untouched_bundle() {
+        let data = oca_bundle_json();
+        let oca_bundle = load_oca_strict(&mut data.as_bytes()).unwrap();
+        let report = oca_bundle.verify();
+        assert!(report.is_valid());
+        assert_eq!(report.overlays.len(), 1);
+    }
+
+    #[test]
+    fn detect_tampered_overlay() {
+        let data = oca_bundle_json().replace("Name: ", "Surname: ");
+        let result = load_oca_strict(&mut data.as_bytes());
+        let Err(LoadError::Integrity(report)) = result else {
+            panic!("expected integrity error");
+        };
+        assert!(report.bundle.said.is_some());
+        assert!(!report.bundle.is_valid());
+        assert!(report.capture_base.is_valid());
+        assert!(!report.overlays[0].digest.is_valid());
+        assert!(report.overlays[0].capture_base_matches);
+
+        let codes = report.errors().iter().map(|e| e.code()).collect::<Vec<_>>();
+        assert_eq!(
+            codes,
+            vec!["malformed_bundle_said", "malformed_overlay_said"]
+        );
+    }
+
+    #[test]
+    fn detect_overlay_of_other_capture_base() {
+        let data = oca_bundle_json();
+        let value: serde_json::Value = serde_json::from_str(&data).unwrap();
+        let capture_base_said = value["capture_base"]["d"].as_str().unwrap();
+        let data = data.replacen(
+            &format!(r#""capture_base":"{capture_base_said}""#),
+            r#""capture_base":"EBQMQm_tXSC8tnNICl7paGUeGg0SyF1tceHhTUutn1PN""#,
+            1,
+        );
+
+        let result = load_oca_strict(&mut data.as_bytes());
+        let Err(LoadError::Integrity(report)) = result else {
+            panic!("expected integrity error");
+        };
+        assert!(!report.overlays[0].capture_base_matches);
+    }
+}
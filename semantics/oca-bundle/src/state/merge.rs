@@ -0,0 +1,781 @@
+use crate::state::{
+    attribute::Attribute,
+    oca::{overlay::Overlay, DynOverlay},
+    oca::{OCABox, OCABundle},
+    patch::{self, expressible, overlay_content, patch_with, same_kind, KEYED_FIELDS},
+    said_options::SaidOptions,
+};
+use isolang::Language;
+use oca_ast_semantics::ast::OCAAst;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+/// Change made differently on both sides. Conflicting values are resolved
+/// to `ours`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "conflict", rename_all = "snake_case")]
+pub enum Conflict {
+    /// Attribute removed on one side and changed on the other one. The
+    /// changed attribute is kept.
+    Removal { attribute: String, removed_by: Side },
+    Attribute {
+        attribute: String,
+        field: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        key: Option<String>,
+        base: Value,
+        ours: Value,
+        theirs: Value,
+    },
+    /// Meta changed on both sides, or removed in `theirs` only, as meta
+    /// can't be removed by a patch.
+    Meta {
+        language: Language,
+        key: String,
+        base: Option<String>,
+        ours: Option<String>,
+        theirs: Option<String>,
+    },
+    Classification {
+        base: String,
+        ours: String,
+        theirs: String,
+    },
+    /// Overlay which isn't built from attributes, changed on both sides or
+    /// changed in `theirs` in a way a patch can't express. Missing overlays
+    /// are `null`.
+    Overlay {
+        overlay_type: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        language: Option<Language>,
+        base: Value,
+        ours: Value,
+        theirs: Value,
+    },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::Removal {
+                attribute,
+                removed_by,
+            } => {
+                let (removed, changed) = match removed_by {
+                    Side::Ours => ("ours", "theirs"),
+                    Side::Theirs => ("theirs", "ours"),
+                };
+                write!(
+                    f,
+                    "attribute {attribute} removed in {removed} and changed in {changed}"
+                )
+            }
+            Conflict::Attribute {
+                attribute,
+                field,
+                key,
+                base,
+                ours,
+                theirs,
+            } => {
+                write!(f, "attribute {attribute} {field}")?;
+                if let Some(key) = key {
+                    write!(f, ".{key}")?;
+                }
+                write!(f, ": base {base}, ours {ours}, theirs {theirs}")
+            }
+            Conflict::Meta {
+                language,
+                key,
+                base,
+                ours,
+                theirs,
+            } => write!(
+                f,
+                "meta {key} ({}): base {base:?}, ours {ours:?}, theirs {theirs:?}",
+                language.to_639_3()
+            ),
+            Conflict::Classification { base, ours, theirs } => write!(
+                f,
+                "classification: base {base:?}, ours {ours:?}, theirs {theirs:?}"
+            ),
+            Conflict::Overlay {
+                overlay_type,
+                language,
+                base,
+                ours,
+                theirs,
+            } => {
+                write!(f, "overlay {overlay_type}")?;
+                if let Some(language) = language {
+                    write!(f, " ({})", language.to_639_3())?;
+                }
+                write!(f, ": base {base}, ours {ours}, theirs {theirs}")
+            }
+        }
+    }
+}
+
+/// Result of a three-way merge.
+#[derive(Debug)]
+pub struct Merge {
+    pub oca_bundle: OCABundle,
+    /// Commands which applied on `ours` build the merged bundle.
+    pub oca_ast: OCAAst,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merge {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Merge changes made in `ours` and `theirs` since their common ancestor
+/// `base`. Changes made on one side only are combined, values changed
+/// differently on both sides are reported as conflicts and resolved to
+/// `ours`.
+pub fn merge(
+    base: &OCABundle,
+    ours: &OCABundle,
+    theirs: &OCABundle,
+) -> Result<Merge, Vec<patch::Error>> {
+    merge_with(base, ours, theirs, &patch::said_options(ours))
+}
+
+/// Merge computing SAIDs of the merged bundle with given options.
+pub fn merge_with(
+    base: &OCABundle,
+    ours: &OCABundle,
+    theirs: &OCABundle,
+    options: &SaidOptions,
+) -> Result<Merge, Vec<patch::Error>> {
+    let base_box = OCABox::from(base.clone());
+    let ours_box = OCABox::from(ours.clone());
+    let theirs_box = OCABox::from(theirs.clone());
+    let mut conflicts = vec![];
+    let mut merged = OCABox::new();
+
+    let mut names = ours_box
+        .attributes
+        .keys()
+        .chain(theirs_box.attributes.keys())
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let attribute = merge_attribute(
+            name,
+            base_box.attributes.get(name),
+            ours_box.attributes.get(name),
+            theirs_box.attributes.get(name),
+            &mut conflicts,
+        )
+        .map_err(|e| vec![e])?;
+        if let Some(attribute) = attribute {
+            merged.attributes.insert(name.clone(), attribute);
+        }
+    }
+
+    merged.meta = merge_meta(&base_box, &ours_box, &theirs_box, &mut conflicts);
+
+    let (base_classification, ours_classification, theirs_classification) = (
+        &base.capture_base.classification,
+        &ours.capture_base.classification,
+        &theirs.capture_base.classification,
+    );
+    let classification = match merge_value(
+        base_classification,
+        ours_classification,
+        theirs_classification,
+    ) {
+        Some(classification) => classification.clone(),
+        None => {
+            conflicts.push(Conflict::Classification {
+                base: base_classification.clone(),
+                ours: ours_classification.clone(),
+                theirs: theirs_classification.clone(),
+            });
+            ours_classification.clone()
+        }
+    };
+    merged.add_classification(classification);
+
+    merge_mappings(
+        &base_box,
+        &ours_box,
+        &theirs_box,
+        &mut merged,
+        &mut conflicts,
+    );
+    merge_custom_overlays(
+        &base_box,
+        &ours_box,
+        &theirs_box,
+        &mut merged,
+        &mut conflicts,
+    );
+
+    let oca_bundle = merged.generate_bundle_with(options);
+    let oca_ast = patch_with(ours, &oca_bundle, options)?;
+
+    Ok(Merge {
+        oca_bundle,
+        oca_ast,
+        conflicts,
+    })
+}
+
+/// Three-way merge of a single value, `None` on conflict.
+fn merge_value<'a, T: PartialEq>(base: &'a T, ours: &'a T, theirs: &'a T) -> Option<&'a T> {
+    if ours == theirs || theirs == base {
+        Some(ours)
+    } else if ours == base {
+        Some(theirs)
+    } else {
+        None
+    }
+}
+
+fn merge_attribute(
+    name: &str,
+    base: Option<&Attribute>,
+    ours: Option<&Attribute>,
+    theirs: Option<&Attribute>,
+    conflicts: &mut Vec<Conflict>,
+) -> Result<Option<Attribute>, patch::Error> {
+    let (base_values, ours_values, theirs_values) = (to_map(base), to_map(ours), to_map(theirs));
+    match (base, ours, theirs) {
+        (Some(_), None, Some(theirs)) => {
+            if theirs_values == base_values {
+                return Ok(None);
+            }
+            conflicts.push(Conflict::Removal {
+                attribute: name.to_string(),
+                removed_by: Side::Ours,
+            });
+            return Ok(Some(theirs.clone()));
+        }
+        (Some(_), Some(ours), None) => {
+            if ours_values == base_values {
+                return Ok(None);
+            }
+            conflicts.push(Conflict::Removal {
+                attribute: name.to_string(),
+                removed_by: Side::Theirs,
+            });
+            return Ok(Some(ours.clone()));
+        }
+        (_, Some(ours), None) => return Ok(Some(ours.clone())),
+        (_, None, Some(theirs)) => return Ok(Some(theirs.clone())),
+        (_, None, None) => return Ok(None),
+        _ => {}
+    }
+
+    let mut merged = Map::new();
+    let mut fields = ours_values.keys().collect::<Vec<_>>();
+    fields.extend(
+        theirs_values
+            .keys()
+            .filter(|f| !ours_values.contains_key(*f)),
+    );
+    for field in fields {
+        if field == "dependencies" {
+            continue;
+        }
+        let base_value = base_values.get(field).unwrap_or(&Value::Null);
+        let ours_value = ours_values.get(field).unwrap_or(&Value::Null);
+        let theirs_value = theirs_values.get(field).unwrap_or(&Value::Null);
+
+        if KEYED_FIELDS.contains(&field.as_str()) {
+            let entries =
+                merge_entries(name, field, base_value, ours_value, theirs_value, conflicts);
+            merged.insert(field.clone(), entries);
+            continue;
+        }
+
+        let value = match merge_value(base_value, ours_value, theirs_value) {
+            Some(value) => value,
+            None => {
+                conflicts.push(Conflict::Attribute {
+                    attribute: name.to_string(),
+                    field: field.clone(),
+                    key: None,
+                    base: base_value.clone(),
+                    ours: ours_value.clone(),
+                    theirs: theirs_value.clone(),
+                });
+                ours_value
+            }
+        };
+        if field == "condition" {
+            // Dependencies are derived from the condition.
+            let dependencies = if value == ours_value {
+                ours_values.get("dependencies")
+            } else {
+                theirs_values.get("dependencies")
+            };
+            merged.insert(
+                "dependencies".to_string(),
+                dependencies.cloned().unwrap_or(Value::Null),
+            );
+        }
+        merged.insert(field.clone(), value.clone());
+    }
+
+    serde_json::from_value(Value::Object(merged))
+        .map(Some)
+        .map_err(|e| patch::Error::Merge {
+            attribute: name.to_string(),
+            message: e.to_string(),
+        })
+}
+
+fn merge_entries(
+    name: &str,
+    field: &str,
+    base: &Value,
+    ours: &Value,
+    theirs: &Value,
+    conflicts: &mut Vec<Conflict>,
+) -> Value {
+    let empty = Map::new();
+    let as_map =
+        |value: &'_ Value| -> Map<String, Value> { value.as_object().unwrap_or(&empty).clone() };
+    let (base_entries, ours_entries, theirs_entries) = (as_map(base), as_map(ours), as_map(theirs));
+
+    let mut keys = ours_entries.keys().collect::<Vec<_>>();
+    keys.extend(
+        theirs_entries
+            .keys()
+            .filter(|key| !ours_entries.contains_key(*key)),
+    );
+    keys.extend(
+        base_entries
+            .keys()
+            .filter(|key| !ours_entries.contains_key(*key) && !theirs_entries.contains_key(*key)),
+    );
+
+    let mut merged = Map::new();
+    for key in keys {
+        let base_value = base_entries.get(key).unwrap_or(&Value::Null);
+        let ours_value = ours_entries.get(key).unwrap_or(&Value::Null);
+        let theirs_value = theirs_entries.get(key).unwrap_or(&Value::Null);
+        let value = match merge_value(base_value, ours_value, theirs_value) {
+            Some(value) => value,
+            None => {
+                conflicts.push(Conflict::Attribute {
+                    attribute: name.to_string(),
+                    field: field.to_string(),
+                    key: Some(key.clone()),
+                    base: base_value.clone(),
+                    ours: ours_value.clone(),
+                    theirs: theirs_value.clone(),
+                });
+                ours_value
+            }
+        };
+        if !value.is_null() {
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+
+    match merged.is_empty() {
+        true => Value::Null,
+        false => Value::Object(merged),
+    }
+}
+
+fn to_map(attribute: Option<&Attribute>) -> Map<String, Value> {
+    match attribute.map(serde_json::to_value) {
+        Some(Ok(Value::Object(values))) => values,
+        _ => Map::new(),
+    }
+}
+
+type Meta = HashMap<Language, HashMap<String, String>>;
+
+fn merge_meta(
+    base: &OCABox,
+    ours: &OCABox,
+    theirs: &OCABox,
+    conflicts: &mut Vec<Conflict>,
+) -> Option<Meta> {
+    let base_meta = base.meta.clone().unwrap_or_default();
+    let ours_meta = ours.meta.clone().unwrap_or_default();
+    let theirs_meta = theirs.meta.clone().unwrap_or_default();
+
+    let mut keys = vec![];
+    for meta in [&base_meta, &ours_meta, &theirs_meta] {
+        for (language, pairs) in meta {
+            for key in pairs.keys() {
+                if !keys.contains(&(*language, key)) {
+                    keys.push((*language, key));
+                }
+            }
+        }
+    }
+    keys.sort_by_key(|(language, key)| (language.to_639_3(), *key));
+
+    let get = |meta: &Meta, language: &Language, key: &str| {
+        meta.get(language).and_then(|pairs| pairs.get(key)).cloned()
+    };
+    let mut merged: Meta = HashMap::new();
+    for (language, key) in keys {
+        let base_value = get(&base_meta, &language, key);
+        let ours_value = get(&ours_meta, &language, key);
+        let theirs_value = get(&theirs_meta, &language, key);
+        let value = match merge_value(&base_value, &ours_value, &theirs_value) {
+            // Meta can't be removed with OCAFILE commands, so the merged
+            // bundle couldn't be built from `ours` without it.
+            Some(None) if ours_value.is_some() => {
+                conflicts.push(Conflict::Meta {
+                    language,
+                    key: key.clone(),
+                    base: base_value.clone(),
+                    ours: ours_value.clone(),
+                    theirs: theirs_value.clone(),
+                });
+                ours_value
+            }
+            Some(value) => value.clone(),
+            None => {
+                conflicts.push(Conflict::Meta {
+                    language,
+                    key: key.clone(),
+                    base: base_value.clone(),
+                    ours: ours_value.clone(),
+                    theirs: theirs_value.clone(),
+                });
+                ours_value
+            }
+        };
+        if let Some(value) = value {
+            merged
+                .entry(language)
+                .or_default()
+                .insert(key.clone(), value);
+        }
+    }
+
+    match merged.is_empty() {
+        true => None,
+        false => Some(merged),
+    }
+}
+
+/// Attribute mappings are merged as a whole. A patch can't add or remove
+/// them, so any change taken from `theirs` is a conflict.
+fn merge_mappings(
+    base: &OCABox,
+    ours: &OCABox,
+    theirs: &OCABox,
+    merged: &mut OCABox,
+    conflicts: &mut Vec<Conflict>,
+) {
+    let values = |oca: &OCABox| {
+        oca.mappings
+            .iter()
+            .flatten()
+            .map(|mapping| mapping.attribute_mapping.clone())
+            .collect::<Vec<_>>()
+    };
+    let (base_values, ours_values, theirs_values) = (values(base), values(ours), values(theirs));
+    if merge_value(&base_values, &ours_values, &theirs_values) != Some(&ours_values) {
+        let mapping = [ours, theirs, base]
+            .into_iter()
+            .find_map(|oca| oca.mappings.iter().flatten().next());
+        if let Some(mapping) = mapping {
+            let as_value = |values| serde_json::to_value(values).unwrap_or_default();
+            conflicts.push(Conflict::Overlay {
+                overlay_type: mapping.overlay_type().to_string(),
+                language: None,
+                base: as_value(&base_values),
+                ours: as_value(&ours_values),
+                theirs: as_value(&theirs_values),
+            });
+        }
+    }
+    merged.mappings.clone_from(&ours.mappings);
+}
+
+/// Custom overlays are merged one per type and language. Changes are taken
+/// from `theirs` only when a patch can add them.
+fn merge_custom_overlays(
+    base: &OCABox,
+    ours: &OCABox,
+    theirs: &OCABox,
+    merged: &mut OCABox,
+    conflicts: &mut Vec<Conflict>,
+) {
+    let mut kinds: Vec<&DynOverlay> = vec![];
+    for overlay in [ours, theirs, base]
+        .into_iter()
+        .flat_map(|oca| &oca.custom_overlays)
+    {
+        if !kinds.iter().any(|kind| same_kind(kind, overlay)) {
+            kinds.push(overlay);
+        }
+    }
+
+    for kind in kinds {
+        let (base_overlay, ours_overlay, theirs_overlay) = (
+            find_overlay(base, kind),
+            find_overlay(ours, kind),
+            find_overlay(theirs, kind),
+        );
+        let content = |overlay: Option<&DynOverlay>| overlay.map_or(Value::Null, overlay_content);
+        let (base_value, ours_value, theirs_value) = (
+            content(base_overlay),
+            content(ours_overlay),
+            content(theirs_overlay),
+        );
+        let overlay = match merge_value(&base_value, &ours_value, &theirs_value) {
+            Some(value) if *value == ours_value => ours_overlay,
+            Some(_) if theirs_overlay.is_some_and(expressible) => theirs_overlay,
+            _ => {
+                conflicts.push(Conflict::Overlay {
+                    overlay_type: kind.overlay_type().to_string(),
+                    language: kind.language().copied(),
+                    base: base_value,
+                    ours: ours_value,
+                    theirs: theirs_value,
+                });
+                ours_overlay
+            }
+        };
+        if let Some(overlay) = overlay {
+            merged.custom_overlays.push(overlay.clone());
+        }
+    }
+}
+
+fn find_overlay<'a>(oca: &'a OCABox, kind: &DynOverlay) -> Option<&'a DynOverlay> {
+    oca.custom_overlays
+        .iter()
+        .find(|overlay| same_kind(overlay, kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{attribute, consent, mapping};
+    use crate::state::{
+        attribute::AttributeType,
+        oca::overlay::{information::Information, label::Labels, meta::Metas},
+    };
+
+    fn base() -> OCABox {
+        let mut oca = OCABox::new();
+        oca.add_meta(Language::Eng, "name".to_string(), "Person".to_string());
+        oca.add_attribute(attribute("name", AttributeType::Text, "Name"));
+        oca.add_attribute(attribute("age", AttributeType::Numeric, "Age"));
+        oca
+    }
+
+    #[test]
+    fn merge_without_conflicts() {
+        let base_bundle = base().generate_bundle();
+
+        let mut ours = base();
+        ours.add_attribute(cascade! {
+            attribute("name", AttributeType::Text, "Name");
+            ..set_label(Language::Pol, "Imię".to_string());
+        });
+        let ours = ours.generate_bundle();
+
+        let mut theirs = base();
+        theirs.add_attribute(cascade! {
+            attribute("age", AttributeType::Numeric, "Age");
+            ..set_information(Language::Eng, "Age in years".to_string());
+        });
+        theirs.add_attribute(attribute("email", AttributeType::Text, "Email"));
+        let theirs = theirs.generate_bundle();
+
+        let result = merge(&base_bundle, &ours, &theirs).unwrap();
+        assert!(result.is_clean());
+
+        let mut expected = base();
+        expected.add_attribute(cascade! {
+            attribute("name", AttributeType::Text, "Name");
+            ..set_label(Language::Pol, "Imię".to_string());
+        });
+        expected.add_attribute(cascade! {
+            attribute("age", AttributeType::Numeric, "Age");
+            ..set_information(Language::Eng, "Age in years".to_string());
+        });
+        expected.add_attribute(attribute("email", AttributeType::Text, "Email"));
+        assert_eq!(result.oca_bundle.said, expected.generate_bundle().said);
+        assert!(result.oca_ast.commands.len() > 1);
+    }
+
+    #[test]
+    fn merge_with_conflicts() {
+        let base_bundle = base().generate_bundle();
+
+        let mut ours = base();
+        ours.add_attribute(attribute("name", AttributeType::Text, "Full name"));
+        ours.add_attribute(attribute("age", AttributeType::Text, "Age"));
+        ours.add_meta(Language::Eng, "name".to_string(), "Human".to_string());
+        let ours = ours.generate_bundle();
+
+        let mut theirs = base();
+        theirs.add_attribute(attribute("name", AttributeType::Text, "First name"));
+        theirs.add_attribute(attribute("age", AttributeType::DateTime, "Age"));
+        theirs.add_meta(Language::Eng, "name".to_string(), "Individual".to_string());
+        let theirs = theirs.generate_bundle();
+
+        let result = merge(&base_bundle, &ours, &theirs).unwrap();
+        assert_eq!(result.oca_bundle.said, ours.said);
+        assert_eq!(
+            result.conflicts,
+            vec![
+                Conflict::Attribute {
+                    attribute: "age".to_string(),
+                    field: "type".to_string(),
+                    key: None,
+                    base: Value::String("Numeric".to_string()),
+                    ours: Value::String("Text".to_string()),
+                    theirs: Value::String("DateTime".to_string()),
+                },
+                Conflict::Attribute {
+                    attribute: "name".to_string(),
+                    field: "labels".to_string(),
+                    key: Some("eng".to_string()),
+                    base: Value::String("Name".to_string()),
+                    ours: Value::String("Full name".to_string()),
+                    theirs: Value::String("First name".to_string()),
+                },
+                Conflict::Meta {
+                    language: Language::Eng,
+                    key: "name".to_string(),
+                    base: Some("Person".to_string()),
+                    ours: Some("Human".to_string()),
+                    theirs: Some("Individual".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            result.conflicts[1].to_string(),
+            r#"attribute name labels.eng: base "Name", ours "Full name", theirs "First name""#
+        );
+    }
+
+    #[test]
+    fn merge_removed_and_changed_attribute() {
+        let base_bundle = base().generate_bundle();
+
+        let mut ours = base();
+        ours.remove_attribute(&"age".to_string());
+        let ours = ours.generate_bundle();
+
+        let mut theirs = base();
+        theirs.add_attribute(cascade! {
+            attribute("age", AttributeType::Numeric, "Age");
+            ..set_label(Language::Pol, "Wiek".to_string());
+        });
+        let theirs = theirs.generate_bundle();
+
+        let result = merge(&base_bundle, &ours, &theirs).unwrap();
+        assert_eq!(
+            result.conflicts,
+            vec![Conflict::Removal {
+                attribute: "age".to_string(),
+                removed_by: Side::Ours,
+            }]
+        );
+        assert_eq!(result.oca_bundle.said, theirs.said);
+    }
+
+    #[test]
+    fn merge_removed_meta() {
+        let base_bundle = base().generate_bundle();
+        let ours = base().generate_bundle();
+
+        let mut theirs = base();
+        theirs.meta = None;
+        theirs.add_attribute(attribute("email", AttributeType::Text, "Email"));
+        let theirs = theirs.generate_bundle();
+
+        let result = merge(&base_bundle, &ours, &theirs).unwrap();
+        assert_eq!(
+            result.conflicts,
+            vec![Conflict::Meta {
+                language: Language::Eng,
+                key: "name".to_string(),
+                base: Some("Person".to_string()),
+                ours: Some("Person".to_string()),
+                theirs: None,
+            }]
+        );
+        let mut expected = base();
+        expected.add_attribute(attribute("email", AttributeType::Text, "Email"));
+        assert_eq!(result.oca_bundle.said, expected.generate_bundle().said);
+    }
+
+    #[test]
+    fn merge_keeps_custom_overlays() {
+        let mut oca = base();
+        oca.add_custom_overlay(consent("required"));
+        oca.add_attribute_mapping(mapping());
+        let base_bundle = oca.generate_bundle();
+
+        let result = merge(&base_bundle, &base_bundle, &base_bundle).unwrap();
+        assert!(result.is_clean());
+        assert_eq!(result.oca_bundle.said, base_bundle.said);
+
+        oca.add_attribute(attribute("email", AttributeType::Text, "Email"));
+        let theirs = oca.generate_bundle();
+        let result = merge(&base_bundle, &base_bundle, &theirs).unwrap();
+        assert!(result.is_clean());
+        assert_eq!(result.oca_bundle.said, theirs.said);
+    }
+
+    #[test]
+    fn merge_changed_custom_overlays() {
+        let mut oca = base();
+        oca.add_custom_overlay(consent("required"));
+        let base_bundle = oca.generate_bundle();
+
+        let mut ours = base();
+        ours.add_custom_overlay(consent("optional"));
+        let ours_bundle = ours.generate_bundle();
+
+        let mut theirs = base();
+        theirs.add_custom_overlay(consent("none"));
+        theirs.add_attribute_mapping(mapping());
+        let theirs = theirs.generate_bundle();
+
+        let result = merge(&base_bundle, &ours_bundle, &theirs).unwrap();
+        let content = |value: &str| serde_json::json!({"type": "spec/overlays/community_consent/1.0", "name": value});
+        assert_eq!(
+            result.conflicts,
+            vec![
+                Conflict::Overlay {
+                    overlay_type: "AttributeMapping".to_string(),
+                    language: None,
+                    base: serde_json::json!([]),
+                    ours: serde_json::json!([]),
+                    theirs: serde_json::json!([{"name": "full_name"}]),
+                },
+                Conflict::Overlay {
+                    overlay_type: "community_consent".to_string(),
+                    language: None,
+                    base: content("required"),
+                    ours: content("optional"),
+                    theirs: content("none"),
+                }
+            ]
+        );
+        assert_eq!(result.oca_bundle.said, ours_bundle.said);
+    }
+}
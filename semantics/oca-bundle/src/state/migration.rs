@@ -0,0 +1,866 @@
+//! Migration of bundles of older OCA specification versions.
+//!
+//! Bundles are upgraded as JSON to the layout of the current version before
+//! they are read, so only fields of the current version survive. SAIDs are
+//! recomputed, so references to migrated bundles are updated only if their
+//! new SAIDs are given. Every change which loses information is reported.
+//!
+//! Bundles can be exported in the layout of an older version with
+//! [`export`].
+use crate::state::{
+    oca::{capture_base::CaptureBase, deserialize_overlay, DynOverlay, OCABundle},
+    patch::said_options,
+    said_options::SaidOptions,
+    spec_version::SpecVersion,
+};
+use isolang::Language;
+use oca_ast_semantics::ast::OverlayType;
+use said::{
+    derivation::{HashFunction, HashFunctionCode},
+    SelfAddressingIdentifier,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::{collections::HashMap, io::Read};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum Error {
+    #[error("OCA specification version {version} is not supported")]
+    UnsupportedVersion { version: String },
+    #[error("Invalid OCA Bundle at '{pointer}': {message}")]
+    InvalidBundle { pointer: String, message: String },
+}
+
+/// Change made to the bundle, `pointer` being JSON Pointer to the changed
+/// value in the original bundle.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    pub pointer: String,
+    pub message: String,
+    /// Whether information is lost, so the migrated bundle can't be turned
+    /// back into the original one.
+    pub lossy: bool,
+}
+
+#[derive(Debug)]
+pub struct Migration {
+    pub bundle: OCABundle,
+    /// Version the bundle was migrated from.
+    pub from: SpecVersion,
+    /// SAID of the bundle before migration.
+    pub original_said: Option<String>,
+    pub changes: Vec<Change>,
+}
+
+impl Migration {
+    pub fn lossy_changes(&self) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(|change| change.lossy)
+    }
+
+    pub fn is_lossless(&self) -> bool {
+        self.lossy_changes().next().is_none()
+    }
+}
+
+/// Read bundle of any supported OCA specification version and migrate it to
+/// the current one.
+pub fn load(source: &mut dyn Read) -> Result<Migration, Error> {
+    let bundle: Value = serde_json::from_reader(source).map_err(|e| invalid("", e))?;
+    migrate(bundle)
+}
+
+pub fn migrate(bundle: Value) -> Result<Migration, Error> {
+    migrate_with(bundle, &SaidOptions::default(), &HashMap::new())
+}
+
+/// Migrate bundle to the current OCA specification version computing SAIDs
+/// with given options. Bundles of the current version are read as they are.
+///
+/// `references` maps original SAIDs of migrated dependencies to their new
+/// ones (see [`Migration::original_said`]). Other references are kept and
+/// reported as lossy, as they dangle once the referenced bundle is migrated.
+pub fn migrate_with(
+    bundle: Value,
+    options: &SaidOptions,
+    references: &HashMap<String, SelfAddressingIdentifier>,
+) -> Result<Migration, Error> {
+    let Value::Object(mut root) = bundle else {
+        return Err(invalid("", "bundle must be an object"));
+    };
+    let from = spec_version(&root)?;
+    if from == SpecVersion::CURRENT {
+        let bundle: OCABundle =
+            serde_json::from_value(Value::Object(root)).map_err(|e| invalid("", e))?;
+        return Ok(Migration {
+            original_said: bundle.said.as_ref().map(ToString::to_string),
+            bundle,
+            from,
+            changes: vec![],
+        });
+    }
+
+    let mut changes = vec![];
+    let old_said = take_said(&mut root);
+    // Legacy bundles declare the version, e.g. `OCAB10000023_`, which is now
+    // a part of the SAID derivation.
+    root.remove("version");
+
+    let mut capture_base = match root.remove("capture_base") {
+        Some(Value::Object(capture_base)) => capture_base,
+        _ => return Err(invalid("/capture_base", "capture base must be an object")),
+    };
+    take_said(&mut capture_base);
+    capture_base.insert(
+        "type".to_string(),
+        Value::String(format!("spec/capture_base/{}", SpecVersion::CURRENT)),
+    );
+    let mut dates = vec![];
+    if let Some(Value::Object(attributes)) = capture_base.get_mut("attributes") {
+        for (name, attribute_type) in attributes.iter_mut() {
+            let pointer = format!("/capture_base/attributes/{}", escape(name));
+            if is_date(attribute_type) {
+                dates.push(name.clone());
+            }
+            *attribute_type =
+                migrate_attribute_type(attribute_type, &pointer, references, &mut changes)?;
+        }
+    }
+    let mut capture_base_value = Value::Object(capture_base);
+    let mut capture_base: CaptureBase = serde_json::from_value(capture_base_value.clone())
+        .map_err(|e| invalid("/capture_base", e))?;
+    report_dropped(
+        "/capture_base",
+        &capture_base_value,
+        &capture_base,
+        &mut changes,
+    );
+    capture_base.sign_with(options);
+    capture_base_value = serde_json::to_value(&capture_base).map_err(|e| invalid("", e))?;
+    let cb_said = capture_base.said.clone().unwrap();
+
+    let mut listed = listed_overlays(root.remove("overlays"))?;
+    add_date_formats(&mut listed, &dates);
+    let mut overlays: Vec<DynOverlay> = vec![];
+    for (pointer, mut overlay, many) in listed {
+        migrate_overlay(&pointer, &mut overlay, &capture_base_value, &mut changes)?;
+        let mut migrated = deserialize_overlay(overlay.clone(), many)
+            .map_err(|message| invalid(&pointer, message))?;
+        report_dropped(&pointer, &overlay, &migrated, &mut changes);
+        migrated.sign_with(&cb_said, options);
+        overlays.push(migrated);
+    }
+
+    for field in root.keys() {
+        changes.push(Change {
+            pointer: format!("/{}", escape(field)),
+            message: format!("field `{field}` is not part of the bundle and is dropped"),
+            lossy: true,
+        });
+    }
+
+    let mut bundle = OCABundle {
+        said: None,
+        capture_base,
+        overlays,
+    };
+    bundle.fill_said_with(options);
+    changes.insert(
+        0,
+        Change {
+            pointer: "/d".to_string(),
+            message: match &old_said {
+                Some(old_said) => format!("SAIDs are recomputed, bundle was {old_said}"),
+                None => "SAIDs are recomputed".to_string(),
+            },
+            lossy: false,
+        },
+    );
+
+    Ok(Migration {
+        bundle,
+        from,
+        original_said: old_said,
+        changes,
+    })
+}
+
+/// Bundle in the JSON layout of given OCA specification version.
+///
+/// In OCA 1.0 layout the bundle declares its `version`, SAIDs are given in
+/// `said` fields, attribute types are written as `Array[T]` and
+/// `Reference:<SAID>` and languages as ISO 639-1 codes when there is one.
+/// SAIDs are recomputed with the digest algorithm of the bundle SAID.
+pub fn export(bundle: &OCABundle, version: SpecVersion) -> Result<Value, Error> {
+    let value = serde_json::to_value(bundle).map_err(|e| invalid("", e))?;
+    if version == SpecVersion::CURRENT {
+        return Ok(value);
+    }
+    let Value::Object(mut root) = value else {
+        return Err(invalid("", "bundle must be an object"));
+    };
+    let code = said_options(bundle).code;
+
+    let mut capture_base = match root.remove("capture_base") {
+        Some(Value::Object(capture_base)) => rename_said(capture_base),
+        _ => return Err(invalid("/capture_base", "capture base must be an object")),
+    };
+    capture_base.insert(
+        "type".to_string(),
+        Value::String(format!("spec/capture_base/{version}")),
+    );
+    if let Some(Value::Object(attributes)) = capture_base.get_mut("attributes") {
+        for (name, attribute_type) in attributes.iter_mut() {
+            let pointer = format!("/capture_base/attributes/{}", escape(name));
+            *attribute_type = Value::String(legacy_attribute_type(attribute_type, &pointer)?);
+        }
+    }
+    fill_said(&mut capture_base, &code);
+    let cb_said = capture_base["said"].clone();
+
+    let mut overlays = Map::new();
+    if let Some(Value::Object(listed)) = root.remove("overlays") {
+        for (name, overlay) in listed {
+            let pointer = format!("/overlays/{}", escape(&name));
+            let overlay = match overlay {
+                Value::Array(many) => Value::Array(
+                    many.into_iter()
+                        .enumerate()
+                        .map(|(i, overlay)| {
+                            let pointer = format!("{pointer}/{i}");
+                            legacy_overlay(&pointer, overlay, &cb_said, version, &code)
+                        })
+                        .collect::<Result<_, _>>()?,
+                ),
+                overlay => legacy_overlay(&pointer, overlay, &cb_said, version, &code)?,
+            };
+            overlays.insert(name, overlay);
+        }
+    }
+
+    let mut legacy = Map::new();
+    legacy.insert("version".to_string(), legacy_version(version, 0));
+    legacy.insert("said".to_string(), said_placeholder(&code));
+    legacy.insert("capture_base".to_string(), Value::Object(capture_base));
+    legacy.insert("overlays".to_string(), Value::Object(overlays));
+    let size = Value::Object(legacy.clone()).to_string().len();
+    legacy.insert("version".to_string(), legacy_version(version, size));
+    fill_said(&mut legacy, &code);
+
+    Ok(Value::Object(legacy))
+}
+
+/// Version of the bundle, e.g. `OCAB10000123_`, with its size in bytes.
+fn legacy_version(version: SpecVersion, size: usize) -> Value {
+    Value::String(format!(
+        "OCAB{}{size:06x}_",
+        version.as_str().replace('.', "")
+    ))
+}
+
+fn legacy_overlay(
+    pointer: &str,
+    overlay: Value,
+    capture_base: &Value,
+    version: SpecVersion,
+    code: &HashFunctionCode,
+) -> Result<Value, Error> {
+    let Value::Object(overlay) = overlay else {
+        return Err(invalid(pointer, "overlay must be an object"));
+    };
+    let mut overlay = rename_said(overlay);
+    overlay.insert("capture_base".to_string(), capture_base.clone());
+    let overlay_type = overlay
+        .get("type")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid(pointer, "overlay type is missing"))?
+        .to_string();
+    if is_specified(&overlay_type) {
+        let (prefix, _) = overlay_type.rsplit_once('/').unwrap();
+        overlay.insert(
+            "type".to_string(),
+            Value::String(format!("{prefix}/{version}")),
+        );
+        if let Some(Value::String(language)) = overlay.get("language") {
+            if let Some(code) = Language::from_639_3(language).and_then(|lang| lang.to_639_1()) {
+                overlay.insert("language".to_string(), Value::String(code.to_string()));
+            }
+        }
+        if prefix.ends_with("/unit") {
+            if let Some(units) = overlay.remove("attribute_unit") {
+                overlay.insert("attribute_units".to_string(), units);
+            }
+        }
+    }
+    fill_said(&mut overlay, code);
+
+    Ok(Value::Object(overlay))
+}
+
+/// Attribute type of OCA 1.0, e.g. `Array[Text]` for `["Text"]` and
+/// `Reference:<SAID>` for `refs:<SAID>`.
+fn legacy_attribute_type(attribute_type: &Value, pointer: &str) -> Result<String, Error> {
+    match attribute_type {
+        Value::Array(element) => match element.first() {
+            Some(element) => Ok(format!(
+                "Array[{}]",
+                legacy_attribute_type(element, pointer)?
+            )),
+            None => Err(invalid(pointer, "array has no element type")),
+        },
+        Value::String(attribute_type) => {
+            if let Some(said) = attribute_type.strip_prefix("refs:") {
+                Ok(format!("Reference:{said}"))
+            } else if attribute_type.starts_with("refn:") {
+                Err(invalid(
+                    pointer,
+                    "reference by name can't be expressed in OCA 1.0",
+                ))
+            } else {
+                Ok(attribute_type.clone())
+            }
+        }
+        other => Err(invalid(pointer, format!("unknown attribute type {other}"))),
+    }
+}
+
+/// Object with `d` field renamed to `said`, as SAIDs were named before.
+fn rename_said(object: Map<String, Value>) -> Map<String, Value> {
+    object
+        .into_iter()
+        .map(|(key, value)| match key.as_str() {
+            "d" => ("said".to_string(), value),
+            _ => (key, value),
+        })
+        .collect()
+}
+
+fn said_placeholder(code: &HashFunctionCode) -> Value {
+    let length = HashFunction::from(code.clone())
+        .derive(&[])
+        .to_string()
+        .len();
+    Value::String("#".repeat(length))
+}
+
+/// Compute `said` field over JSON serialization of the object with the field
+/// filled with `#`.
+fn fill_said(object: &mut Map<String, Value>, code: &HashFunctionCode) {
+    object.insert("said".to_string(), said_placeholder(code));
+    let said = HashFunction::from(code.clone())
+        .derive(Value::Object(object.clone()).to_string().as_bytes());
+    object.insert("said".to_string(), Value::String(said.to_string()));
+}
+
+/// The oldest version used by the bundle, its capture base or any overlay
+/// defined by the specification.
+fn spec_version(root: &Map<String, Value>) -> Result<SpecVersion, Error> {
+    if let Some(Value::String(version)) = root.get("version") {
+        if version.starts_with("OCAB10") || version.starts_with("OCAS10") {
+            return Ok(SpecVersion::V1_0);
+        }
+    }
+    let capture_base_type = root
+        .get("capture_base")
+        .and_then(|capture_base| capture_base.get("type"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid("/capture_base/type", "capture base type is missing"))?;
+    let mut version = SpecVersion::of_type(capture_base_type)?;
+    for (pointer, overlay, _) in listed_overlays(root.get("overlays").cloned())? {
+        let overlay_type = overlay
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid(&pointer, "overlay type is missing"))?;
+        if is_specified(overlay_type) {
+            version = version.min(SpecVersion::of_type(overlay_type)?);
+        }
+    }
+
+    Ok(version)
+}
+
+/// Whether the overlay type is defined by the OCA specification, so it
+/// follows its version.
+fn is_specified(overlay_type: &str) -> bool {
+    !matches!(
+        serde_json::from_value(Value::String(overlay_type.to_string())),
+        Ok(OverlayType::Custom { .. }) | Err(_)
+    )
+}
+
+/// Overlays with their pointers and whether they are listed. Bundles of OCA
+/// 1.0 list them all in an array, overlays of a type given more than once
+/// are listed then.
+fn listed_overlays(overlays: Option<Value>) -> Result<Vec<(String, Value, bool)>, Error> {
+    let mut listed = vec![];
+    match overlays {
+        None => (),
+        Some(Value::Array(overlays)) => {
+            let types: Vec<Option<&Value>> =
+                overlays.iter().map(|overlay| overlay.get("type")).collect();
+            let many: Vec<bool> = types
+                .iter()
+                .map(|t| types.iter().filter(|other| *other == t).count() > 1)
+                .collect();
+            for (i, overlay) in overlays.into_iter().enumerate() {
+                listed.push((format!("/overlays/{i}"), overlay, many[i]));
+            }
+        }
+        Some(Value::Object(overlays)) => {
+            for (name, overlay) in overlays {
+                let pointer = format!("/overlays/{}", escape(&name));
+                match overlay {
+                    Value::Array(overlays) => {
+                        for (i, overlay) in overlays.into_iter().enumerate() {
+                            listed.push((format!("{pointer}/{i}"), overlay, true));
+                        }
+                    }
+                    overlay => listed.push((pointer, overlay, false)),
+                }
+            }
+        }
+        Some(_) => return Err(invalid("/overlays", "overlays must be an object")),
+    }
+
+    Ok(listed)
+}
+
+/// Add `YYYY-MM-DD` format to attributes which were of Date type, unless they
+/// have a format already. Format overlay is added if there is none.
+fn add_date_formats(listed: &mut Vec<(String, Value, bool)>, dates: &[String]) {
+    if dates.is_empty() {
+        return;
+    }
+    let format_overlay = listed.iter_mut().find_map(|(_, overlay, _)| {
+        let overlay_type = overlay.get("type").and_then(Value::as_str)?;
+        overlay_type
+            .starts_with("spec/overlays/format/")
+            .then_some(overlay)
+    });
+    let format_overlay = match format_overlay {
+        Some(overlay) => overlay,
+        None => {
+            listed.push((
+                "/overlays/format".to_string(),
+                serde_json::json!({"type": "spec/overlays/format/1.0"}),
+                false,
+            ));
+            &mut listed.last_mut().unwrap().1
+        }
+    };
+    let Value::Object(format_overlay) = format_overlay else {
+        return;
+    };
+    if let Value::Object(formats) = format_overlay
+        .entry("attribute_formats")
+        .or_insert_with(|| Value::Object(Map::new()))
+    {
+        for name in dates {
+            formats
+                .entry(name.clone())
+                .or_insert_with(|| Value::String("YYYY-MM-DD".to_string()));
+        }
+    }
+}
+
+fn migrate_overlay(
+    pointer: &str,
+    overlay: &mut Value,
+    capture_base: &Value,
+    changes: &mut Vec<Change>,
+) -> Result<(), Error> {
+    let Value::Object(overlay) = overlay else {
+        return Err(invalid(pointer, "overlay must be an object"));
+    };
+    take_said(overlay);
+    overlay.insert("capture_base".to_string(), capture_base["d"].clone());
+    let overlay_type = overlay
+        .get("type")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid(pointer, "overlay type is missing"))?
+        .to_string();
+    if !is_specified(&overlay_type) {
+        // Versions of other overlays aren't tied to the specification.
+        return Ok(());
+    }
+    let (prefix, _) = overlay_type.rsplit_once('/').unwrap();
+    overlay.insert(
+        "type".to_string(),
+        Value::String(format!("{prefix}/{}", SpecVersion::CURRENT)),
+    );
+
+    if let Some(Value::String(language)) = overlay.get("language") {
+        // Languages were given as ISO 639-1 codes, e.g. `En`.
+        let code = language.to_lowercase();
+        let language = Language::from_639_3(&code)
+            .or_else(|| Language::from_639_1(&code))
+            .ok_or_else(|| {
+                invalid(
+                    &format!("{pointer}/language"),
+                    format!("unknown language {language}"),
+                )
+            })?;
+        overlay.insert(
+            "language".to_string(),
+            Value::String(language.to_639_3().to_string()),
+        );
+    }
+
+    match prefix.rsplit('/').next() {
+        Some("character_encoding") => {
+            if let Some(default) = overlay.remove("default_character_encoding") {
+                let encodings = overlay
+                    .entry("attribute_character_encoding")
+                    .or_insert_with(|| Value::Object(Map::new()));
+                if let (Value::Object(encodings), Some(Value::Object(attributes))) =
+                    (encodings, capture_base.get("attributes"))
+                {
+                    for name in attributes.keys() {
+                        encodings
+                            .entry(name.clone())
+                            .or_insert_with(|| default.clone());
+                    }
+                }
+                changes.push(Change {
+                    pointer: format!("{pointer}/attribute_character_encoding"),
+                    message: format!(
+                        "default character encoding {default} is set on attributes without one"
+                    ),
+                    lossy: false,
+                });
+            }
+        }
+        Some("unit") => {
+            if let Some(units) = overlay.remove("attribute_units") {
+                overlay.insert("attribute_unit".to_string(), units);
+            }
+            if let Some(metric_system) = overlay.remove("metric_system") {
+                changes.push(Change {
+                    pointer: format!("{pointer}/metric_system"),
+                    message: format!("metric system {metric_system} is dropped"),
+                    lossy: true,
+                });
+            }
+        }
+        _ => (),
+    }
+
+    Ok(())
+}
+
+/// Attribute type of OCA 1.1, e.g. `["Text"]` for `Array[Text]` and
+/// `refs:<SAID>` for `Reference:<SAID>`.
+fn migrate_attribute_type(
+    attribute_type: &Value,
+    pointer: &str,
+    references: &HashMap<String, SelfAddressingIdentifier>,
+    changes: &mut Vec<Change>,
+) -> Result<Value, Error> {
+    let attribute_type = match attribute_type {
+        Value::String(attribute_type) => attribute_type,
+        Value::Array(element) => {
+            return Ok(Value::Array(match element.first() {
+                Some(element) => vec![migrate_attribute_type(
+                    element, pointer, references, changes,
+                )?],
+                None => vec![],
+            }))
+        }
+        other => return Ok(other.clone()),
+    };
+    if let Some(element) = attribute_type
+        .strip_prefix("Array[")
+        .and_then(|element| element.strip_suffix(']'))
+    {
+        let element = Value::String(element.to_string());
+        return Ok(Value::Array(vec![migrate_attribute_type(
+            &element, pointer, references, changes,
+        )?]));
+    }
+    if let Some(said) = attribute_type.strip_prefix("Reference") {
+        let said = said.trim_start_matches(':').trim();
+        if said.parse::<SelfAddressingIdentifier>().is_err() {
+            return Err(invalid(
+                pointer,
+                format!("reference `{attribute_type}` has no valid SAID"),
+            ));
+        }
+        return Ok(Value::String(match references.get(said) {
+            Some(migrated) => {
+                changes.push(Change {
+                    pointer: pointer.to_string(),
+                    message: format!("reference to {said} is changed to migrated {migrated}"),
+                    lossy: false,
+                });
+                format!("refs:{migrated}")
+            }
+            None => {
+                changes.push(Change {
+                    pointer: pointer.to_string(),
+                    message: format!(
+                        "reference to {said} is kept, it dangles if the referenced bundle is migrated"
+                    ),
+                    lossy: true,
+                });
+                format!("refs:{said}")
+            }
+        }));
+    }
+    if attribute_type == "Date" {
+        changes.push(Change {
+            pointer: pointer.to_string(),
+            message: "Date type is changed to DateTime with YYYY-MM-DD format".to_string(),
+            lossy: false,
+        });
+        return Ok(Value::String("DateTime".to_string()));
+    }
+
+    Ok(Value::String(attribute_type.clone()))
+}
+
+/// Whether attribute type of OCA 1.0 is Date or an array of dates.
+fn is_date(attribute_type: &Value) -> bool {
+    match attribute_type {
+        Value::String(attribute_type) => {
+            attribute_type
+                .trim_start_matches("Array[")
+                .trim_end_matches(']')
+                == "Date"
+        }
+        Value::Array(element) => element.first().is_some_and(is_date),
+        _ => false,
+    }
+}
+
+/// Report fields of `original` which aren't serialized by what it was read
+/// into.
+fn report_dropped<T: Serialize + ?Sized>(
+    pointer: &str,
+    original: &Value,
+    read: &T,
+    changes: &mut Vec<Change>,
+) {
+    let (Value::Object(original), Ok(Value::Object(read))) = (original, serde_json::to_value(read))
+    else {
+        return;
+    };
+    for field in original.keys().filter(|field| !read.contains_key(*field)) {
+        changes.push(Change {
+            pointer: format!("{pointer}/{}", escape(field)),
+            message: format!(
+                "field `{field}` is not part of OCA {}",
+                SpecVersion::CURRENT
+            ),
+            lossy: true,
+        });
+    }
+}
+
+/// Remove SAID of any version, `digest` and `said` being used before `d`.
+fn take_said(object: &mut Map<String, Value>) -> Option<String> {
+    ["d", "said", "digest"]
+        .iter()
+        .filter_map(|field| object.remove(*field))
+        .find_map(|said| said.as_str().map(str::to_string))
+}
+
+fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn invalid(pointer: &str, message: impl ToString) -> Error {
+    Error::InvalidBundle {
+        pointer: pointer.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{
+        attribute::{Attribute, AttributeType},
+        oca::{overlay::label::Labels, OCABox},
+    };
+    use oca_ast_semantics::ast::{NestedAttrType, RefValue};
+    use said::derivation::{HashFunction, HashFunctionCode};
+
+    #[test]
+    fn migrate_oca_1_0_bundle() {
+        let reference = HashFunction::from(HashFunctionCode::Blake3_256).derive("ref".as_bytes());
+        let data = format!(
+            r#"{{
+    "version": "OCAB10000023_",
+    "said": "EBQMQm_tXSC8tnNICl7paGUeGg0SyF1tceHhTUutn1PN",
+    "capture_base": {{
+        "type": "spec/capture_base/1.0",
+        "said": "EBQMQm_tXSC8tnNICl7paGUeGg0SyF1tceHhTUutn1PN",
+        "classification": "",
+        "attributes": {{
+            "n1": "Text",
+            "n2": "Date",
+            "n3": "Array[Numeric]",
+            "n4": "Reference:{reference}"
+        }},
+        "flagged_attributes": ["n1"]
+    }},
+    "overlays": {{
+        "character_encoding": {{
+            "capture_base": "EBQMQm_tXSC8tnNICl7paGUeGg0SyF1tceHhTUutn1PN",
+            "said": "EBQMQm_tXSC8tnNICl7paGUeGg0SyF1tceHhTUutn1PN",
+            "type": "spec/overlays/character_encoding/1.0",
+            "default_character_encoding": "utf-8",
+            "attribute_character_encoding": {{"n2": "iso-8859-1"}}
+        }},
+        "unit": {{
+            "capture_base": "EBQMQm_tXSC8tnNICl7paGUeGg0SyF1tceHhTUutn1PN",
+            "said": "EBQMQm_tXSC8tnNICl7paGUeGg0SyF1tceHhTUutn1PN",
+            "type": "spec/overlays/unit/1.0",
+            "metric_system": "SI",
+            "attribute_units": {{"n3": "cm"}}
+        }},
+        "information": [{{
+            "capture_base": "EBQMQm_tXSC8tnNICl7paGUeGg0SyF1tceHhTUutn1PN",
+            "said": "EBQMQm_tXSC8tnNICl7paGUeGg0SyF1tceHhTUutn1PN",
+            "type": "spec/overlays/information/1.0",
+            "language": "En",
+            "comment": "draft",
+            "attribute_information": {{"n1": "Name"}}
+        }}]
+    }}
+}}"#
+        );
+
+        let migration = load(&mut data.as_bytes()).unwrap();
+        assert_eq!(migration.from, SpecVersion::V1_0);
+        let bundle = &migration.bundle;
+        assert!(bundle.verify().is_valid());
+        assert_eq!(bundle.capture_base.schema_type, "spec/capture_base/1.1");
+        assert_eq!(
+            bundle.capture_base.attributes["n3"],
+            NestedAttrType::Array(Box::new(NestedAttrType::Value(AttributeType::Numeric)))
+        );
+        assert_eq!(
+            bundle.capture_base.attributes["n4"],
+            NestedAttrType::Reference(RefValue::Said(reference.clone()))
+        );
+        let json = serde_json::to_value(bundle).unwrap();
+        assert_eq!(
+            json["overlays"]["character_encoding"]["attribute_character_encoding"],
+            serde_json::json!({"n1": "utf-8", "n2": "iso-8859-1", "n3": "utf-8", "n4": "utf-8"})
+        );
+        assert_eq!(
+            json["overlays"]["unit"]["attribute_unit"],
+            serde_json::json!({"n3": "cm"})
+        );
+        assert_eq!(json["overlays"]["information"][0]["language"], "eng");
+        assert_eq!(
+            json["overlays"]["format"]["attribute_formats"],
+            serde_json::json!({"n2": "YYYY-MM-DD"})
+        );
+
+        let lossy: Vec<&str> = migration
+            .lossy_changes()
+            .map(|change| change.pointer.as_str())
+            .collect();
+        assert_eq!(
+            lossy,
+            vec![
+                "/capture_base/attributes/n4",
+                "/overlays/unit/metric_system",
+                "/overlays/information/0/comment"
+            ]
+        );
+        assert!(migration.changes[0]
+            .message
+            .ends_with("bundle was EBQMQm_tXSC8tnNICl7paGUeGg0SyF1tceHhTUutn1PN"));
+        assert_eq!(
+            migration.original_said.as_deref(),
+            Some("EBQMQm_tXSC8tnNICl7paGUeGg0SyF1tceHhTUutn1PN")
+        );
+
+        // References to migrated bundles are updated.
+        let migrated = HashFunction::from(HashFunctionCode::Blake3_256).derive("new".as_bytes());
+        let references = HashMap::from([(reference.to_string(), migrated.clone())]);
+        let updated = migrate_with(
+            serde_json::from_str(&data).unwrap(),
+            &SaidOptions::default(),
+            &references,
+        )
+        .unwrap();
+        assert_eq!(
+            updated.bundle.capture_base.attributes["n4"],
+            NestedAttrType::Reference(RefValue::Said(migrated))
+        );
+        assert_eq!(updated.lossy_changes().count(), 2);
+
+        // Migrated bundle is read as it is.
+        let again = migrate(json).unwrap();
+        assert_eq!(again.from, SpecVersion::V1_1);
+        assert!(again.changes.is_empty());
+        assert_eq!(again.bundle.said, bundle.said);
+    }
+
+    #[test]
+    fn export_oca_1_0_bundle() {
+        let reference = HashFunction::from(HashFunctionCode::Blake3_256).derive("ref".as_bytes());
+        let mut oca = OCABox::new();
+        let mut name = Attribute::new("name".to_string());
+        name.set_attribute_type(NestedAttrType::Value(AttributeType::Text));
+        name.set_label(Language::Eng, "Name".to_string());
+        oca.add_attribute(name);
+        let mut tags = Attribute::new("tags".to_string());
+        tags.set_attribute_type(NestedAttrType::Array(Box::new(NestedAttrType::Value(
+            AttributeType::Text,
+        ))));
+        oca.add_attribute(tags);
+        let mut address = Attribute::new("address".to_string());
+        address.set_attribute_type(NestedAttrType::Reference(RefValue::Said(reference.clone())));
+        oca.add_attribute(address);
+        let bundle = oca.generate_bundle();
+
+        assert_eq!(
+            export(&bundle, SpecVersion::V1_1).unwrap(),
+            serde_json::to_value(&bundle).unwrap()
+        );
+        let json = export(&bundle, SpecVersion::V1_0).unwrap();
+        let version = json["version"].as_str().unwrap();
+        assert!(version.starts_with("OCAB10"));
+        assert_eq!(
+            usize::from_str_radix(&version[6..12], 16).unwrap(),
+            json.to_string().len()
+        );
+        let capture_base = &json["capture_base"];
+        assert_eq!(capture_base["type"], "spec/capture_base/1.0");
+        assert_eq!(capture_base["attributes"]["tags"], "Array[Text]");
+        assert_eq!(
+            capture_base["attributes"]["address"],
+            format!("Reference:{reference}")
+        );
+        let label = &json["overlays"]["label"][0];
+        assert_eq!(label["type"], "spec/overlays/label/1.0");
+        assert_eq!(label["language"], "en");
+        assert_eq!(label["capture_base"], capture_base["said"]);
+        assert!(label.get("d").is_none());
+
+        let references = HashMap::from([(reference.to_string(), reference)]);
+        let migration = migrate_with(json, &SaidOptions::default(), &references).unwrap();
+        assert_eq!(migration.from, SpecVersion::V1_0);
+        assert!(migration.is_lossless());
+        assert_eq!(migration.bundle.said, bundle.said);
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let bundle = serde_json::json!({
+            "capture_base": {"type": "spec/capture_base/2.0"},
+            "overlays": {}
+        });
+        assert_eq!(
+            migrate(bundle).unwrap_err(),
+            Error::UnsupportedVersion {
+                version: "2.0".to_string()
+            }
+        );
+    }
+}
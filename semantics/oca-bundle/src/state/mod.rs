@@ -1,7 +1,19 @@
 pub mod attribute;
+pub mod coverage;
+pub mod dependencies;
+pub mod diff;
 pub mod encoding;
 pub mod entries;
 pub mod entry_codes;
+pub mod integrity;
+pub mod merge;
+pub mod migration;
+pub mod naming;
 pub mod oca;
+pub mod patch;
+pub mod privacy;
+pub mod said_options;
+pub mod spec_version;
 pub mod standard;
 pub mod validator;
+pub mod view;
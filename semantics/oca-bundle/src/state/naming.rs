@@ -0,0 +1,67 @@
+//! Naming helpers shared by code generators and exporters.
+use crate::state::view::BundleView;
+use isolang::Language;
+
+/// PascalCase type name based on the meta name, see
+/// [`BundleView::metas_or_first`]. The SAID is added when the name doesn't
+/// start with a letter.
+pub fn type_name(view: &BundleView, lang: Option<Language>) -> String {
+    let name = view
+        .metas_or_first(lang)
+        .and_then(|meta| meta.get("name"))
+        .map(|name| pascal_case(name))
+        .unwrap_or_default();
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!(
+            "Bundle{name}{}",
+            pascal_case(view.said().unwrap_or_default())
+        )
+    }
+}
+
+/// Join alphanumeric parts of the name, capitalizing each of them.
+pub fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            first.to_string() + chars.as_str()
+        })
+        .collect()
+}
+
+/// ISO 639-1 code of the language, or ISO 639-3 one when there is none.
+pub fn lang_code(lang: &Language) -> String {
+    lang.to_639_1()
+        .unwrap_or_else(|| lang.to_639_3())
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::oca::{overlay::meta::Metas, OCABox};
+
+    #[test]
+    fn type_names() {
+        let mut oca = OCABox::new();
+        oca.add_meta(Language::Eng, "name".to_string(), "Person".to_string());
+        oca.add_meta(Language::Pol, "name".to_string(), "Osoba".to_string());
+        let view = BundleView::new(&oca.generate_bundle());
+        assert_eq!(type_name(&view, None), "Person");
+        assert_eq!(type_name(&view, Some(Language::Pol)), "Osoba");
+
+        let unnamed = BundleView::new(&OCABox::new().generate_bundle());
+        assert!(type_name(&unnamed, None).starts_with("BundleE"));
+    }
+
+    #[test]
+    fn names_and_codes() {
+        assert_eq!(pascal_case("first-name 2"), "FirstName2");
+        assert_eq!(lang_code(&Language::Pol), "pl");
+        assert_eq!(lang_code(&Language::Ast), "ast");
+    }
+}
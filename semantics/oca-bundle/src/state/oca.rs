@@ -13,6 +13,7 @@ use crate::state::oca::overlay::unit::Units;
 use indexmap::IndexMap;
 use overlay::attribute_framing::Framings;
 use overlay::link::Links;
+use overlay::plugin;
 use said::derivation::HashFunctionCode;
 use said::sad::{SerializationFormats, SAD};
 use said::version::SerializationInfo;
@@ -22,7 +23,10 @@ pub mod capture_base;
 pub mod overlay;
 use crate::state::{
     attribute::Attribute,
+    integrity::IntegrityReport,
     oca::{capture_base::CaptureBase, overlay::Overlay},
+    said_options::SaidOptions,
+    spec_version::SpecVersion,
 };
 use convert_case::{Case, Casing};
 use isolang::Language;
@@ -55,6 +59,8 @@ pub struct OCABox {
     pub mappings: Option<Vec<overlay::AttributeMapping>>,
     pub meta: Option<HashMap<Language, HashMap<String, String>>>,
     pub classification: Option<String>,
+    /// Overlays of types unknown to this crate, opaque or built by plugins.
+    pub custom_overlays: Vec<DynOverlay>,
 }
 
 impl Default for OCABox {
@@ -70,6 +76,7 @@ impl OCABox {
             mappings: None,
             meta: None,
             classification: None,
+            custom_overlays: vec![],
         }
     }
     /// Remove attribute from the OCA Bundle
@@ -106,14 +113,31 @@ impl OCABox {
         self.classification = None;
     }
 
+    /// Add overlay of a type unknown to this crate, replacing the one of the
+    /// same type and language.
+    pub fn add_custom_overlay(&mut self, overlay: DynOverlay) {
+        self.custom_overlays.retain(|o| {
+            o.overlay_type() != overlay.overlay_type() || o.language() != overlay.language()
+        });
+        self.custom_overlays.push(overlay);
+    }
+
     pub fn generate_bundle(&mut self) -> OCABundle {
+        self.generate_bundle_with(&SaidOptions::default())
+    }
+
+    /// Generate bundle computing all SAIDs with given digest algorithm and
+    /// serialization format.
+    pub fn generate_bundle_with(&mut self, options: &SaidOptions) -> OCABundle {
         let mut capture_base = self.generate_capture_base();
         let mut overlays = self.generate_overlays();
 
-        capture_base.sign();
+        capture_base.sign_with(options);
 
         let cb_said = capture_base.said.as_ref();
-        overlays.iter_mut().for_each(|x| x.sign(cb_said.unwrap()));
+        overlays
+            .iter_mut()
+            .for_each(|x| x.sign_with(cb_said.unwrap(), options));
 
         let mut oca_bundle = OCABundle {
             said: None,
@@ -121,7 +145,7 @@ impl OCABox {
             overlays,
         };
 
-        oca_bundle.fill_said();
+        oca_bundle.fill_said_with(options);
         oca_bundle
     }
 
@@ -140,7 +164,7 @@ impl OCABox {
         }
 
         for attribute in self.attributes.values() {
-            let overlay_version = "1.1".to_string();
+            let overlay_version = SpecVersion::CURRENT.to_string();
             if attribute.encoding.is_some() {
                 let mut encoding_ov = overlays
                     .iter_mut()
@@ -317,6 +341,8 @@ impl OCABox {
             }
         }
 
+        overlays.extend(self.custom_overlays.iter().cloned());
+
         overlays
     }
     fn generate_capture_base(&mut self) -> CaptureBase {
@@ -341,215 +367,111 @@ impl<'de> Deserialize<'de> for DynOverlay {
     where
         D: Deserializer<'de>,
     {
-        let de_overlay = serde_value::Value::deserialize(deserializer)?;
-        if let serde_value::Value::Map(ref overlay) = de_overlay {
-            if let Some(de_overlay_type) =
-                overlay.get(&serde_value::Value::String("type".to_string()))
-            {
-                let overlay_type = de_overlay_type
-                    .clone()
-                    .deserialize_into::<OverlayType>()
-                    .map_err(|e| serde::de::Error::custom(format!("Overlay type: {e}")))?;
-
-                match overlay_type {
-                    OverlayType::AttributeMapping(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::AttributeMapping>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!(
-                                        "Attribute Mapping overlay: {e}"
-                                    ))
-                                })?,
-                        ));
-                    }
-                    OverlayType::CharacterEncoding(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::CharacterEncoding>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!(
-                                        "Character Encoding overlay: {e}"
-                                    ))
-                                })?,
-                        ));
-                    }
-                    OverlayType::Cardinality(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::Cardinality>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Cardinality overlay: {e}"))
-                                })?,
-                        ));
-                    }
-                    OverlayType::Conformance(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::Conformance>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Conformance overlay: {e}"))
-                                })?,
-                        ));
-                    }
-                    OverlayType::Conditional(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::Conditional>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Conditional overlay: {e}"))
-                                })?,
-                        ));
-                    }
-                    OverlayType::Entry(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::Entry>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Entry overlay: {e}"))
-                                })?,
-                        ));
-                    }
-                    OverlayType::EntryCode(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::EntryCode>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Entry Code overlay: {e}"))
-                                })?,
-                        ));
-                    }
-                    OverlayType::EntryCodeMapping(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::EntryCodeMapping>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!(
-                                        "Entry Code Mapping overlay: {e}"
-                                    ))
-                                })?,
-                        ));
-                    }
-                    OverlayType::Unit(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::Unit>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Unit overlay: {e}"))
-                                })?,
-                        ));
-                    }
-
-                    #[cfg(feature = "format_overlay")]
-                    OverlayType::Format(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::Format>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Format overlay: {e}"))
-                                })?,
-                        ));
-                    }
+        let de_overlay = serde_json::Value::deserialize(deserializer)?;
+        deserialize_overlay(de_overlay, false).map_err(serde::de::Error::custom)
+    }
+}
 
-                    OverlayType::Information(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::Information>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Information overlay: {e}"))
-                                })?,
-                        ));
-                    }
-                    OverlayType::Label(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::Label>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Label overlay: {e}"))
-                                })?,
-                        ));
-                    }
-                    OverlayType::Meta(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::Meta>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Meta overlay: {e}"))
-                                })?,
-                        ));
-                    }
+/// Deserialize overlay of any type. Overlays of types without registered
+/// plugin are kept opaque, `many` telling whether they were listed.
+pub(crate) fn deserialize_overlay(de_overlay: serde_json::Value, many: bool) -> Result<DynOverlay, String> {
+    let serde_json::Value::Object(ref overlay) = de_overlay else {
+        return Err(format!("overlay must be an object, got: {de_overlay}"));
+    };
+    let overlay_type = overlay.get("type").ok_or("missing field `type`")?;
+    let overlay_type = serde_json::from_value::<OverlayType>(overlay_type.clone())
+        .map_err(|e| format!("Overlay type: {e}"))?;
+
+    match overlay_type {
+        OverlayType::AttributeMapping(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::AttributeMapping>(de_overlay)
+                .map_err(|e| format!("Attribute Mapping overlay: {e}"))?,
+        )),
+        OverlayType::CharacterEncoding(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::CharacterEncoding>(de_overlay)
+                .map_err(|e| format!("Character Encoding overlay: {e}"))?,
+        )),
+        OverlayType::Cardinality(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::Cardinality>(de_overlay)
+                .map_err(|e| format!("Cardinality overlay: {e}"))?,
+        )),
+        OverlayType::Conformance(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::Conformance>(de_overlay)
+                .map_err(|e| format!("Conformance overlay: {e}"))?,
+        )),
+        OverlayType::Conditional(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::Conditional>(de_overlay)
+                .map_err(|e| format!("Conditional overlay: {e}"))?,
+        )),
+        OverlayType::Entry(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::Entry>(de_overlay)
+                .map_err(|e| format!("Entry overlay: {e}"))?,
+        )),
+        OverlayType::EntryCode(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::EntryCode>(de_overlay)
+                .map_err(|e| format!("Entry Code overlay: {e}"))?,
+        )),
+        OverlayType::EntryCodeMapping(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::EntryCodeMapping>(de_overlay)
+                .map_err(|e| format!("Entry Code Mapping overlay: {e}"))?,
+        )),
+        OverlayType::Unit(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::Unit>(de_overlay)
+                .map_err(|e| format!("Unit overlay: {e}"))?,
+        )),
+        #[cfg(feature = "format_overlay")]
+        OverlayType::Format(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::Format>(de_overlay)
+                .map_err(|e| format!("Format overlay: {e}"))?,
+        )),
+        OverlayType::Information(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::Information>(de_overlay)
+                .map_err(|e| format!("Information overlay: {e}"))?,
+        )),
+        OverlayType::Label(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::Label>(de_overlay)
+                .map_err(|e| format!("Label overlay: {e}"))?,
+        )),
+        OverlayType::Meta(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::Meta>(de_overlay)
+                .map_err(|e| format!("Meta overlay: {e}"))?,
+        )),
+        OverlayType::Subset(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::Subset>(de_overlay)
+                .map_err(|e| format!("Subset overlay: {e}"))?,
+        )),
+        OverlayType::Standard(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::Standard>(de_overlay)
+                .map_err(|e| format!("Standard overlay: {e}"))?,
+        )),
+        OverlayType::Link(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::Link>(de_overlay)
+                .map_err(|e| format!("Link overlay: {e}"))?,
+        )),
+        OverlayType::AttributeFraming(_) => Ok(Box::new(
+            serde_json::from_value::<overlay::AttributeFraming>(de_overlay)
+                .map_err(|e| format!("AttributeFraming overlay: {e}"))?,
+        )),
+        OverlayType::Custom { ref name, .. } => match plugin::registered_overlay(name) {
+            Some(plugin) => plugin
+                .deserialize(de_overlay)
+                .map_err(|e| format!("{name} overlay: {e}")),
+            None => Ok(Box::new(overlay::Opaque::from_value(de_overlay, many)?)),
+        },
+        _ => Ok(Box::new(overlay::Opaque::from_value(de_overlay, many)?)),
+    }
+}
 
-                    /* OverlayType::FormLayout => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::FormLayout>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Form Layout overlay: {e}"))
-                                })?,
-                        ));
-                    }
-                    OverlayType::CredentialLayout => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::CredentialLayout>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Credential Layout overlay: {e}"))
-                                })?,
-                        ));
-                    } */
-                    OverlayType::Subset(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::Subset>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Subset overlay: {e}"))
-                                })?,
-                        ));
-                    }
-                    OverlayType::Standard(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::Standard>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Standard overlay: {e}"))
-                                })?,
-                        ));
-                    }
-                    OverlayType::Link(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::Link>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!("Link overlay: {e}"))
-                                })?,
-                        ));
-                    }
-                    OverlayType::AttributeFraming(_) => {
-                        return Ok(Box::new(
-                            de_overlay
-                                .deserialize_into::<overlay::AttributeFraming>()
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!(
-                                        "AttributeFraming overlay: {e}"
-                                    ))
-                                })?,
-                        ));
-                    }
-                    _ => {
-                        return Err(serde::de::Error::custom(format!(
-                            "Overlay type not supported: {:?}",
-                            overlay_type
-                        )));
-                    }
-                }
-            } else {
-                return Err(serde::de::Error::missing_field("type"));
-            }
+/// Whether overlay of a type unknown to this crate is listed in bundles.
+fn is_many(overlay: &DynOverlay) -> bool {
+    if let Some(opaque) = overlay.as_any().downcast_ref::<overlay::Opaque>() {
+        return opaque.many;
+    }
+    match overlay.overlay_type() {
+        OverlayType::Custom { name, .. } => {
+            plugin::registered_overlay(name).is_some_and(|plugin| plugin.many())
         }
-
-        Err(serde::de::Error::custom(format!(
-            "overlay must be an object, got: {de_overlay:?}"
-        )))
+        _ => false,
     }
 }
 
@@ -578,9 +500,16 @@ where
 
     let mut overlays_map: BTreeMap<Value, OverlayValue> = BTreeMap::new();
     for overlay in overlays {
-        let o_type_str = overlay.overlay_type().to_string().to_case(Case::Snake);
+        let (o_type_str, many) = match overlay.overlay_type() {
+            OverlayType::Custom { name, .. } => (name.clone(), is_many(overlay)),
+            o_type => {
+                let o_type_str = o_type.to_string().to_case(Case::Snake);
+                let many = overlays_many.contains(&o_type_str.as_str()) || is_many(overlay);
+                (o_type_str, many)
+            }
+        };
 
-        if overlays_many.contains(&o_type_str.as_str()) {
+        if many {
             if let Some(OverlayValue::Array(ov)) =
                 overlays_map.get_mut(&Value::String(o_type_str.clone()))
             {
@@ -639,13 +568,17 @@ where
         {
             let mut overlays = vec![];
 
-            while let Some((_, value)) = map.next_entry::<String, serde_value::Value>()? {
-                if let serde_value::Value::Seq(ov) = value {
+            // Values are read as JSON to keep the order of fields of opaque
+            // overlays.
+            while let Some((_, value)) = map.next_entry::<String, serde_json::Value>()? {
+                if let serde_json::Value::Array(ov) = value {
                     for o in ov {
-                        overlays.push(o.deserialize_into().unwrap());
+                        overlays
+                            .push(deserialize_overlay(o, true).map_err(serde::de::Error::custom)?);
                     }
-                } else if let serde_value::Value::Map(_) = value {
-                    overlays.push(value.deserialize_into().unwrap());
+                } else if let serde_json::Value::Object(_) = value {
+                    overlays
+                        .push(deserialize_overlay(value, false).map_err(serde::de::Error::custom)?);
                 }
             }
 
@@ -914,15 +847,49 @@ impl From<OCABundle> for OCABox {
             oca_box.add_attribute(attribute);
         }
 
+        for overlay in &oca_bundle.overlays {
+            if let Some(mapping) = overlay.as_any().downcast_ref::<overlay::AttributeMapping>() {
+                oca_box.add_attribute_mapping(mapping.clone());
+            }
+        }
+
+        oca_box.custom_overlays = oca_bundle
+            .overlays
+            .into_iter()
+            .filter(|o| {
+                matches!(o.overlay_type(), OverlayType::Custom { .. })
+                    || o.as_any().is::<overlay::Opaque>()
+            })
+            .collect();
+
         oca_box
     }
 }
 
 impl OCABundle {
     pub fn fill_said(&mut self) {
-        let code = HashFunctionCode::Blake3_256;
-        let format = SerializationFormats::JSON;
-        self.compute_digest(&code, &format);
+        self.fill_said_with(&SaidOptions::default());
+    }
+
+    pub fn fill_said_with(&mut self, options: &SaidOptions) {
+        self.compute_digest(&options.code, &options.format);
+    }
+
+    /// Recompute all SAIDs and check overlays against the capture base.
+    /// Digest algorithm is taken from the bundle SAID, JSON serialization is
+    /// assumed.
+    pub fn verify(&self) -> IntegrityReport {
+        let options = match &self.said {
+            Some(said) => {
+                SaidOptions::new(said.derivation.clone().into(), SerializationFormats::JSON)
+            }
+            None => SaidOptions::default(),
+        };
+        self.verify_with(&options)
+    }
+
+    pub fn verify_with(&self, options: &SaidOptions) -> IntegrityReport {
+        IntegrityReport::new(self, options)
     }
 
     pub fn to_ast(&self) -> OCAAst {
@@ -1287,6 +1254,20 @@ impl OCABundle {
                     };
                     ast.commands.push(command);
                 }
+                // Opaque overlays can't be expressed in OCAFILE.
+                OverlayType::Custom { name, .. } => {
+                    if let Some(content) =
+                        plugin::registered_overlay(name).and_then(|p| p.to_content(&**overlay))
+                    {
+                        ast.commands.push(Command {
+                            kind: CommandType::Add,
+                            object_kind: ObjectKind::Overlay(
+                                overlay.overlay_type().clone(),
+                                content,
+                            ),
+                        });
+                    }
+                }
                 _ => {}
             }
         });
@@ -1415,6 +1396,31 @@ mod tests {
 
         assert_eq!(said, said2);
     }
+
+    #[test]
+    fn build_oca_bundle_with_said_options() {
+        let mut oca = OCABox::new();
+        let mut attr = Attribute::new("first_name".to_string());
+        attr.set_attribute_type(NestedAttrType::Value(AttributeType::Text));
+        attr.set_label(Language::Eng, "First name".to_string());
+        oca.add_attribute(attr);
+
+        let default_bundle = oca.generate_bundle();
+        let options = SaidOptions::new(HashFunctionCode::SHA3_256, SerializationFormats::CBOR);
+        let oca_bundle = oca.generate_bundle_with(&options);
+
+        assert_ne!(default_bundle.said, oca_bundle.said);
+        assert_ne!(
+            default_bundle.capture_base.said,
+            oca_bundle.capture_base.said
+        );
+        assert!(oca_bundle.verify_with(&options).is_valid());
+        assert!(!oca_bundle.verify().is_valid());
+
+        let options = SaidOptions::new(HashFunctionCode::SHA3_256, SerializationFormats::JSON);
+        let oca_bundle = oca.generate_bundle_with(&options);
+        assert!(oca_bundle.verify().is_valid());
+    }
 }
 
 /* struct CatAttributes {
@@ -1,4 +1,4 @@
-use crate::state::attribute::Attribute;
+use crate::state::{attribute::Attribute, said_options::SaidOptions, spec_version::SpecVersion};
 use indexmap::IndexMap;
 use oca_ast_semantics::ast::NestedAttrType;
 use said::{
@@ -61,7 +61,7 @@ impl Default for CaptureBase {
 impl CaptureBase {
     pub fn new() -> CaptureBase {
         CaptureBase {
-            schema_type: String::from("spec/capture_base/1.1"),
+            schema_type: format!("spec/capture_base/{}", SpecVersion::CURRENT),
             said: None,
             classification: String::from(""),
             attributes: IndexMap::new(),
@@ -97,12 +97,18 @@ impl CaptureBase {
     }
 
     pub fn fill_said(&mut self) {
-        let code = HashFunctionCode::Blake3_256;
-        let format = SerializationFormats::JSON;
-        self.compute_digest(&code, &format);
+        self.fill_said_with(&SaidOptions::default());
+    }
+
+    pub fn fill_said_with(&mut self, options: &SaidOptions) {
+        self.compute_digest(&options.code, &options.format);
     }
 
     pub fn sign(&mut self) {
         self.fill_said();
     }
+
+    pub fn sign_with(&mut self, options: &SaidOptions) {
+        self.fill_said_with(options);
+    }
 }
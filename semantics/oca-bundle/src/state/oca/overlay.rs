@@ -13,6 +13,8 @@ pub mod information;
 pub mod label;
 pub mod link;
 pub mod meta;
+pub mod opaque;
+pub mod plugin;
 pub mod standard;
 pub mod subset;
 pub mod unit;
@@ -32,15 +34,16 @@ pub use self::information::InformationOverlay as Information;
 pub use self::label::LabelOverlay as Label;
 pub use self::link::LinkOverlay as Link;
 pub use self::meta::MetaOverlay as Meta;
+pub use self::opaque::OpaqueOverlay as Opaque;
 pub use self::standard::StandardOverlay as Standard;
 pub use self::subset::SubsetOverlay as Subset;
 pub use oca_ast_semantics::ast::OverlayType;
-use said::derivation::HashFunctionCode;
 
 pub use self::unit::UnitOverlay as Unit;
 use crate::state::attribute::Attribute;
+use crate::state::said_options::SaidOptions;
 use isolang::Language;
-use said::sad::{SerializationFormats, SAD};
+use said::sad::SAD;
 use std::any::Any;
 erased_serde::serialize_trait_object!(Overlay);
 
@@ -62,14 +65,24 @@ pub trait Overlay: erased_serde::Serialize + Clone + SAD {
     fn add(&mut self, attribute: &Attribute);
 
     fn fill_said(&mut self) {
-        let code = HashFunctionCode::Blake3_256;
-        let format = SerializationFormats::JSON;
-        self.compute_digest(&code, &format);
+        self.fill_said_with(&SaidOptions::default());
+    }
+
+    fn fill_said_with(&mut self, options: &SaidOptions) {
+        self.compute_digest(&options.code, &options.format);
     }
 
     fn sign(&mut self, capture_base_sai: &said::SelfAddressingIdentifier) {
+        self.sign_with(capture_base_sai, &SaidOptions::default());
+    }
+
+    fn sign_with(
+        &mut self,
+        capture_base_sai: &said::SelfAddressingIdentifier,
+        options: &SaidOptions,
+    ) {
         self.set_capture_base(capture_base_sai);
-        self.fill_said();
+        self.fill_said_with(options);
     }
 }
 
@@ -147,7 +160,7 @@ macro_rules! overlay {
 
             impl [<$name Overlay>] {
                 pub fn new() -> Self {
-                    let overlay_version = "1.1".to_string();
+                    let overlay_version = crate::state::spec_version::SpecVersion::CURRENT.to_string();
                     Self {
                         capture_base: None,
                         said: None,
@@ -1,4 +1,4 @@
-use crate::state::{attribute::Attribute, oca::Overlay};
+use crate::state::{attribute::Attribute, oca::Overlay, spec_version::SpecVersion};
 use isolang::Language;
 use oca_ast_semantics::ast::OverlayType;
 use said::derivation::HashFunctionCode;
@@ -129,7 +129,7 @@ impl AttributeFramingOverlay {
     pub fn new(id: String) -> Self {
         let mut metadata = HashMap::new();
         metadata.insert("frame_id".to_string(), id);
-        let overlay_version = "1.1".to_string();
+        let overlay_version = SpecVersion::CURRENT.to_string();
         Self {
             capture_base: None,
             said: None,
@@ -173,8 +173,11 @@ mod tests {
         // even that attribute has 2 lagnuage only one attribute should be added to the overlay according to it's language
         overlay.add(&attr);
 
-        let overlay_version = "1.1".to_string();
-        assert_eq!(overlay.overlay_type, OverlayType::AttributeFraming(overlay_version));
+        let overlay_version = SpecVersion::CURRENT.to_string();
+        assert_eq!(
+            overlay.overlay_type,
+            OverlayType::AttributeFraming(overlay_version)
+        );
         assert_eq!(overlay.attribute_framing.len(), 1);
     }
 }
@@ -1,4 +1,4 @@
-use crate::state::{attribute::Attribute, oca::Overlay};
+use crate::state::{attribute::Attribute, oca::Overlay, spec_version::SpecVersion};
 use oca_ast_semantics::ast::OverlayType;
 use serde::{Deserialize, Serialize};
 use std::any::Any;
@@ -49,7 +49,7 @@ impl Overlay for AttributeMappingOverlay {
 }
 impl AttributeMappingOverlay {
     pub fn new() -> Box<Self> {
-        let overlay_version = "1.1".to_string();
+        let overlay_version = SpecVersion::CURRENT.to_string();
         Box::new(Self {
             capture_base: None,
             said: None,
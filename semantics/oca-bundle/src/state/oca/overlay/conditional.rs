@@ -1,4 +1,4 @@
-use crate::state::{attribute::Attribute, oca::Overlay};
+use crate::state::{attribute::Attribute, oca::Overlay, spec_version::SpecVersion};
 use oca_ast_semantics::ast::OverlayType;
 use piccolo::{Closure, Lua, Thread};
 use said::derivation::HashFunctionCode;
@@ -150,23 +150,19 @@ impl Overlay for ConditionalOverlay {
     }
 
     fn add(&mut self, attribute: &Attribute) {
-        if attribute.condition.is_some() {
-            self.attribute_conditions.insert(
-                attribute.name.clone(),
-                attribute.condition.as_ref().unwrap().clone(),
-            );
+        if let Some(condition) = &attribute.condition {
+            self.attribute_conditions
+                .insert(attribute.name.clone(), condition.clone());
         }
-        if attribute.dependencies.is_some() {
-            self.attribute_dependencies.insert(
-                attribute.name.clone(),
-                attribute.dependencies.as_ref().unwrap().clone(),
-            );
+        if let Some(dependencies) = &attribute.dependencies {
+            self.attribute_dependencies
+                .insert(attribute.name.clone(), dependencies.clone());
         }
     }
 }
 impl ConditionalOverlay {
     pub fn new() -> Self {
-        let overlay_version = "1.1".to_string();
+        let overlay_version = SpecVersion::CURRENT.to_string();
         Self {
             capture_base: None,
             said: None,
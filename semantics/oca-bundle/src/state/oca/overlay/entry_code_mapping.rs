@@ -1,4 +1,4 @@
-use crate::state::{attribute::Attribute, oca::Overlay};
+use crate::state::{attribute::Attribute, oca::Overlay, spec_version::SpecVersion};
 use oca_ast_semantics::ast::OverlayType;
 use said::derivation::HashFunctionCode;
 use said::{sad::SerializationFormats, sad::SAD};
@@ -40,17 +40,15 @@ impl Overlay for EntryCodeMappingOverlay {
     }
 
     fn add(&mut self, attribute: &Attribute) {
-        if attribute.entry_codes_mapping.is_some() {
-            self.attribute_entry_codes_mapping.insert(
-                attribute.name.clone(),
-                attribute.entry_codes_mapping.as_ref().unwrap().clone(),
-            );
+        if let Some(entry_codes_mapping) = &attribute.entry_codes_mapping {
+            self.attribute_entry_codes_mapping
+                .insert(attribute.name.clone(), entry_codes_mapping.clone());
         }
     }
 }
 impl EntryCodeMappingOverlay {
     pub fn new() -> Box<Self> {
-        let overlay_version = "1.1".to_string();
+        let overlay_version = SpecVersion::CURRENT.to_string();
         Box::new(Self {
             capture_base: None,
             said: None,
@@ -1,4 +1,4 @@
-use crate::state::{attribute::Attribute, oca::Overlay};
+use crate::state::{attribute::Attribute, oca::Overlay, spec_version::SpecVersion};
 use isolang::Language;
 use oca_ast_semantics::ast::OverlayType;
 use said::derivation::HashFunctionCode;
@@ -87,7 +87,7 @@ impl Overlay for InformationOverlay {
 }
 impl InformationOverlay {
     pub fn new(lang: Language) -> Self {
-        let overlay_version = "1.1".to_string();
+        let overlay_version = SpecVersion::CURRENT.to_string();
         Self {
             capture_base: None,
             said: None,
@@ -1,4 +1,4 @@
-use crate::state::{attribute::Attribute, oca::Overlay};
+use crate::state::{attribute::Attribute, oca::Overlay, spec_version::SpecVersion};
 use isolang::Language;
 use oca_ast_semantics::ast::OverlayType;
 use said::derivation::HashFunctionCode;
@@ -128,7 +128,7 @@ impl Overlay for LabelOverlay {
 
 impl LabelOverlay {
     pub fn new(lang: Language) -> Self {
-        let overlay_version = "1.1".to_string();
+        let overlay_version = SpecVersion::CURRENT.to_string();
         Self {
             capture_base: None,
             said: None,
@@ -206,7 +206,7 @@ mod tests {
         // even that attribute has 2 lagnuage only one attribute should be added to the overlay according to it's language
         overlay.add(&attr);
 
-        let overlay_version = "1.1".to_string();
+        let overlay_version = SpecVersion::CURRENT.to_string();
         assert_eq!(overlay.overlay_type, OverlayType::Label(overlay_version));
         assert_eq!(overlay.language, Language::Eng);
         assert_eq!(overlay.attribute_labels.len(), 1);
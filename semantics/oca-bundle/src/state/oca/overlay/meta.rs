@@ -1,4 +1,4 @@
-use crate::state::{attribute::Attribute, oca::OCABox, oca::Overlay};
+use crate::state::{attribute::Attribute, oca::OCABox, oca::Overlay, spec_version::SpecVersion};
 use isolang::Language;
 use oca_ast_semantics::ast::OverlayType;
 use said::derivation::HashFunctionCode;
@@ -92,7 +92,7 @@ impl Overlay for MetaOverlay {
 
 impl MetaOverlay {
     pub fn new(lang: Language, attr_pairs: HashMap<String, String>) -> Self {
-        let overlay_version = "1.1".to_string();
+        let overlay_version = SpecVersion::CURRENT.to_string();
         Self {
             capture_base: None,
             said: None,
@@ -0,0 +1,160 @@
+use crate::state::{attribute::Attribute, oca::Overlay};
+use isolang::Language;
+use oca_ast_semantics::ast::OverlayType;
+use said::derivation::{HashFunction, HashFunctionCode};
+use said::sad::{DerivationCode, SerializationFormats, SAD};
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::any::Any;
+use std::str::FromStr;
+
+/// Overlay of a type without registered plugin. It is kept as it was read,
+/// field order included, so it serializes byte for byte the same and the
+/// SAIDs of the bundle don't change. Its content isn't interpreted.
+#[derive(Debug, Clone)]
+pub struct OpaqueOverlay {
+    said: Option<said::SelfAddressingIdentifier>,
+    capture_base: Option<said::SelfAddressingIdentifier>,
+    overlay_type: OverlayType,
+    language: Option<Language>,
+    /// Whether the bundle holds a list of overlays of this type.
+    pub many: bool,
+    value: Map<String, Value>,
+}
+
+impl OpaqueOverlay {
+    pub fn from_value(value: Value, many: bool) -> Result<Self, String> {
+        let Value::Object(value) = value else {
+            return Err(format!("overlay must be an object, got: {value}"));
+        };
+        let overlay_type: OverlayType = value
+            .get("type")
+            .cloned()
+            .map(serde_json::from_value)
+            .ok_or("missing field `type`")?
+            .map_err(|e| format!("Overlay type: {e}"))?;
+        let said_field = |name: &str| match value.get(name) {
+            Some(Value::String(said)) if !said.is_empty() => {
+                said::SelfAddressingIdentifier::from_str(said)
+                    .map(Some)
+                    .map_err(|e| format!("{name}: {e}"))
+            }
+            _ => Ok(None),
+        };
+        let language = value
+            .get("language")
+            .and_then(|language| serde_json::from_value(language.clone()).ok());
+
+        Ok(Self {
+            said: said_field("d")?,
+            capture_base: said_field("capture_base")?,
+            overlay_type,
+            language,
+            many,
+            value,
+        })
+    }
+
+    /// Overlay as it is serialized.
+    pub fn value(&self) -> &Map<String, Value> {
+        &self.value
+    }
+}
+
+impl Serialize for OpaqueOverlay {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.value.serialize(serializer)
+    }
+}
+
+impl SAD for OpaqueOverlay {
+    fn compute_digest(&mut self, code: &HashFunctionCode, format: &SerializationFormats) {
+        let said = HashFunction::from(code.clone()).derive(&self.derivation_data(code, format));
+        self.value
+            .insert("d".to_string(), Value::String(said.to_string()));
+        self.said = Some(said);
+    }
+
+    fn derivation_data(&self, code: &HashFunctionCode, format: &SerializationFormats) -> Vec<u8> {
+        let mut value = self.value.clone();
+        value.insert("d".to_string(), Value::String("#".repeat(code.full_size())));
+        format.encode(&value).unwrap()
+    }
+}
+
+impl Overlay for OpaqueOverlay {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn capture_base(&self) -> &Option<said::SelfAddressingIdentifier> {
+        &self.capture_base
+    }
+    fn set_capture_base(&mut self, said: &said::SelfAddressingIdentifier) {
+        self.value
+            .insert("capture_base".to_string(), Value::String(said.to_string()));
+        self.capture_base = Some(said.clone());
+    }
+    fn said(&self) -> &Option<said::SelfAddressingIdentifier> {
+        &self.said
+    }
+    fn overlay_type(&self) -> &OverlayType {
+        &self.overlay_type
+    }
+    fn language(&self) -> Option<&Language> {
+        self.language.as_ref()
+    }
+    /// Attributes aren't known without interpreting the content.
+    fn attributes(&self) -> Vec<&String> {
+        vec![]
+    }
+
+    fn add(&mut self, _attribute: &Attribute) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::load_oca;
+    use crate::state::{
+        attribute::AttributeType,
+        oca::{OCABox, OCABundle},
+    };
+    use oca_ast_semantics::ast::NestedAttrType;
+
+    #[test]
+    fn unknown_overlay_round_trips() {
+        let mut oca = OCABox::new();
+        let mut name = Attribute::new("name".to_string());
+        name.set_attribute_type(NestedAttrType::Value(AttributeType::Text));
+        oca.add_attribute(name);
+        let mut bundle = oca.generate_bundle();
+
+        let mut overlay = OpaqueOverlay::from_value(
+            serde_json::json!({
+                "d": "",
+                "capture_base": "",
+                "type": "spec/overlays/community_consent/1.0",
+                "zeta": {"name": "required"},
+                "alpha": [2, 1.5]
+            }),
+            false,
+        )
+        .unwrap();
+        overlay.sign(bundle.capture_base.said.as_ref().unwrap());
+        bundle.overlays.push(Box::new(overlay));
+        bundle.fill_said();
+        let json = serde_json::to_string(&bundle).unwrap();
+        assert!(json.contains(r#""zeta":{"name":"required"},"alpha":[2,1.5]}"#));
+
+        let loaded = load_oca(&mut json.as_bytes()).unwrap();
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+        assert!(loaded.verify().is_valid());
+
+        // Kept when the bundle is rebuilt.
+        let rebuilt: OCABundle = OCABox::from(loaded).generate_bundle();
+        assert_eq!(rebuilt.said, bundle.said);
+    }
+}
@@ -0,0 +1,307 @@
+//! Registry of overlays not defined by the OCA specification.
+//!
+//! Downstream crates register an [`OverlayPlugin`] for each custom or
+//! community overlay type, identified by its name in
+//! `spec/overlays/<name>/<version>`. Registered overlays are deserialized
+//! with the plugin and can be added in OCAFILE with
+//! `ADD OVERLAY <name> [lang] [PROPS ...] [ATTRS ...]`. Overlays without
+//! plugin are kept as [`Opaque`](super::Opaque). Names of built-in overlays
+//! can't be taken over.
+//!
+//! The registry is shared by the whole process, register plugins once at
+//! startup.
+use crate::state::oca::{overlay::Overlay, DynOverlay};
+use oca_ast_semantics::ast::{Content, OverlayType};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum Error {
+    #[error("Overlay name `{name}` is taken by a built-in overlay")]
+    BuiltInName { name: String },
+    #[error("Invalid overlay name `{name}`, only letters, digits and _ are allowed")]
+    InvalidName { name: String },
+}
+
+pub trait OverlayPlugin: Send + Sync {
+    /// Name of the overlay type, e.g. `consent`.
+    fn name(&self) -> &str;
+
+    /// Whether bundles hold a list of these overlays, e.g. one per language.
+    fn many(&self) -> bool {
+        false
+    }
+
+    fn deserialize(&self, value: serde_json::Value) -> Result<DynOverlay, String>;
+
+    /// Build the overlay from `ADD OVERLAY` command. Language is given as
+    /// `lang` property.
+    fn build(&self, _overlay_type: &OverlayType, _content: &Content) -> Result<DynOverlay, String> {
+        Err(format!(
+            "{} overlay can't be built from OCAFILE",
+            self.name()
+        ))
+    }
+
+    /// Content of `ADD OVERLAY` command building the overlay, if it can be
+    /// expressed in OCAFILE.
+    fn to_content(&self, _overlay: &dyn Overlay) -> Option<Content> {
+        None
+    }
+}
+
+type Registry = RwLock<HashMap<String, Arc<dyn OverlayPlugin>>>;
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Register the plugin, replacing any registered under the same name.
+pub fn register_overlay(plugin: impl OverlayPlugin + 'static) -> Result<(), Error> {
+    check_name(plugin.name())?;
+    // The map stays consistent even if a writer panicked, so poisoning is
+    // ignored.
+    registry()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(plugin.name().to_string(), Arc::new(plugin));
+    Ok(())
+}
+
+pub fn unregister_overlay(name: &str) {
+    registry()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(name);
+}
+
+pub fn registered_overlay(name: &str) -> Option<Arc<dyn OverlayPlugin>> {
+    registry()
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(name)
+        .cloned()
+}
+
+/// Check that overlays named `name` are serialized as custom overlays, i.e.
+/// that the name is valid and isn't used by a built-in overlay.
+pub fn check_name(name: &str) -> Result<(), Error> {
+    let overlay_type = serde_json::Value::String(format!("spec/overlays/{name}/1.1"));
+    match serde_json::from_value(overlay_type) {
+        Ok(OverlayType::Custom { .. }) => Ok(()),
+        Ok(_) => Err(Error::BuiltInName {
+            name: name.to_string(),
+        }),
+        Err(_) => Err(Error::InvalidName {
+            name: name.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::from_ast;
+    use crate::state::{attribute::Attribute, oca::OCABundle};
+    use indexmap::IndexMap;
+    use oca_ast_semantics::ast::{
+        AttributeType, CaptureContent, Command, CommandType, NestedAttrType, NestedValue, OCAAst,
+        ObjectKind,
+    };
+    use said::derivation::HashFunctionCode;
+    use said::{sad::SerializationFormats, sad::SAD};
+    use serde::{Deserialize, Serialize};
+    use std::any::Any;
+    use std::collections::BTreeMap;
+
+    #[derive(SAD, Serialize, Deserialize, Debug, Clone)]
+    struct ConsentOverlay {
+        #[said]
+        #[serde(rename = "d")]
+        said: Option<said::SelfAddressingIdentifier>,
+        capture_base: Option<said::SelfAddressingIdentifier>,
+        #[serde(rename = "type")]
+        overlay_type: OverlayType,
+        purpose: String,
+        attribute_consents: BTreeMap<String, String>,
+    }
+
+    impl Overlay for ConsentOverlay {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+        fn capture_base(&self) -> &Option<said::SelfAddressingIdentifier> {
+            &self.capture_base
+        }
+        fn set_capture_base(&mut self, said: &said::SelfAddressingIdentifier) {
+            self.capture_base = Some(said.clone());
+        }
+        fn said(&self) -> &Option<said::SelfAddressingIdentifier> {
+            &self.said
+        }
+        fn overlay_type(&self) -> &OverlayType {
+            &self.overlay_type
+        }
+        fn attributes(&self) -> Vec<&String> {
+            self.attribute_consents.keys().collect()
+        }
+        fn add(&mut self, _attribute: &Attribute) {}
+    }
+
+    struct ConsentPlugin;
+
+    impl OverlayPlugin for ConsentPlugin {
+        fn name(&self) -> &str {
+            "consent"
+        }
+
+        fn deserialize(&self, value: serde_json::Value) -> Result<DynOverlay, String> {
+            let overlay: ConsentOverlay =
+                serde_json::from_value(value).map_err(|e| e.to_string())?;
+            Ok(Box::new(overlay))
+        }
+
+        fn build(
+            &self,
+            overlay_type: &OverlayType,
+            content: &Content,
+        ) -> Result<DynOverlay, String> {
+            let value = |value: &NestedValue| match value {
+                NestedValue::Value(value) => Ok(value.clone()),
+                _ => Err("consent must be text".to_string()),
+            };
+            let purpose = content
+                .properties
+                .as_ref()
+                .and_then(|properties| properties.get("purpose"))
+                .ok_or("purpose is missing")?;
+            let mut attribute_consents = BTreeMap::new();
+            for (name, consent) in content.attributes.iter().flatten() {
+                attribute_consents.insert(name.clone(), value(consent)?);
+            }
+            Ok(Box::new(ConsentOverlay {
+                said: None,
+                capture_base: None,
+                overlay_type: overlay_type.clone(),
+                purpose: value(purpose)?,
+                attribute_consents,
+            }))
+        }
+
+        fn to_content(&self, overlay: &dyn Overlay) -> Option<Content> {
+            let overlay = overlay.as_any().downcast_ref::<ConsentOverlay>()?;
+            Some(Content {
+                properties: Some(IndexMap::from([(
+                    "purpose".to_string(),
+                    NestedValue::Value(overlay.purpose.clone()),
+                )])),
+                attributes: Some(
+                    overlay
+                        .attribute_consents
+                        .iter()
+                        .map(|(name, consent)| (name.clone(), NestedValue::Value(consent.clone())))
+                        .collect(),
+                ),
+            })
+        }
+    }
+
+    fn add_overlay(name: &str) -> Command {
+        Command {
+            kind: CommandType::Add,
+            object_kind: ObjectKind::Overlay(
+                OverlayType::Custom {
+                    name: name.to_string(),
+                    version: "1.0".to_string(),
+                },
+                Content {
+                    properties: Some(IndexMap::from([(
+                        "purpose".to_string(),
+                        NestedValue::Value("research".to_string()),
+                    )])),
+                    attributes: Some(IndexMap::from([(
+                        "name".to_string(),
+                        NestedValue::Value("required".to_string()),
+                    )])),
+                },
+            ),
+        }
+    }
+
+    #[test]
+    fn registered_overlay() {
+        register_overlay(ConsentPlugin).unwrap();
+        let mut oca_ast = OCAAst::new();
+        oca_ast.commands.push(Command {
+            kind: CommandType::Add,
+            object_kind: ObjectKind::CaptureBase(CaptureContent {
+                attributes: Some(IndexMap::from([(
+                    "name".to_string(),
+                    NestedAttrType::Value(AttributeType::Text),
+                )])),
+                properties: None,
+                flagged_attributes: None,
+            }),
+        });
+        oca_ast.commands.push(add_overlay("consent"));
+        let bundle = from_ast(None, &oca_ast).unwrap().oca_bundle;
+
+        let json = serde_json::to_string(&bundle).unwrap();
+        assert!(json.contains(r#""consent":{"d":"#));
+        let loaded: OCABundle = serde_json::from_str(&json).unwrap();
+        let consent = loaded
+            .overlays
+            .iter()
+            .find_map(|o| o.as_any().downcast_ref::<ConsentOverlay>())
+            .unwrap();
+        assert_eq!(consent.purpose, "research");
+        assert!(loaded.verify().is_valid());
+        assert_eq!(loaded.to_ast().commands.last(), oca_ast.commands.last());
+
+        oca_ast.commands.push(add_overlay("unregistered_consent"));
+        let errors = from_ast(None, &oca_ast).unwrap_err();
+        assert!(errors[0]
+            .to_string()
+            .ends_with("Unsupported overlay type: unregistered_consent"));
+        unregister_overlay("consent");
+    }
+
+    struct NamedPlugin(&'static str);
+
+    impl OverlayPlugin for NamedPlugin {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn deserialize(&self, _value: serde_json::Value) -> Result<DynOverlay, String> {
+            Err("not supported".to_string())
+        }
+    }
+
+    #[test]
+    fn reject_built_in_name() {
+        assert_eq!(
+            register_overlay(NamedPlugin("label")),
+            Err(Error::BuiltInName {
+                name: "label".to_string()
+            })
+        );
+        assert_eq!(
+            register_overlay(NamedPlugin("my-consent")),
+            Err(Error::InvalidName {
+                name: "my-consent".to_string()
+            })
+        );
+        assert!(super::registered_overlay("label").is_none());
+
+        let mut oca_ast = OCAAst::new();
+        oca_ast.commands.push(add_overlay("meta"));
+        let errors = from_ast(None, &oca_ast).unwrap_err();
+        assert!(errors[0]
+            .to_string()
+            .ends_with("Overlay name `meta` is taken by a built-in overlay"));
+    }
+}
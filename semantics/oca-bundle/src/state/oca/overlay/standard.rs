@@ -1,3 +1,4 @@
+use crate::state::spec_version::SpecVersion;
 use crate::state::standard::Standard;
 use crate::state::{attribute::Attribute, oca::Overlay};
 use oca_ast_semantics::ast::OverlayType;
@@ -66,7 +67,7 @@ impl Overlay for StandardOverlay {
 }
 impl StandardOverlay {
     pub fn new() -> Box<StandardOverlay> {
-        let overlay_version = "1.1".to_string();
+        let overlay_version = SpecVersion::CURRENT.to_string();
         Box::new(StandardOverlay {
             capture_base: None,
             said: None,
@@ -1,4 +1,4 @@
-use crate::state::{attribute::Attribute, oca::Overlay};
+use crate::state::{attribute::Attribute, oca::Overlay, spec_version::SpecVersion};
 use oca_ast_semantics::ast::OverlayType;
 use said::derivation::HashFunctionCode;
 use said::{sad::SerializationFormats, sad::SAD};
@@ -42,7 +42,7 @@ impl Overlay for SubsetOverlay {
 }
 impl SubsetOverlay {
     pub fn new() -> Box<SubsetOverlay> {
-        let overlay_version = "1.1".to_string();
+        let overlay_version = SpecVersion::CURRENT.to_string();
         Box::new(SubsetOverlay {
             capture_base: None,
             said: None,
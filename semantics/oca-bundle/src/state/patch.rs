@@ -0,0 +1,646 @@
+use crate::build::from_ast_with;
+use crate::state::{
+    attribute::Attribute,
+    oca::{
+        overlay::{plugin::registered_overlay, Overlay},
+        DynOverlay, OCABox, OCABundle,
+    },
+    said_options::SaidOptions,
+};
+use indexmap::IndexMap;
+use isolang::Language;
+use oca_ast_semantics::ast::{
+    BundleContent, CaptureContent, Command, CommandType, NestedAttrType, NestedValue, OCAAst,
+    ObjectKind, OverlayType, RefValue, ReferenceAttrType,
+};
+use said::{sad::SerializationFormats, SelfAddressingIdentifier};
+use serde_json::{Map, Value};
+
+/// Attribute fields which ADD commands extend entry by entry, so they are
+/// also merged entry by entry.
+pub(crate) const KEYED_FIELDS: [&str; 6] = [
+    "labels",
+    "category_labels",
+    "informations",
+    "entries",
+    "links",
+    "framings",
+];
+/// Attribute fields which ADD commands can't overwrite once set.
+const SET_ONCE_FIELDS: [&str; 3] = ["unit", "entry_codes", "entry_codes_mapping"];
+/// Attribute fields handled separately from overlay values.
+const SKIPPED_FIELDS: [&str; 4] = ["name", "type", "is_flagged", "dependencies"];
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum Error {
+    #[error("Source OCA Bundle has no SAID")]
+    MissingSourceSaid,
+    #[error("Removing meta '{key}' ({}) is not supported", language.to_639_3())]
+    UnsupportedMetaRemoval { language: Language, key: String },
+    #[error("Adding {overlay_type} overlay is not supported")]
+    UnsupportedOverlay { overlay_type: String },
+    #[error("Removing {overlay_type} overlay is not supported")]
+    UnsupportedOverlayRemoval { overlay_type: String },
+    #[error("Patch can't be applied: {message}")]
+    Build { message: String },
+    #[error("Attribute {attribute} can't be merged: {message}")]
+    Merge { attribute: String, message: String },
+    #[error("Patch builds {actual:?} instead of {expected:?}")]
+    Unreproducible {
+        expected: Option<SelfAddressingIdentifier>,
+        actual: Option<SelfAddressingIdentifier>,
+    },
+}
+
+/// Compute commands turning `a` into `b`. The returned AST starts with a
+/// `FROM` command pointing to `a`, so it can be rendered as OCAFILE with
+/// `generate_from_ast`.
+///
+/// Values are added on top of `a` where possible. An attribute which lost
+/// some value, or which value can't be overwritten, is removed and added
+/// again. The patch is checked to rebuild the exact SAID of `b`.
+pub fn patch(a: &OCABundle, b: &OCABundle) -> Result<OCAAst, Vec<Error>> {
+    patch_with(a, b, &said_options(b))
+}
+
+/// Compute the patch checking it with given SAID options, which `b` was
+/// built with.
+pub fn patch_with(
+    a: &OCABundle,
+    b: &OCABundle,
+    options: &SaidOptions,
+) -> Result<OCAAst, Vec<Error>> {
+    let from_said = a.said.clone().ok_or(vec![Error::MissingSourceSaid])?;
+    let base = OCABox::from(a.clone());
+    let target = OCABox::from(b.clone());
+
+    let mut removed = base
+        .attributes
+        .keys()
+        .filter(|name| !target.attributes.contains_key(*name))
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut added = vec![];
+    let mut flagged = vec![];
+    let mut delta = OCABox::new();
+
+    let mut names = target.attributes.keys().collect::<Vec<_>>();
+    names.sort();
+    for name in names {
+        let new = &target.attributes[name];
+        let old = base.attributes.get(name);
+        let mut attribute = match old.map(|old| attribute_delta(old, new)) {
+            Some(Some(attribute)) => {
+                if old.unwrap().attribute_type != new.attribute_type {
+                    added.push(name.clone());
+                }
+                attribute
+            }
+            Some(None) => {
+                removed.push(name.clone());
+                added.push(name.clone());
+                new.clone()
+            }
+            None => {
+                added.push(name.clone());
+                new.clone()
+            }
+        };
+        if new.is_flagged && (added.contains(name) || !old.is_some_and(|old| old.is_flagged)) {
+            flagged.push(name.clone());
+        }
+        attribute.is_flagged = false;
+        delta.add_attribute(attribute);
+    }
+    removed.sort();
+
+    let mut errors = diff_meta(&base, &target, &mut delta);
+    errors.extend(diff_overlays(&base, &target, &mut delta));
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut commands = vec![];
+    if !removed.is_empty() {
+        let attributes = removed
+            .into_iter()
+            .map(|name| (name, NestedAttrType::Null))
+            .collect();
+        commands.push(capture_base_command(
+            CommandType::Remove,
+            CaptureContent {
+                attributes: Some(attributes),
+                properties: None,
+                flagged_attributes: None,
+            },
+        ));
+    }
+
+    let (old_classification, new_classification) = (
+        &a.capture_base.classification,
+        &b.capture_base.classification,
+    );
+    if old_classification != new_classification {
+        let kind = match new_classification.is_empty() {
+            true => CommandType::Remove,
+            false => CommandType::Add,
+        };
+        let mut properties = IndexMap::new();
+        properties.insert(
+            "classification".to_string(),
+            NestedValue::Value(new_classification.clone()),
+        );
+        commands.push(capture_base_command(
+            kind,
+            CaptureContent {
+                attributes: None,
+                properties: Some(properties),
+                flagged_attributes: None,
+            },
+        ));
+    }
+
+    let mut delta_commands = delta.generate_bundle().to_ast().commands;
+    // Capture base command comes first and lists all attributes of the delta.
+    let attributes = match delta_commands.remove(0).object_kind {
+        ObjectKind::CaptureBase(content) => content.attributes.unwrap_or_default(),
+        _ => IndexMap::new(),
+    };
+    let mut attributes = attributes
+        .into_iter()
+        .filter(|(name, _)| added.contains(name))
+        .collect::<IndexMap<_, _>>();
+    attributes.sort_keys();
+    if !attributes.is_empty() {
+        commands.push(capture_base_command(
+            CommandType::Add,
+            CaptureContent {
+                attributes: Some(attributes),
+                properties: None,
+                flagged_attributes: None,
+            },
+        ));
+    }
+
+    delta_commands.iter_mut().for_each(sort_content);
+    delta_commands.sort_by_key(overlay_command_key);
+    commands.extend(delta_commands);
+
+    if !flagged.is_empty() {
+        commands.push(capture_base_command(
+            CommandType::Add,
+            CaptureContent {
+                attributes: None,
+                properties: None,
+                flagged_attributes: Some(flagged),
+            },
+        ));
+    }
+
+    let mut oca_ast = OCAAst::new();
+    oca_ast.commands = commands;
+    check_patch(a, b, &oca_ast, options)?;
+
+    oca_ast.commands.insert(
+        0,
+        Command {
+            kind: CommandType::From,
+            object_kind: ObjectKind::OCABundle(BundleContent {
+                said: ReferenceAttrType::Reference(RefValue::Said(from_said)),
+            }),
+        },
+    );
+    Ok(oca_ast)
+}
+
+/// Attribute holding only values which have to be added on top of `old` to
+/// get `new`, or `None` when `new` can't be reached without recreating the
+/// attribute.
+fn attribute_delta(old: &Attribute, new: &Attribute) -> Option<Attribute> {
+    if old.is_flagged && !new.is_flagged {
+        return None;
+    }
+    let old_values = to_map(old);
+    let new_values = to_map(new);
+
+    let mut delta = Map::new();
+    for field in SKIPPED_FIELDS {
+        delta.insert(field.to_string(), new_values[field].clone());
+    }
+    delta.insert("dependencies".to_string(), Value::Null);
+
+    for (field, new_value) in &new_values {
+        if SKIPPED_FIELDS.contains(&field.as_str()) {
+            continue;
+        }
+        let old_value = old_values.get(field).unwrap_or(&Value::Null);
+        if old_value == new_value {
+            continue;
+        }
+        if new_value.is_null()
+            || (SET_ONCE_FIELDS.contains(&field.as_str()) && !old_value.is_null())
+        {
+            return None;
+        }
+        match (old_value, new_value) {
+            (Value::Object(old_entries), Value::Object(new_entries))
+                if KEYED_FIELDS.contains(&field.as_str()) =>
+            {
+                if old_entries.keys().any(|key| !new_entries.contains_key(key)) {
+                    return None;
+                }
+                let changed = new_entries
+                    .iter()
+                    .filter(|(key, value)| old_entries.get(*key) != Some(value))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect::<Map<_, _>>();
+                delta.insert(field.clone(), Value::Object(changed));
+            }
+            _ => {
+                delta.insert(field.clone(), new_value.clone());
+                if field == "condition" {
+                    delta.insert(
+                        "dependencies".to_string(),
+                        new_values["dependencies"].clone(),
+                    );
+                }
+            }
+        }
+    }
+
+    serde_json::from_value(Value::Object(delta)).ok()
+}
+
+fn to_map(attribute: &Attribute) -> Map<String, Value> {
+    match serde_json::to_value(attribute) {
+        Ok(Value::Object(values)) => values,
+        _ => Map::new(),
+    }
+}
+
+/// Add changed meta to `delta`. Meta can't be removed with OCAFILE commands.
+fn diff_meta(base: &OCABox, target: &OCABox, delta: &mut OCABox) -> Vec<Error> {
+    let mut errors = vec![];
+    let base_meta = base.meta.clone().unwrap_or_default();
+    let target_meta = target.meta.clone().unwrap_or_default();
+
+    for (language, pairs) in &base_meta {
+        for key in pairs.keys() {
+            if !target_meta
+                .get(language)
+                .is_some_and(|pairs| pairs.contains_key(key))
+            {
+                errors.push(Error::UnsupportedMetaRemoval {
+                    language: *language,
+                    key: key.clone(),
+                });
+            }
+        }
+    }
+    for (language, pairs) in &target_meta {
+        for (key, value) in pairs {
+            if base_meta.get(language).and_then(|pairs| pairs.get(key)) != Some(value) {
+                let mut meta = delta.meta.take().unwrap_or_default();
+                meta.entry(*language)
+                    .or_default()
+                    .insert(key.clone(), value.clone());
+                delta.meta = Some(meta);
+            }
+        }
+    }
+    errors
+}
+
+/// Add changed overlays which aren't built from attributes to `delta`. Only
+/// overlays of registered plugins can be added with OCAFILE commands, and
+/// none of them can be removed.
+fn diff_overlays(base: &OCABox, target: &OCABox, delta: &mut OCABox) -> Vec<Error> {
+    let mut errors = vec![];
+    let base_mappings = base.mappings.clone().unwrap_or_default();
+    let target_mappings = target.mappings.clone().unwrap_or_default();
+    for mapping in &base_mappings {
+        if !target_mappings
+            .iter()
+            .any(|m| m.attribute_mapping == mapping.attribute_mapping)
+        {
+            errors.push(Error::UnsupportedOverlayRemoval {
+                overlay_type: mapping.overlay_type().to_string(),
+            });
+        }
+    }
+    for mapping in &target_mappings {
+        if !base_mappings
+            .iter()
+            .any(|m| m.attribute_mapping == mapping.attribute_mapping)
+        {
+            errors.push(Error::UnsupportedOverlay {
+                overlay_type: mapping.overlay_type().to_string(),
+            });
+        }
+    }
+
+    for overlay in &base.custom_overlays {
+        if !target.custom_overlays.iter().any(|o| same_kind(o, overlay)) {
+            errors.push(Error::UnsupportedOverlayRemoval {
+                overlay_type: overlay.overlay_type().to_string(),
+            });
+        }
+    }
+    for overlay in &target.custom_overlays {
+        let content = overlay_content(overlay);
+        if base
+            .custom_overlays
+            .iter()
+            .any(|o| same_kind(o, overlay) && overlay_content(o) == content)
+        {
+            continue;
+        }
+        if expressible(overlay) {
+            delta.add_custom_overlay(overlay.clone());
+        } else {
+            errors.push(Error::UnsupportedOverlay {
+                overlay_type: overlay.overlay_type().to_string(),
+            });
+        }
+    }
+    errors
+}
+
+/// Whether the overlay can be added with an OCAFILE command, which needs a
+/// registered plugin.
+pub(crate) fn expressible(overlay: &DynOverlay) -> bool {
+    match overlay.overlay_type() {
+        OverlayType::Custom { name, .. } => registered_overlay(name)
+            .and_then(|plugin| plugin.to_content(&**overlay))
+            .is_some(),
+        _ => false,
+    }
+}
+
+/// Whether both overlays have the same type and language, so one replaces
+/// the other.
+pub(crate) fn same_kind(a: &DynOverlay, b: &DynOverlay) -> bool {
+    a.overlay_type() == b.overlay_type() && a.language() == b.language()
+}
+
+/// Overlay value without SAIDs, which depend on the capture base.
+pub(crate) fn overlay_content(overlay: &DynOverlay) -> Value {
+    let mut value = serde_json::to_value(overlay).unwrap_or_default();
+    if let Value::Object(fields) = &mut value {
+        fields.remove("d");
+        fields.remove("capture_base");
+    }
+    value
+}
+
+fn capture_base_command(kind: CommandType, content: CaptureContent) -> Command {
+    Command {
+        kind,
+        object_kind: ObjectKind::CaptureBase(content),
+    }
+}
+
+/// Sort overlay command values so that the patch doesn't depend on hash map
+/// ordering. Language property is kept first.
+fn sort_content(command: &mut Command) {
+    if let ObjectKind::Overlay(_, content) = &mut command.object_kind {
+        if let Some(attributes) = content.attributes.as_mut() {
+            attributes.sort_keys();
+        }
+        if let Some(properties) = content.properties.as_mut() {
+            let lang = properties.shift_remove("lang");
+            properties.sort_keys();
+            if let Some(lang) = lang {
+                let mut sorted = IndexMap::new();
+                sorted.insert("lang".to_string(), lang);
+                sorted.extend(properties.drain(..));
+                *properties = sorted;
+            }
+        }
+    }
+}
+
+fn overlay_command_key(command: &Command) -> (String, String) {
+    match &command.object_kind {
+        ObjectKind::Overlay(overlay_type, content) => {
+            let lang = content
+                .properties
+                .as_ref()
+                .and_then(|properties| properties.get("lang"))
+                .map(|lang| format!("{:?}", lang))
+                .unwrap_or_default();
+            (overlay_type.to_string(), lang)
+        }
+        _ => (String::new(), String::new()),
+    }
+}
+
+/// Digest algorithm of the bundle SAID with JSON serialization.
+pub(crate) fn said_options(oca_bundle: &OCABundle) -> SaidOptions {
+    match &oca_bundle.said {
+        Some(said) => SaidOptions::new(said.derivation.clone().into(), SerializationFormats::JSON),
+        None => SaidOptions::default(),
+    }
+}
+
+fn check_patch(
+    a: &OCABundle,
+    b: &OCABundle,
+    oca_ast: &OCAAst,
+    options: &SaidOptions,
+) -> Result<(), Vec<Error>> {
+    let oca_build = from_ast_with(Some(a.clone()), oca_ast, options).map_err(|errors| {
+        errors
+            .into_iter()
+            .map(|e| Error::Build {
+                message: e.to_string(),
+            })
+            .collect::<Vec<_>>()
+    })?;
+    if oca_build.oca_bundle.said != b.said {
+        return Err(vec![Error::Unreproducible {
+            expected: b.said.clone(),
+            actual: oca_build.oca_bundle.said,
+        }]);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{attribute, consent, mapping};
+    use crate::state::{
+        attribute::AttributeType,
+        oca::overlay::{
+            conformance::Conformances, information::Information, meta::Metas, unit::Units,
+        },
+    };
+
+    fn base() -> OCABox {
+        let mut oca = OCABox::new();
+        oca.add_meta(Language::Eng, "name".to_string(), "Person".to_string());
+        oca.add_attribute(attribute("name", AttributeType::Text, "Name"));
+        let mut age = attribute("age", AttributeType::Text, "Age");
+        age.set_unit("year".to_string());
+        oca.add_attribute(age);
+        oca.add_attribute(attribute("email", AttributeType::Text, "Email"));
+        oca
+    }
+
+    #[test]
+    fn patch_rebuilds_target() {
+        let a = base().generate_bundle();
+
+        let mut oca = base();
+        oca.add_classification("GICS:35102020".to_string());
+        oca.add_meta(
+            Language::Eng,
+            "description".to_string(),
+            "Person data".to_string(),
+        );
+        oca.remove_attribute(&"email".to_string());
+        let mut name = attribute("name", AttributeType::Text, "Full name");
+        name.set_information(Language::Pol, "Imię i nazwisko".to_string());
+        name.set_conformance("M".to_string());
+        oca.add_attribute(name);
+        oca.attributes.get_mut("name").unwrap().set_flagged();
+        let mut age = attribute("age", AttributeType::Numeric, "Age");
+        age.set_unit("month".to_string());
+        oca.attributes.insert("age".to_string(), age);
+        oca.add_attribute(attribute("phone", AttributeType::Text, "Phone"));
+        let b = oca.generate_bundle();
+
+        let oca_ast = patch(&a, &b).unwrap();
+        let kinds = oca_ast
+            .commands
+            .iter()
+            .map(|c| match &c.object_kind {
+                ObjectKind::OCABundle(_) => "FROM".to_string(),
+                ObjectKind::CaptureBase(_) => format!("{:?} CAPTURE_BASE", c.kind),
+                ObjectKind::Overlay(o, _) => format!("{:?} {}", c.kind, o),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                "FROM",
+                "Remove CAPTURE_BASE",
+                "Add CAPTURE_BASE",
+                "Add CAPTURE_BASE",
+                "Add Conformance",
+                "Add Information",
+                "Add Label",
+                "Add Meta",
+                "Add Unit",
+                "Add CAPTURE_BASE",
+            ]
+        );
+        let ObjectKind::CaptureBase(removed) = &oca_ast.commands[1].object_kind else {
+            panic!("expected capture base command")
+        };
+        assert_eq!(
+            removed
+                .attributes
+                .as_ref()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            vec!["age", "email"]
+        );
+        let ObjectKind::CaptureBase(added) = &oca_ast.commands[3].object_kind else {
+            panic!("expected capture base command")
+        };
+        assert_eq!(
+            added
+                .attributes
+                .as_ref()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            vec!["age", "phone"]
+        );
+        let ObjectKind::Overlay(_, meta) = &oca_ast.commands[7].object_kind else {
+            panic!("expected overlay command")
+        };
+        assert_eq!(
+            meta.properties.as_ref().unwrap().keys().collect::<Vec<_>>(),
+            vec!["lang", "description"]
+        );
+    }
+
+    #[test]
+    fn patch_of_same_bundle_is_empty() {
+        let a = base().generate_bundle();
+        let oca_ast = patch(&a, &a).unwrap();
+        assert_eq!(oca_ast.commands.len(), 1);
+        assert_eq!(oca_ast.commands[0].kind, CommandType::From);
+    }
+
+    #[test]
+    fn patch_with_removed_meta() {
+        let a = base().generate_bundle();
+        let mut oca = base();
+        oca.meta = None;
+        let b = oca.generate_bundle();
+
+        let errors = patch(&a, &b).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![Error::UnsupportedMetaRemoval {
+                language: Language::Eng,
+                key: "name".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn patch_keeps_custom_overlays() {
+        let mut oca = base();
+        oca.add_custom_overlay(consent("required"));
+        oca.add_attribute_mapping(mapping());
+        let a = oca.generate_bundle();
+
+        oca.add_attribute(attribute("phone", AttributeType::Text, "Phone"));
+        let b = oca.generate_bundle();
+
+        let oca_ast = patch(&a, &b).unwrap();
+        assert_eq!(oca_ast.commands.len(), 3);
+    }
+
+    #[test]
+    fn patch_with_changed_overlays() {
+        let mut oca = base();
+        oca.add_custom_overlay(consent("required"));
+        let a = oca.generate_bundle();
+
+        let mut oca = base();
+        oca.add_custom_overlay(consent("optional"));
+        oca.add_attribute_mapping(mapping());
+        let b = oca.generate_bundle();
+
+        assert_eq!(
+            patch(&a, &b).unwrap_err(),
+            vec![
+                Error::UnsupportedOverlay {
+                    overlay_type: "AttributeMapping".to_string()
+                },
+                Error::UnsupportedOverlay {
+                    overlay_type: "community_consent".to_string()
+                }
+            ]
+        );
+        assert_eq!(
+            patch(&b, &base().generate_bundle()).unwrap_err(),
+            vec![
+                Error::UnsupportedOverlayRemoval {
+                    overlay_type: "AttributeMapping".to_string()
+                },
+                Error::UnsupportedOverlayRemoval {
+                    overlay_type: "community_consent".to_string()
+                }
+            ]
+        );
+    }
+}
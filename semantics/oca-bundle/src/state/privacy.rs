@@ -0,0 +1,285 @@
+//! Handling of flagged attributes in records.
+//!
+//! Attributes listed in `flagged_attributes` of a capture base hold
+//! sensitive data. Records, JSON objects keyed by attribute names, can have
+//! their flagged values redacted, blinded with salted digests or split off.
+//! Records of referenced bundles are handled the same way.
+use crate::state::{dependencies::Dependencies, oca::OCABundle};
+use oca_ast_semantics::ast::NestedAttrType;
+use said::derivation::{HashFunction, HashFunctionCode};
+use serde_json::{Map, Value};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum Error {
+    #[error("Record of {said} is not a JSON object")]
+    InvalidRecord { said: String },
+    #[serde(untagged)]
+    #[error(transparent)]
+    Dependency(#[from] crate::state::dependencies::Error),
+}
+
+/// Record split into part which can be shared and part holding values of
+/// flagged attributes.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SplitRecord {
+    /// Record with flagged values replaced by their digests.
+    pub public: Value,
+    /// Flagged values by their digests.
+    pub sensitive: Map<String, Value>,
+}
+
+/// Replace values of flagged attributes with `null`.
+pub fn redact(
+    record: &Value,
+    bundle: &OCABundle,
+    dependencies: &[OCABundle],
+) -> Result<Value, Error> {
+    let mut record = record.clone();
+    Walker::new(dependencies).record(bundle, &mut record, &mut |value| *value = Value::Null)?;
+    Ok(record)
+}
+
+/// Replace values of flagged attributes with their digests, salted with
+/// `salt`.
+///
+/// Digests are Blake3-256 SAIDs of the salt followed by the JSON of the
+/// value. Equal values get equal digests under the same salt, use a fresh
+/// random salt per record when records mustn't be linkable.
+pub fn blind(
+    record: &Value,
+    bundle: &OCABundle,
+    dependencies: &[OCABundle],
+    salt: &[u8],
+) -> Result<Value, Error> {
+    Ok(split(record, bundle, dependencies, salt)?.public)
+}
+
+/// Split record into blinded public part and sensitive part, which maps
+/// digests used in the public part to original values.
+pub fn split(
+    record: &Value,
+    bundle: &OCABundle,
+    dependencies: &[OCABundle],
+    salt: &[u8],
+) -> Result<SplitRecord, Error> {
+    let mut public = record.clone();
+    let mut sensitive = Map::new();
+    Walker::new(dependencies).record(bundle, &mut public, &mut |value| {
+        let digest = digest(value, salt);
+        sensitive.insert(
+            digest.clone(),
+            std::mem::replace(value, Value::String(digest)),
+        );
+    })?;
+
+    Ok(SplitRecord { public, sensitive })
+}
+
+/// Restore record from its split parts. Digests missing from the
+/// sensitive part are left in place.
+pub fn join(
+    split: &SplitRecord,
+    bundle: &OCABundle,
+    dependencies: &[OCABundle],
+) -> Result<Value, Error> {
+    let mut record = split.public.clone();
+    Walker::new(dependencies).record(bundle, &mut record, &mut |value| {
+        if let Some(original) = value.as_str().and_then(|d| split.sensitive.get(d)) {
+            *value = original.clone();
+        }
+    })?;
+    Ok(record)
+}
+
+fn digest(value: &Value, salt: &[u8]) -> String {
+    let mut data = salt.to_vec();
+    data.extend(value.to_string().into_bytes());
+    HashFunction::from(HashFunctionCode::Blake3_256)
+        .derive(&data)
+        .to_string()
+}
+
+struct Walker<'a> {
+    dependencies: Dependencies<'a>,
+}
+
+impl<'a> Walker<'a> {
+    fn new(dependencies: &'a [OCABundle]) -> Self {
+        Self {
+            dependencies: Dependencies::new(dependencies),
+        }
+    }
+
+    /// Apply `f` to flagged values of the record, null values are skipped.
+    fn record(
+        &self,
+        bundle: &OCABundle,
+        record: &mut Value,
+        f: &mut dyn FnMut(&mut Value),
+    ) -> Result<(), Error> {
+        let capture_base = &bundle.capture_base;
+        let Value::Object(values) = record else {
+            return Err(Error::InvalidRecord {
+                said: capture_base
+                    .said
+                    .as_ref()
+                    .map(|said| said.to_string())
+                    .unwrap_or_default(),
+            });
+        };
+        for (name, value) in values.iter_mut() {
+            let Some(attr_type) = capture_base.attributes.get(name) else {
+                continue;
+            };
+            if value.is_null() {
+                continue;
+            }
+            if capture_base.flagged_attributes.contains(name) {
+                f(value);
+            } else {
+                self.value(attr_type, value, f)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn value(
+        &self,
+        attr_type: &NestedAttrType,
+        value: &mut Value,
+        f: &mut dyn FnMut(&mut Value),
+    ) -> Result<(), Error> {
+        match (attr_type, value) {
+            (NestedAttrType::Array(item_type), Value::Array(items)) => {
+                for item in items.iter_mut().filter(|item| !item.is_null()) {
+                    self.value(item_type, item, f)?;
+                }
+            }
+            (NestedAttrType::Reference(reference), value) => {
+                let dependency = self.dependencies.resolve(reference)?;
+                self.record(dependency, value, f)?;
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{
+        attribute::{Attribute, AttributeType},
+        dependencies,
+        oca::OCABox,
+    };
+    use oca_ast_semantics::ast::RefValue;
+    use serde_json::json;
+
+    fn attribute(name: &str, attr_type: NestedAttrType, flagged: bool) -> Attribute {
+        let mut attribute = Attribute::new(name.to_string());
+        attribute.set_attribute_type(attr_type);
+        if flagged {
+            attribute.set_flagged();
+        }
+        attribute
+    }
+
+    fn bundles() -> (OCABundle, OCABundle) {
+        let mut address = OCABox::new();
+        address.add_attribute(attribute(
+            "street",
+            NestedAttrType::Value(AttributeType::Text),
+            true,
+        ));
+        address.add_attribute(attribute(
+            "city",
+            NestedAttrType::Value(AttributeType::Text),
+            false,
+        ));
+        let address = address.generate_bundle();
+
+        let mut person = OCABox::new();
+        person.add_attribute(attribute(
+            "name",
+            NestedAttrType::Value(AttributeType::Text),
+            true,
+        ));
+        person.add_attribute(attribute(
+            "age",
+            NestedAttrType::Value(AttributeType::Numeric),
+            false,
+        ));
+        person.add_attribute(attribute(
+            "homes",
+            NestedAttrType::Array(Box::new(NestedAttrType::Reference(RefValue::Said(
+                address.said.clone().unwrap(),
+            )))),
+            false,
+        ));
+        (person.generate_bundle(), address)
+    }
+
+    fn record() -> Value {
+        json!({
+            "name": "Jan Kowalski",
+            "age": 30,
+            "homes": [{ "street": "Długa 1", "city": "Kraków" }]
+        })
+    }
+
+    #[test]
+    fn redact_flagged_values() {
+        let (person, address) = bundles();
+        let redacted = redact(&record(), &person, &[address]).unwrap();
+
+        assert_eq!(
+            redacted,
+            json!({
+                "name": null,
+                "age": 30,
+                "homes": [{ "street": null, "city": "Kraków" }]
+            })
+        );
+    }
+
+    #[test]
+    fn blind_and_split() {
+        let (person, address) = bundles();
+        let dependencies = [address];
+        let blinded = blind(&record(), &person, &dependencies, b"salt").unwrap();
+        let name = blinded["name"].as_str().unwrap();
+        assert!(name.parse::<said::SelfAddressingIdentifier>().is_ok());
+        assert_eq!(blinded["age"], json!(30));
+        assert_eq!(blinded["homes"][0]["city"], json!("Kraków"));
+        assert_ne!(
+            blind(&record(), &person, &dependencies, b"pepper").unwrap()["name"],
+            blinded["name"]
+        );
+
+        let split = split(&record(), &person, &dependencies, b"salt").unwrap();
+        assert_eq!(split.public, blinded);
+        assert_eq!(split.sensitive[name], json!("Jan Kowalski"));
+        assert_eq!(split.sensitive.len(), 2);
+        assert_eq!(join(&split, &person, &dependencies).unwrap(), record());
+    }
+
+    #[test]
+    fn missing_dependency() {
+        let (person, address) = bundles();
+        assert_eq!(
+            redact(&record(), &person, &[]),
+            Err(Error::Dependency(dependencies::Error::MissingDependency {
+                said: address.said.unwrap().to_string()
+            }))
+        );
+        let error = redact(&record(), &person, &[]).unwrap_err();
+        assert_eq!(
+            serde_json::to_value(error).unwrap()["code"],
+            "missing_dependency"
+        );
+    }
+}
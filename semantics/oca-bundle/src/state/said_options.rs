@@ -0,0 +1,24 @@
+use said::{derivation::HashFunctionCode, sad::SerializationFormats};
+
+/// Digest algorithm and serialization format used to compute SAIDs of
+/// capture bases, overlays and bundles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaidOptions {
+    pub code: HashFunctionCode,
+    pub format: SerializationFormats,
+}
+
+impl Default for SaidOptions {
+    fn default() -> Self {
+        Self {
+            code: HashFunctionCode::Blake3_256,
+            format: SerializationFormats::JSON,
+        }
+    }
+}
+
+impl SaidOptions {
+    pub fn new(code: HashFunctionCode, format: SerializationFormats) -> Self {
+        Self { code, format }
+    }
+}
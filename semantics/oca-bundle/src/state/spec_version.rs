@@ -0,0 +1,57 @@
+use crate::state::migration::Error;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Version of the OCA specification, the last segment of capture base and
+/// overlay types, e.g. `spec/overlays/label/1.0`.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum SpecVersion {
+    #[serde(rename = "1.0")]
+    V1_0,
+    #[default]
+    #[serde(rename = "1.1")]
+    V1_1,
+}
+
+impl SpecVersion {
+    /// Version bundles are built in and migrated to.
+    pub const CURRENT: SpecVersion = SpecVersion::V1_1;
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpecVersion::V1_0 => "1.0",
+            SpecVersion::V1_1 => "1.1",
+        }
+    }
+
+    /// Version of capture base or overlay type, e.g.
+    /// `spec/capture_base/1.0`.
+    pub fn of_type(type_: &str) -> Result<Self, Error> {
+        type_
+            .rsplit_once('/')
+            .map_or(type_, |(_, version)| version)
+            .parse()
+    }
+}
+
+impl fmt::Display for SpecVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SpecVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1.0" => Ok(SpecVersion::V1_0),
+            "1.1" => Ok(SpecVersion::V1_1),
+            _ => Err(Error::UnsupportedVersion {
+                version: s.to_string(),
+            }),
+        }
+    }
+}
@@ -3,45 +3,196 @@ use crate::state::oca::DynOverlay;
 use indexmap::IndexMap;
 use isolang::Language;
 use oca_ast_semantics::ast::{AttributeType, NestedAttrType, OverlayType};
-use std::{
-    collections::{HashMap, HashSet},
-    error::Error as StdError,
-};
+use said::SelfAddressingIdentifier;
+use std::collections::{HashMap, HashSet};
 
 use super::oca::{overlay, OCABundle};
-use piccolo::{Closure, Lua, Thread};
+use super::said_options::SaidOptions;
+use super::spec_version::SpecVersion;
+use piccolo::{Closure, Lua, StaticError, Thread};
+
+mod profile;
+pub use profile::{
+    AttributeTranslationsRule, MaxAttributesRule, MetaRule, ProfileError, Rule,
+    TranslatableOverlay, ValidationProfile,
+};
 
-#[derive(Debug)]
+/// Semantic validation error.
+///
+/// Every variant carries a stable machine-readable code (see [`Error::code`])
+/// which is also used as the `code` tag when the error is serialized, so
+/// consumers can match on it without parsing the message.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
 pub enum Error {
-    Custom(String),
-    MissingTranslations(Language),
-    MissingMetaTranslation(Language, String),
-    UnexpectedTranslations(Language),
-    MissingAttributeTranslation(Language, String),
+    #[error("OCA Bundle: Malformed SAID")]
+    MalformedBundleSaid {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        said: Option<SelfAddressingIdentifier>,
+    },
+    #[error("capture_base: Malformed SAID")]
+    MalformedCaptureBaseSaid {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        said: Option<SelfAddressingIdentifier>,
+    },
+    #[error("{}: Malformed SAID", overlay_label(overlay_type, language))]
+    MalformedOverlaySaid {
+        overlay_type: OverlayType,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        language: Option<Language>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        said: Option<SelfAddressingIdentifier>,
+    },
+    #[error("{}: Mismatch capture_base SAI", overlay_label(overlay_type, language))]
+    CaptureBaseMismatch {
+        overlay_type: OverlayType,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        language: Option<Language>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        said: Option<SelfAddressingIdentifier>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        capture_base: Option<SelfAddressingIdentifier>,
+    },
+    #[error("Attribute '{attribute_path}' cannot be a dependency of itself")]
+    SelfDependentCondition { attribute_path: String },
+    #[error("Attribute '{attribute_path}' depends on unknown attribute '{dependency}'")]
+    UnknownConditionDependency {
+        attribute_path: String,
+        dependency: String,
+    },
+    #[error("Attribute '{attribute_path}' has invalid condition: {message}")]
+    InvalidCondition {
+        attribute_path: String,
+        message: String,
+    },
+    #[error("{overlay_type} overlay: translations in {language:?} language are missing")]
+    MissingTranslations {
+        overlay_type: OverlayType,
+        language: Language,
+    },
+    #[error("{overlay_type} overlay: translations in {language:?} language are not enforced")]
+    UnexpectedTranslations {
+        overlay_type: OverlayType,
+        language: Language,
+    },
+    #[error("meta overlay: for '{key}' translation in {language:?} language is missing")]
+    MissingMetaTranslation {
+        overlay_type: OverlayType,
+        language: Language,
+        key: String,
+    },
+    #[error("{overlay_type} overlay: for '{attribute_path}' attribute missing translations in {language:?} language")]
+    MissingAttributeTranslation {
+        overlay_type: OverlayType,
+        language: Language,
+        attribute_path: String,
+    },
+    #[error("Flagged attribute '{attribute_path}' has no sensitivity entry")]
+    MissingSensitivity { attribute_path: String },
+    #[error("capture_base: classification is not set")]
+    MissingClassification,
+    #[error("capture_base: {count} attributes exceed the limit of {limit}")]
+    TooManyAttributes { limit: usize, count: usize },
+}
+
+fn overlay_label(overlay_type: &OverlayType, language: &Option<Language>) -> String {
+    match language {
+        Some(lang) => format!("{overlay_type} ({lang})"),
+        None => overlay_type.to_string(),
+    }
 }
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Error {
+    /// Stable identifier of the error kind.
+    pub fn code(&self) -> &'static str {
         match self {
-            Error::Custom(error) => write!(f, "{error}"),
-            Error::MissingTranslations(language) => {
-                write!(f, "Missing translation in {language} language")
-            }
-            Error::MissingMetaTranslation(language, attr) => write!(
-                f,
-                "Missing meta translation for {attr} in {language} language"
-            ),
-            Error::UnexpectedTranslations(language) => {
-                write!(f, "Unexpected translations in {language} language")
-            }
-            Error::MissingAttributeTranslation(language, attr) => {
-                write!(f, "Missing translation for {attr} in {language} language")
-            }
+            Error::MalformedBundleSaid { .. } => "malformed_bundle_said",
+            Error::MalformedCaptureBaseSaid { .. } => "malformed_capture_base_said",
+            Error::MalformedOverlaySaid { .. } => "malformed_overlay_said",
+            Error::CaptureBaseMismatch { .. } => "capture_base_mismatch",
+            Error::SelfDependentCondition { .. } => "self_dependent_condition",
+            Error::UnknownConditionDependency { .. } => "unknown_condition_dependency",
+            Error::InvalidCondition { .. } => "invalid_condition",
+            Error::MissingTranslations { .. } => "missing_translations",
+            Error::UnexpectedTranslations { .. } => "unexpected_translations",
+            Error::MissingMetaTranslation { .. } => "missing_meta_translation",
+            Error::MissingAttributeTranslation { .. } => "missing_attribute_translation",
+            Error::MissingSensitivity { .. } => "missing_sensitivity",
+            Error::MissingClassification => "missing_classification",
+            Error::TooManyAttributes { .. } => "too_many_attributes",
+        }
+    }
+
+    /// Attribute the error refers to, if any.
+    pub fn attribute_path(&self) -> Option<&str> {
+        match self {
+            Error::SelfDependentCondition { attribute_path }
+            | Error::UnknownConditionDependency { attribute_path, .. }
+            | Error::InvalidCondition { attribute_path, .. }
+            | Error::MissingAttributeTranslation { attribute_path, .. }
+            | Error::MissingSensitivity { attribute_path } => Some(attribute_path),
+            _ => None,
+        }
+    }
+
+    pub fn overlay_type(&self) -> Option<&OverlayType> {
+        match self {
+            Error::MalformedOverlaySaid { overlay_type, .. }
+            | Error::CaptureBaseMismatch { overlay_type, .. }
+            | Error::MissingTranslations { overlay_type, .. }
+            | Error::UnexpectedTranslations { overlay_type, .. }
+            | Error::MissingMetaTranslation { overlay_type, .. }
+            | Error::MissingAttributeTranslation { overlay_type, .. } => Some(overlay_type),
+            _ => None,
+        }
+    }
+
+    pub fn language(&self) -> Option<&Language> {
+        match self {
+            Error::MalformedOverlaySaid { language, .. }
+            | Error::CaptureBaseMismatch { language, .. } => language.as_ref(),
+            Error::MissingTranslations { language, .. }
+            | Error::UnexpectedTranslations { language, .. }
+            | Error::MissingMetaTranslation { language, .. }
+            | Error::MissingAttributeTranslation { language, .. } => Some(language),
+            _ => None,
+        }
+    }
+
+    /// SAID of the object the error refers to, if any.
+    pub fn said(&self) -> Option<&SelfAddressingIdentifier> {
+        match self {
+            Error::MalformedBundleSaid { said }
+            | Error::MalformedCaptureBaseSaid { said }
+            | Error::MalformedOverlaySaid { said, .. }
+            | Error::CaptureBaseMismatch { said, .. } => said.as_ref(),
+            _ => None,
         }
     }
 }
 
-impl std::error::Error for Error {}
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+}
+
+/// Validation result with its severity. Only findings of `Error` severity
+/// make a bundle invalid.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    #[serde(flatten)]
+    pub error: Error,
+}
+
+impl Finding {
+    pub fn new(severity: Severity, error: Error) -> Self {
+        Self { severity, error }
+    }
+}
 
 pub enum SemanticValidationStatus {
     Valid,
@@ -58,6 +209,8 @@ pub fn validate(oca_bundle: &OCABundle) -> Result<SemanticValidationStatus, Stri
 
 pub struct Validator {
     enforced_translations: Vec<Language>,
+    profile: Option<ValidationProfile>,
+    said_options: Option<SaidOptions>,
 }
 
 impl Default for Validator {
@@ -70,6 +223,8 @@ impl Validator {
     pub fn new() -> Validator {
         Validator {
             enforced_translations: vec![],
+            profile: None,
+            said_options: None,
         }
     }
 
@@ -82,50 +237,56 @@ impl Validator {
         self
     }
 
-    pub fn validate(self, oca_bundle: &OCABundle) -> Result<(), Vec<Error>> {
-        let enforced_langs: HashSet<_> = self.enforced_translations.iter().collect();
-        let mut errors: Vec<Error> = vec![];
+    pub fn with_profile(mut self, profile: ValidationProfile) -> Validator {
+        self.profile = Some(profile);
+        self
+    }
 
-        /* let oca_bundle: OCABundle = serde_json::from_str(oca_str.as_str())
-                   .map_err(|e| vec![Error::Custom(e.to_string())])?;
-        */
-        let mut recalculated_oca_bundle = oca_bundle.clone();
-        recalculated_oca_bundle.fill_said();
+    /// Verify SAIDs with given options instead of assuming the digest
+    /// algorithm of the bundle SAID and JSON serialization.
+    pub fn with_said_options(mut self, said_options: SaidOptions) -> Validator {
+        self.said_options = Some(said_options);
+        self
+    }
 
-        if oca_bundle.said.ne(&recalculated_oca_bundle.said) {
-            errors.push(Error::Custom("OCA Bundle: Malformed SAID".to_string()));
-        }
+    pub fn validate(self, oca_bundle: &OCABundle) -> Result<(), Vec<Error>> {
+        let errors: Vec<Error> = self
+            .check(oca_bundle)
+            .into_iter()
+            .filter(|finding| finding.severity == Severity::Error)
+            .map(|finding| finding.error)
+            .collect();
 
-        let capture_base = &oca_bundle.capture_base;
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 
-        let mut recalculated_capture_base = capture_base.clone();
-        recalculated_capture_base.sign();
+    /// Run all checks, including the ones from the profile, and return every
+    /// finding regardless of its severity.
+    pub fn check(&self, oca_bundle: &OCABundle) -> Vec<Finding> {
+        let mut findings: Vec<Finding> = self
+            .validate_structure(oca_bundle)
+            .into_iter()
+            .map(|error| Finding::new(Severity::Error, error))
+            .collect();
 
-        if capture_base.said.ne(&recalculated_capture_base.said) {
-            errors.push(Error::Custom("capture_base: Malformed SAID".to_string()));
+        if let Some(profile) = &self.profile {
+            findings.extend(profile.apply(oca_bundle));
         }
 
-        for o in &oca_bundle.overlays {
-            let mut recalculated_overlay = o.clone();
-            recalculated_overlay.fill_said();
-            if o.said().ne(recalculated_overlay.said()) {
-                let msg = match o.language() {
-                    Some(lang) => format!("{} ({}): Malformed SAID", o.overlay_type(), lang),
-                    None => format!("{}: Malformed SAID", o.overlay_type()),
-                };
-                errors.push(Error::Custom(msg));
-            }
+        findings
+    }
 
-            if o.capture_base().ne(&capture_base.said) {
-                let msg = match o.language() {
-                    Some(lang) => {
-                        format!("{} ({}): Mismatch capture_base SAI", o.overlay_type(), lang)
-                    }
-                    None => format!("{}: Mismatch capture_base SAI", o.overlay_type()),
-                };
-                errors.push(Error::Custom(msg));
-            }
-        }
+    fn validate_structure(&self, oca_bundle: &OCABundle) -> Vec<Error> {
+        let enforced_langs: HashSet<_> = self.enforced_translations.iter().collect();
+        let report = match &self.said_options {
+            Some(said_options) => oca_bundle.verify_with(said_options),
+            None => oca_bundle.verify(),
+        };
+        let mut errors: Vec<Error> = report.errors();
 
         let conditional_overlay = oca_bundle
             .overlays
@@ -133,10 +294,11 @@ impl Validator {
             .find_map(|x| x.as_any().downcast_ref::<overlay::Conditional>());
 
         if let Some(conditional_overlay) = conditional_overlay {
-            self.validate_conditional(
-                oca_bundle.capture_base.attributes.clone(),
-                conditional_overlay,
-            )?;
+            if let Err(conditional_errors) =
+                self.validate_conditional(&oca_bundle.capture_base.attributes, conditional_overlay)
+            {
+                errors.extend(conditional_errors);
+            }
         }
 
         if !enforced_langs.is_empty() {
@@ -148,30 +310,11 @@ impl Validator {
 
             if !meta_overlays.is_empty() {
                 if let Err(meta_errors) = self.validate_meta(&enforced_langs, meta_overlays) {
-                    errors = errors
-                        .into_iter()
-                        .chain(meta_errors.into_iter().map(|e| {
-                            if let Error::UnexpectedTranslations(lang) = e {
-                                Error::Custom(format!(
-                                    "meta overlay: translations in {lang:?} language are not enforced"
-                                ))
-                            } else if let Error::MissingTranslations(lang) = e {
-                                Error::Custom(format!(
-                                    "meta overlay: translations in {lang:?} language are missing"
-                                ))
-                            } else if let Error::MissingMetaTranslation(lang, attr) = e {
-                                Error::Custom(format!(
-                                    "meta overlay: for '{attr}' translation in {lang:?} language is missing"
-                                ))
-                            } else {
-                                e
-                            }
-                        }))
-                        .collect();
+                    errors.extend(meta_errors);
                 }
             }
 
-            let overlay_version = "1.1".to_string();
+            let overlay_version = SpecVersion::CURRENT.to_string();
             for overlay_type in &[
                 OverlayType::Entry(overlay_version.clone()),
                 OverlayType::Information(overlay_version.clone()),
@@ -189,39 +332,17 @@ impl Validator {
                 if let Err(translation_errors) =
                     self.validate_translations(&enforced_langs, typed_overlays)
                 {
-                    errors = errors.into_iter().chain(
-                        translation_errors.into_iter().map(|e| {
-                            if let Error::UnexpectedTranslations(lang) = e {
-                                Error::Custom(
-                                    format!("{overlay_type} overlay: translations in {lang:?} language are not enforced")
-                                )
-                            } else if let Error::MissingTranslations(lang) = e {
-                                Error::Custom(
-                                    format!("{overlay_type} overlay: translations in {lang:?} language are missing")
-                                )
-                            } else if let Error::MissingAttributeTranslation(lang, attr_name) = e {
-                                Error::Custom(
-                                    format!("{overlay_type} overlay: for '{attr_name}' attribute missing translations in {lang:?} language")
-                                )
-                            } else {
-                                e
-                            }
-                        })
-                    ).collect();
+                    errors.extend(translation_errors);
                 }
             }
         }
 
-        if errors.is_empty() {
-            Ok(())
-        } else {
-            Err(errors)
-        }
+        errors
     }
 
     fn validate_conditional(
         &self,
-        attr_types: IndexMap<String, NestedAttrType>,
+        attr_types: &IndexMap<String, NestedAttrType>,
         overlay: &overlay::Conditional,
     ) -> Result<(), Vec<Error>> {
         let mut errors: Vec<Error> = vec![];
@@ -229,19 +350,25 @@ impl Validator {
         let conditions = overlay.attribute_conditions.clone();
         let dependencies = overlay.attribute_dependencies.clone();
         let re = regex::Regex::new(r"\$\{(\d+)\}").unwrap();
-        for &attr in overlay.attributes().iter() {
+        'attributes: for &attr in overlay.attributes().iter() {
             let condition = conditions.get(attr).unwrap(); // todo
             let condition_dependencies = dependencies.get(attr).unwrap(); // todo
             if condition_dependencies.contains(attr) {
-                errors.push(Error::Custom(format!(
-                    "Attribute '{attr}' cannot be a dependency of itself"
-                )));
+                errors.push(Error::SelfDependentCondition {
+                    attribute_path: attr.clone(),
+                });
                 continue;
             }
 
             let mut attr_mocks: HashMap<String, String> = HashMap::new();
-            condition_dependencies.iter().for_each(|dep| {
-                let dep_type = attr_types.get(dep).unwrap(); // todo
+            for dep in condition_dependencies {
+                let Some(dep_type) = attr_types.get(dep) else {
+                    errors.push(Error::UnknownConditionDependency {
+                        attribute_path: attr.clone(),
+                        dependency: dep.clone(),
+                    });
+                    continue 'attributes;
+                };
                 let value = match dep_type {
                     NestedAttrType::Null => "null".to_string(),
                     NestedAttrType::Value(base_type) => match base_type {
@@ -260,12 +387,20 @@ impl Validator {
                             AttributeType::Binary => "[test]".to_string(),
                             AttributeType::Boolean => "[true]".to_string(),
                         },
-                        _ => panic!("Invalid or not supported array type"),
+                        _ => {
+                            errors.push(Error::InvalidCondition {
+                                attribute_path: attr.clone(),
+                                message: format!(
+                                    "dependency '{dep}' has invalid or not supported array type"
+                                ),
+                            });
+                            continue 'attributes;
+                        }
                     },
                     NestedAttrType::Reference(ref_value) => ref_value.to_string(),
                 };
                 attr_mocks.insert(dep.to_string(), value);
-            });
+            }
 
             let script = re
                 .replace_all(condition, |caps: &regex::Captures| {
@@ -277,28 +412,19 @@ impl Validator {
                 .to_string();
 
             let mut lua = Lua::new();
-            let thread_result = lua.try_run(|ctx| {
-                let closure = Closure::load(ctx, format!("return {script}").as_bytes())?;
-                let thread = Thread::new(&ctx);
-                thread.start(ctx, closure.into(), ())?;
-                Ok(ctx.state.registry.stash(&ctx, thread))
-            });
-
-            match thread_result {
-                Ok(thread) => {
-                    if let Err(e) = lua.run_thread::<bool>(&thread) {
-                        errors.push(Error::Custom(format!(
-                            "Attribute '{attr}' has invalid condition: {}",
-                            e.source().unwrap()
-                        )));
-                    }
-                }
-                Err(e) => {
-                    errors.push(Error::Custom(format!(
-                        "Attribute '{attr}' has invalid condition: {}",
-                        e.source().unwrap()
-                    )));
-                }
+            let result = lua
+                .try_run(|ctx| {
+                    let closure = Closure::load(ctx, format!("return {script}").as_bytes())?;
+                    let thread = Thread::new(&ctx);
+                    thread.start(ctx, closure.into(), ())?;
+                    Ok(ctx.state.registry.stash(&ctx, thread))
+                })
+                .and_then(|thread| lua.run_thread::<bool>(&thread));
+            if let Err(e) = result {
+                errors.push(Error::InvalidCondition {
+                    attribute_path: attr.clone(),
+                    message: lua_error_message(e),
+                });
             }
         }
 
@@ -315,6 +441,7 @@ impl Validator {
         meta_overlays: Vec<&overlay::Meta>,
     ) -> Result<(), Vec<Error>> {
         let mut errors: Vec<Error> = vec![];
+        let overlay_type = meta_overlays[0].overlay_type().clone();
         let translation_langs: HashSet<_> = meta_overlays
             .iter()
             .map(|o| o.language().unwrap())
@@ -323,13 +450,19 @@ impl Validator {
         let missing_enforcement: HashSet<&_> =
             translation_langs.difference(enforced_langs).collect();
         for m in missing_enforcement {
-            errors.push(Error::UnexpectedTranslations(**m));
+            errors.push(Error::UnexpectedTranslations {
+                overlay_type: overlay_type.clone(),
+                language: **m,
+            });
         }
 
         let missing_translations: HashSet<&_> =
             enforced_langs.difference(&translation_langs).collect();
         for m in missing_translations {
-            errors.push(Error::MissingTranslations(**m));
+            errors.push(Error::MissingTranslations {
+                overlay_type: overlay_type.clone(),
+                language: **m,
+            });
         }
 
         let attributes = meta_overlays
@@ -340,10 +473,11 @@ impl Validator {
         for meta_overlay in meta_overlays {
             attributes.iter().for_each(|attr| {
                 if !meta_overlay.attr_pairs.contains_key(*attr) {
-                    errors.push(Error::MissingMetaTranslation(
-                        *meta_overlay.language().unwrap(),
-                        attr.to_string(),
-                    ));
+                    errors.push(Error::MissingMetaTranslation {
+                        overlay_type: overlay_type.clone(),
+                        language: *meta_overlay.language().unwrap(),
+                        key: attr.to_string(),
+                    });
                 }
             });
         }
@@ -361,17 +495,24 @@ impl Validator {
         overlays: Vec<&DynOverlay>,
     ) -> Result<(), Vec<Error>> {
         let mut errors: Vec<Error> = vec![];
+        let overlay_type = overlays[0].overlay_type().clone();
 
         let overlay_langs: HashSet<_> = overlays.iter().map(|x| x.language().unwrap()).collect();
 
         let missing_enforcement: HashSet<&_> = overlay_langs.difference(enforced_langs).collect();
         for m in missing_enforcement {
-            errors.push(Error::UnexpectedTranslations(**m)); // why we have && here?
+            errors.push(Error::UnexpectedTranslations {
+                overlay_type: overlay_type.clone(),
+                language: **m,
+            });
         }
 
         let missing_translations: HashSet<&_> = enforced_langs.difference(&overlay_langs).collect();
         for m in missing_translations {
-            errors.push(Error::MissingTranslations(**m)); // why we have && here?
+            errors.push(Error::MissingTranslations {
+                overlay_type: overlay_type.clone(),
+                language: **m,
+            });
         }
 
         let all_attributes: HashSet<&String> =
@@ -382,10 +523,11 @@ impl Validator {
             let missing_attr_translation: HashSet<&_> =
                 all_attributes.difference(&attributes).collect();
             for m in missing_attr_translation {
-                errors.push(Error::MissingAttributeTranslation(
-                    *overlay.language().unwrap(),
-                    m.to_string(),
-                ));
+                errors.push(Error::MissingAttributeTranslation {
+                    overlay_type: overlay_type.clone(),
+                    language: *overlay.language().unwrap(),
+                    attribute_path: m.to_string(),
+                });
             }
         }
 
@@ -397,6 +539,14 @@ impl Validator {
     }
 }
 
+/// Lua error message without the `lua error:` or `runtime error:` prefix.
+fn lua_error_message(error: StaticError) -> String {
+    match error {
+        StaticError::Lua(error) => error.to_string(),
+        StaticError::Runtime(error) => error.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -471,6 +621,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn serialize_missing_attribute_translation_error() {
+        let validator = Validator::new().enforce_translations(vec![Language::Eng, Language::Pol]);
+
+        let mut oca = OCABox::new();
+        let attribute = cascade! {
+            Attribute::new("name".to_string());
+            ..set_attribute_type(NestedAttrType::Value(AttributeType::Text));
+            ..set_label(Language::Eng, "Name: ".to_string());
+            ..set_label(Language::Pol, "Imię: ".to_string());
+        };
+        oca.add_attribute(attribute);
+        let attribute = cascade! {
+            Attribute::new("age".to_string());
+            ..set_attribute_type(NestedAttrType::Value(AttributeType::Numeric));
+            ..set_label(Language::Eng, "Age: ".to_string());
+        };
+        oca.add_attribute(attribute);
+
+        let oca_bundle = oca.generate_bundle();
+        let errors = validator.validate(&oca_bundle).unwrap_err();
+        assert_eq!(errors.len(), 1);
+
+        let error = &errors[0];
+        assert_eq!(error.code(), "missing_attribute_translation");
+        assert_eq!(error.attribute_path(), Some("age"));
+        assert_eq!(error.language(), Some(&Language::Pol));
+        assert_eq!(
+            serde_json::to_value(error).unwrap(),
+            serde_json::json!({
+                "code": "missing_attribute_translation",
+                "overlay_type": "spec/overlays/label/1.1",
+                "language": "pol",
+                "attribute_path": "age"
+            })
+        );
+    }
+
     #[test]
     fn validate_oca_with_standards() {
         /*         let validator = Validator::new();
@@ -528,6 +716,16 @@ mod tests {
                 if let Err(errors) = result {
                     println!("{:?}", errors);
                     assert_eq!(errors.len(), 4);
+                    let codes = errors.iter().map(|e| e.code()).collect::<Vec<_>>();
+                    assert_eq!(
+                        codes,
+                        vec![
+                            "malformed_bundle_said",
+                            "malformed_capture_base_said",
+                            "malformed_overlay_said",
+                            "capture_base_mismatch"
+                        ]
+                    );
                 }
             }
             Err(e) => {
@@ -571,4 +769,32 @@ mod tests {
             assert_eq!(errors.len(), 1);
         } */
     }
+
+    #[test]
+    fn validate_oca_with_invalid_condition() {
+        let mut oca = OCABox::new();
+        oca.add_attribute(cascade! {
+            Attribute::new("age".to_string());
+            ..set_attribute_type(NestedAttrType::Value(AttributeType::Numeric));
+        });
+        oca.add_attribute(cascade! {
+            Attribute::new("name".to_string());
+            ..set_attribute_type(NestedAttrType::Value(AttributeType::Text));
+            ..set_condition("${age} >".to_string());
+        });
+
+        let errors = Validator::new()
+            .validate(&oca.generate_bundle())
+            .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        let Error::InvalidCondition {
+            attribute_path,
+            message,
+        } = &errors[0]
+        else {
+            panic!("unexpected error: {:?}", errors[0]);
+        };
+        assert_eq!(attribute_path, "name");
+        assert!(!message.starts_with("lua error") && !message.starts_with("runtime error"));
+    }
 }
@@ -0,0 +1,355 @@
+use super::{Error, Finding, Severity};
+use crate::state::{
+    oca::{overlay, OCABundle},
+    spec_version::SpecVersion,
+};
+use isolang::Language;
+use oca_ast_semantics::ast::OverlayType;
+use serde::{Deserialize, Serialize};
+
+#[derive(thiserror::Error, Debug)]
+pub enum ProfileError {
+    #[error("Invalid YAML profile: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("Invalid JSON profile: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Organization-level validation policy applied on top of the built-in
+/// semantic checks.
+///
+/// Example (YAML):
+///
+/// ```yaml
+/// languages: [en, pl]
+/// attribute_translations:
+///   overlays: [label, information]
+/// meta:
+///   keys: [name, description]
+/// flagged_sensitivity: {}
+/// classification:
+///   severity: warning
+/// max_attributes:
+///   limit: 50
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ValidationProfile {
+    /// Languages required by the translation related rules.
+    pub languages: Vec<Language>,
+    pub attribute_translations: Option<AttributeTranslationsRule>,
+    pub meta: Option<MetaRule>,
+    pub flagged_sensitivity: Option<Rule>,
+    pub classification: Option<Rule>,
+    pub max_attributes: Option<MaxAttributesRule>,
+}
+
+/// Rule without parameters.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rule {
+    pub severity: Severity,
+}
+
+/// Every attribute has to be covered by the listed overlays in every
+/// required language. Entries are required only for attributes with entry
+/// codes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttributeTranslationsRule {
+    pub overlays: Vec<TranslatableOverlay>,
+    pub severity: Severity,
+}
+
+/// META overlay in every required language has to contain the listed keys.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetaRule {
+    pub keys: Vec<String>,
+    pub severity: Severity,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MaxAttributesRule {
+    pub limit: usize,
+    pub severity: Severity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranslatableOverlay {
+    Label,
+    Information,
+    Entry,
+}
+
+impl TranslatableOverlay {
+    fn overlay_type(&self) -> OverlayType {
+        let overlay_version = SpecVersion::CURRENT.to_string();
+        match self {
+            TranslatableOverlay::Label => OverlayType::Label(overlay_version),
+            TranslatableOverlay::Information => OverlayType::Information(overlay_version),
+            TranslatableOverlay::Entry => OverlayType::Entry(overlay_version),
+        }
+    }
+}
+
+impl ValidationProfile {
+    pub fn from_yaml(source: &str) -> Result<Self, ProfileError> {
+        Ok(serde_yaml::from_str(source)?)
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, ProfileError> {
+        Ok(serde_json::from_str(source)?)
+    }
+
+    pub fn apply(&self, oca_bundle: &OCABundle) -> Vec<Finding> {
+        let mut findings = vec![];
+
+        if let Some(rule) = &self.attribute_translations {
+            self.check_attribute_translations(oca_bundle, rule, &mut findings);
+        }
+        if let Some(rule) = &self.meta {
+            self.check_meta(oca_bundle, rule, &mut findings);
+        }
+        if let Some(rule) = &self.flagged_sensitivity {
+            check_flagged_sensitivity(oca_bundle, rule, &mut findings);
+        }
+        if let Some(rule) = &self.classification {
+            if oca_bundle.capture_base.classification.is_empty() {
+                findings.push(Finding::new(rule.severity, Error::MissingClassification));
+            }
+        }
+        if let Some(rule) = &self.max_attributes {
+            let count = oca_bundle.capture_base.attributes.len();
+            if count > rule.limit {
+                findings.push(Finding::new(
+                    rule.severity,
+                    Error::TooManyAttributes {
+                        limit: rule.limit,
+                        count,
+                    },
+                ));
+            }
+        }
+
+        findings
+    }
+
+    fn check_attribute_translations(
+        &self,
+        oca_bundle: &OCABundle,
+        rule: &AttributeTranslationsRule,
+        findings: &mut Vec<Finding>,
+    ) {
+        let entry_code_attributes = oca_bundle
+            .overlays
+            .iter()
+            .filter_map(|x| x.as_any().downcast_ref::<overlay::EntryCode>())
+            .flat_map(|o| o.attribute_entry_codes.keys())
+            .collect::<Vec<_>>();
+
+        for translatable in &rule.overlays {
+            let overlay_type = translatable.overlay_type();
+            for language in &self.languages {
+                let overlay = oca_bundle.overlays.iter().find(|o| {
+                    o.overlay_type().to_string() == overlay_type.to_string()
+                        && o.language() == Some(language)
+                });
+                let translated = overlay.map(|o| o.attributes()).unwrap_or_default();
+
+                for attr_name in oca_bundle.capture_base.attributes.keys() {
+                    if *translatable == TranslatableOverlay::Entry
+                        && !entry_code_attributes.contains(&attr_name)
+                    {
+                        continue;
+                    }
+                    if !translated.contains(&attr_name) {
+                        findings.push(Finding::new(
+                            rule.severity,
+                            Error::MissingAttributeTranslation {
+                                overlay_type: overlay
+                                    .map(|o| o.overlay_type().clone())
+                                    .unwrap_or_else(|| overlay_type.clone()),
+                                language: *language,
+                                attribute_path: attr_name.clone(),
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_meta(&self, oca_bundle: &OCABundle, rule: &MetaRule, findings: &mut Vec<Finding>) {
+        let meta_overlays = oca_bundle
+            .overlays
+            .iter()
+            .filter_map(|x| x.as_any().downcast_ref::<overlay::Meta>())
+            .collect::<Vec<_>>();
+
+        for language in &self.languages {
+            let meta_overlay = meta_overlays.iter().find(|o| o.language == *language);
+            for key in &rule.keys {
+                let present = meta_overlay
+                    .map(|o| o.attr_pairs.get(key).is_some_and(|v| !v.is_empty()))
+                    .unwrap_or(false);
+                if !present {
+                    findings.push(Finding::new(
+                        rule.severity,
+                        Error::MissingMetaTranslation {
+                            overlay_type: OverlayType::Meta(SpecVersion::CURRENT.to_string()),
+                            language: *language,
+                            key: key.clone(),
+                        },
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Sensitivity is looked up in any overlay of `Sensitivity` type, so that
+/// externally provided overlays can satisfy the rule.
+fn check_flagged_sensitivity(oca_bundle: &OCABundle, rule: &Rule, findings: &mut Vec<Finding>) {
+    let sensitive_attributes = oca_bundle
+        .overlays
+        .iter()
+        .filter(|o| matches!(o.overlay_type(), OverlayType::Sensitivity(_)))
+        .flat_map(|o| o.attributes())
+        .collect::<Vec<_>>();
+
+    for attr_name in &oca_bundle.capture_base.flagged_attributes {
+        if !sensitive_attributes.contains(&attr_name) {
+            findings.push(Finding::new(
+                rule.severity,
+                Error::MissingSensitivity {
+                    attribute_path: attr_name.clone(),
+                },
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::validator::Validator;
+    use crate::state::{
+        attribute::{Attribute, AttributeType},
+        oca::overlay::information::Information,
+        oca::overlay::label::Labels,
+        oca::overlay::meta::Metas,
+        oca::OCABox,
+    };
+    use oca_ast_semantics::ast::NestedAttrType;
+
+    fn oca_bundle() -> OCABundle {
+        let mut oca = cascade! {
+            OCABox::new();
+            ..add_meta(Language::Eng, "name".to_string(), "Driving Licence".to_string());
+        };
+
+        let attribute = cascade! {
+            Attribute::new("name".to_string());
+            ..set_attribute_type(NestedAttrType::Value(AttributeType::Text));
+            ..set_flagged();
+            ..set_label(Language::Eng, "Name: ".to_string());
+            ..set_information(Language::Eng, "Full name".to_string());
+        };
+        oca.add_attribute(attribute);
+
+        let attribute = cascade! {
+            Attribute::new("age".to_string());
+            ..set_attribute_type(NestedAttrType::Value(AttributeType::Numeric));
+            ..set_label(Language::Eng, "Age: ".to_string());
+        };
+        oca.add_attribute(attribute);
+
+        oca.generate_bundle()
+    }
+
+    #[test]
+    fn load_profile_from_yaml_and_json() {
+        let yaml = r#"
+languages: [en]
+attribute_translations:
+  overlays: [label, information]
+meta:
+  keys: [name, description]
+flagged_sensitivity: {}
+classification:
+  severity: warning
+max_attributes:
+  limit: 1
+  severity: warning
+"#;
+        let profile = ValidationProfile::from_yaml(yaml).unwrap();
+        assert_eq!(profile.languages, vec![Language::Eng]);
+        assert_eq!(
+            profile.flagged_sensitivity.as_ref().unwrap().severity,
+            Severity::Error
+        );
+
+        let json = serde_json::to_string(&profile).unwrap();
+        assert_eq!(ValidationProfile::from_json(&json).unwrap(), profile);
+
+        assert!(ValidationProfile::from_yaml("unknown_rule: {}").is_err());
+    }
+
+    #[test]
+    fn apply_profile_to_bundle() {
+        let profile = ValidationProfile::from_yaml(
+            r#"
+languages: [en]
+attribute_translations:
+  overlays: [label, information]
+meta:
+  keys: [name, description]
+flagged_sensitivity: {}
+classification:
+  severity: warning
+max_attributes:
+  limit: 1
+  severity: warning
+"#,
+        )
+        .unwrap();
+
+        let findings = Validator::new().with_profile(profile).check(&oca_bundle());
+        let summary = findings
+            .iter()
+            .map(|f| (f.severity, f.error.code(), f.error.attribute_path()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    Severity::Error,
+                    "missing_attribute_translation",
+                    Some("age")
+                ),
+                (Severity::Error, "missing_meta_translation", None),
+                (Severity::Error, "missing_sensitivity", Some("name")),
+                (Severity::Warning, "missing_classification", None),
+                (Severity::Warning, "too_many_attributes", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn warnings_do_not_invalidate_bundle() {
+        let profile = ValidationProfile::from_yaml(
+            r#"
+classification:
+  severity: warning
+"#,
+        )
+        .unwrap();
+
+        let validator = Validator::new().with_profile(profile);
+        assert_eq!(validator.check(&oca_bundle()).len(), 1);
+        assert!(validator.validate(&oca_bundle()).is_ok());
+    }
+}
@@ -0,0 +1,372 @@
+//! Localized read access to OCA Bundles.
+//!
+//! `BundleView` gathers values of all overlays per attribute, so they don't
+//! have to be looked up in individual overlays. Localized values are looked
+//! up in the requested language first and then in the fallback languages,
+//! in order.
+use crate::state::{
+    attribute::Attribute,
+    entries::EntriesElement,
+    entry_codes::EntryCodes,
+    oca::{overlay, overlay::Overlay, OCABox, OCABundle},
+};
+use isolang::Language;
+use oca_ast_semantics::ast::NestedAttrType;
+use std::collections::HashMap;
+
+pub struct BundleView {
+    said: Option<String>,
+    oca_box: OCABox,
+    /// Category labels by language, keyed as in the Label overlay.
+    category_labels: HashMap<Language, HashMap<String, String>>,
+    fallback: Vec<Language>,
+}
+
+impl BundleView {
+    pub fn new(bundle: &OCABundle) -> Self {
+        let mut category_labels: HashMap<Language, HashMap<String, String>> = HashMap::new();
+        for label in bundle
+            .overlays
+            .iter()
+            .filter_map(|o| o.as_any().downcast_ref::<overlay::Label>())
+        {
+            if let Some(lang) = label.language() {
+                category_labels
+                    .entry(*lang)
+                    .or_default()
+                    .extend(label.category_labels.clone());
+            }
+        }
+
+        Self {
+            said: bundle.said.as_ref().map(ToString::to_string),
+            oca_box: OCABox::from(bundle.clone()),
+            category_labels,
+            fallback: vec![],
+        }
+    }
+
+    /// Languages tried, in order, when a value is missing in the requested
+    /// one.
+    pub fn with_fallback(mut self, languages: Vec<Language>) -> Self {
+        self.fallback = languages;
+        self
+    }
+
+    /// Languages of all localized values, sorted by their codes.
+    pub fn languages(&self) -> Vec<Language> {
+        let mut languages: Vec<Language> = self
+            .oca_box
+            .meta
+            .iter()
+            .flat_map(|meta| meta.keys())
+            .chain(self.category_labels.keys())
+            .chain(self.oca_box.attributes.values().flat_map(|attribute| {
+                [
+                    attribute.labels.as_ref(),
+                    attribute.informations.as_ref(),
+                    attribute.category_labels.as_ref(),
+                ]
+                .into_iter()
+                .flatten()
+                .flat_map(|values| values.keys())
+                .chain(attribute.entries.iter().flat_map(|entries| entries.keys()))
+            }))
+            .copied()
+            .collect();
+        languages.sort_by_key(|lang| lang.to_639_3());
+        languages.dedup();
+        languages
+    }
+
+    pub fn said(&self) -> Option<&str> {
+        self.said.as_deref()
+    }
+
+    pub fn classification(&self) -> Option<&str> {
+        self.oca_box
+            .classification
+            .as_deref()
+            .filter(|classification| !classification.is_empty())
+    }
+
+    /// Meta property, e.g. `name` or `description`.
+    pub fn meta(&self, lang: Language, key: &str) -> Option<&str> {
+        self.localized(lang, |lang| {
+            self.oca_box.meta.as_ref()?.get(&lang)?.get(key)
+        })
+        .map(String::as_str)
+    }
+
+    /// All meta properties in the first language which has any.
+    pub fn metas(&self, lang: Language) -> Option<&HashMap<String, String>> {
+        self.localized(lang, |lang| self.oca_box.meta.as_ref()?.get(&lang))
+    }
+
+    /// Meta properties in `lang`, or in the first language (by code) when
+    /// `lang` is `None`.
+    pub fn metas_or_first(&self, lang: Option<Language>) -> Option<&HashMap<String, String>> {
+        match lang {
+            Some(lang) => self.metas(lang),
+            None => {
+                let meta = self.oca_box.meta.as_ref()?;
+                let mut languages: Vec<_> = meta.keys().collect();
+                languages.sort();
+                languages.first().and_then(|lang| meta.get(lang))
+            }
+        }
+    }
+
+    /// Category labels of the Label overlay in the first language which has
+    /// any.
+    pub fn categories(&self, lang: Language) -> Option<&HashMap<String, String>> {
+        self.localized(lang, |lang| {
+            self.category_labels
+                .get(&lang)
+                .filter(|labels| !labels.is_empty())
+        })
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<AttributeView<'_>> {
+        self.oca_box
+            .attributes
+            .get(name)
+            .map(|attribute| AttributeView {
+                view: self,
+                attribute,
+            })
+    }
+
+    /// Views of all attributes, sorted by name.
+    pub fn attributes(&self) -> Vec<AttributeView<'_>> {
+        let mut attributes: Vec<AttributeView> = self
+            .oca_box
+            .attributes
+            .values()
+            .map(|attribute| AttributeView {
+                view: self,
+                attribute,
+            })
+            .collect();
+        attributes.sort_by(|a, b| a.name().cmp(b.name()));
+        attributes
+    }
+
+    /// First value found in `lang` or in the fallback languages.
+    fn localized<'a, T: 'a>(
+        &self,
+        lang: Language,
+        get: impl Fn(Language) -> Option<&'a T>,
+    ) -> Option<&'a T> {
+        std::iter::once(lang)
+            .chain(self.fallback.iter().copied())
+            .find_map(get)
+    }
+}
+
+pub struct AttributeView<'a> {
+    view: &'a BundleView,
+    attribute: &'a Attribute,
+}
+
+impl<'a> AttributeView<'a> {
+    pub fn name(&self) -> &'a str {
+        &self.attribute.name
+    }
+
+    pub fn attribute_type(&self) -> Option<&'a NestedAttrType> {
+        self.attribute.attribute_type.as_ref()
+    }
+
+    pub fn is_flagged(&self) -> bool {
+        self.attribute.is_flagged
+    }
+
+    pub fn is_mandatory(&self) -> bool {
+        self.attribute.conformance.as_deref() == Some("M")
+    }
+
+    pub fn conformance(&self) -> Option<&'a str> {
+        self.attribute.conformance.as_deref()
+    }
+
+    pub fn cardinality(&self) -> Option<&'a str> {
+        self.attribute.cardinality.as_deref()
+    }
+
+    pub fn unit(&self) -> Option<&'a str> {
+        self.attribute.unit.as_deref()
+    }
+
+    #[cfg(feature = "format_overlay")]
+    pub fn format(&self) -> Option<&'a str> {
+        self.attribute.format.as_deref()
+    }
+
+    pub fn entry_codes(&self) -> Option<&'a EntryCodes> {
+        self.attribute.entry_codes.as_ref()
+    }
+
+    pub fn label(&self, lang: Language) -> Option<&'a str> {
+        let labels = self.attribute.labels.as_ref()?;
+        self.view
+            .localized(lang, |lang| labels.get(&lang))
+            .map(String::as_str)
+    }
+
+    pub fn information(&self, lang: Language) -> Option<&'a str> {
+        let informations = self.attribute.informations.as_ref()?;
+        self.view
+            .localized(lang, |lang| informations.get(&lang))
+            .map(String::as_str)
+    }
+
+    pub fn category_label(&self, lang: Language) -> Option<&'a str> {
+        let category_labels = self.attribute.category_labels.as_ref()?;
+        self.view
+            .localized(lang, |lang| category_labels.get(&lang))
+            .map(String::as_str)
+    }
+
+    /// Labels of entry codes in the first language which has any. Entries
+    /// referenced by SAID aren't resolved.
+    pub fn entries(&self, lang: Language) -> Option<&'a HashMap<String, String>> {
+        let entries = self.attribute.entries.as_ref()?;
+        self.view.localized(lang, |lang| match entries.get(&lang)? {
+            EntriesElement::Object(entries) => Some(entries),
+            EntriesElement::Sai(_) => None,
+        })
+    }
+
+    /// Label of single entry code, looked up in fallback languages
+    /// separately for each code.
+    pub fn entry(&self, lang: Language, code: &str) -> Option<&'a str> {
+        let entries = self.attribute.entries.as_ref()?;
+        self.view
+            .localized(lang, |lang| match entries.get(&lang)? {
+                EntriesElement::Object(entries) => entries.get(code),
+                EntriesElement::Sai(_) => None,
+            })
+            .map(String::as_str)
+    }
+
+    /// Condition with dependencies referenced by name, e.g. `${age} < 18`.
+    pub fn condition(&self) -> Option<String> {
+        self.attribute.named_condition()
+    }
+
+    /// Attributes the condition depends on.
+    pub fn dependencies(&self) -> &'a [String] {
+        self.attribute.dependencies.as_deref().unwrap_or_default()
+    }
+
+    /// Underlying attribute with values of all overlays.
+    pub fn attribute(&self) -> &'a Attribute {
+        self.attribute
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{
+        attribute::AttributeType,
+        oca::overlay::{
+            conditional::Conditionals, conformance::Conformances, entry::Entries,
+            entry_code::EntryCodes as _, information::Information, label::Labels, meta::Metas,
+            unit::Units,
+        },
+    };
+
+    fn bundle() -> OCABundle {
+        let mut oca = OCABox::new();
+        oca.add_meta(Language::Eng, "name".to_string(), "Person".to_string());
+        oca.add_meta(Language::Pol, "name".to_string(), "Osoba".to_string());
+        oca.add_meta(
+            Language::Eng,
+            "description".to_string(),
+            "Person data".to_string(),
+        );
+
+        let mut age = Attribute::new("age".to_string());
+        age.set_attribute_type(NestedAttrType::Value(AttributeType::Numeric));
+        age.set_label(Language::Eng, "Age".to_string());
+        age.set_label(Language::Pol, "Wiek".to_string());
+        age.set_information(Language::Eng, "Age in years".to_string());
+        age.set_unit("year".to_string());
+        age.set_conformance("M".to_string());
+        oca.add_attribute(age);
+
+        let mut sex = Attribute::new("sex".to_string());
+        sex.set_attribute_type(NestedAttrType::Value(AttributeType::Text));
+        sex.set_entry_codes(EntryCodes::Array(vec!["m".to_string(), "f".to_string()]));
+        sex.set_entry(
+            Language::Eng,
+            EntriesElement::Object(HashMap::from([
+                ("m".to_string(), "Male".to_string()),
+                ("f".to_string(), "Female".to_string()),
+            ])),
+        );
+        sex.set_entry(
+            Language::Pol,
+            EntriesElement::Object(HashMap::from([("m".to_string(), "Mężczyzna".to_string())])),
+        );
+        sex.set_condition("${age} > 0".to_string());
+        oca.add_attribute(sex);
+
+        oca.generate_bundle()
+    }
+
+    #[test]
+    fn localized_values_with_fallback() {
+        let view = BundleView::new(&bundle()).with_fallback(vec![Language::Eng]);
+
+        assert_eq!(view.languages(), vec![Language::Eng, Language::Pol]);
+        assert_eq!(view.meta(Language::Pol, "name"), Some("Osoba"));
+        assert_eq!(view.meta(Language::Pol, "description"), Some("Person data"));
+        assert_eq!(view.meta(Language::Deu, "name"), Some("Person"));
+
+        let age = view.attribute("age").unwrap();
+        assert_eq!(age.label(Language::Pol), Some("Wiek"));
+        assert_eq!(age.information(Language::Pol), Some("Age in years"));
+        assert_eq!(age.unit(), Some("year"));
+        assert!(age.is_mandatory());
+
+        let sex = view.attribute("sex").unwrap();
+        assert_eq!(sex.entry(Language::Pol, "m"), Some("Mężczyzna"));
+        assert_eq!(sex.entry(Language::Pol, "f"), Some("Female"));
+        assert_eq!(sex.entries(Language::Pol).unwrap().len(), 1);
+        assert_eq!(sex.condition().as_deref(), Some("${age} > 0"));
+        assert_eq!(sex.dependencies(), ["age".to_string()]);
+
+        assert_eq!(
+            view.attributes()
+                .iter()
+                .map(|attribute| attribute.name())
+                .collect::<Vec<_>>(),
+            vec!["age", "sex"]
+        );
+    }
+
+    #[test]
+    fn first_language_metas() {
+        let view = BundleView::new(&bundle());
+        assert_eq!(
+            view.metas_or_first(None).unwrap()["description"],
+            "Person data"
+        );
+        assert_eq!(
+            view.metas_or_first(Some(Language::Pol)).unwrap()["name"],
+            "Osoba"
+        );
+    }
+
+    #[test]
+    fn no_fallback() {
+        let view = BundleView::new(&bundle());
+
+        assert_eq!(view.meta(Language::Pol, "description"), None);
+        assert_eq!(view.attribute("age").unwrap().label(Language::Deu), None);
+        assert!(view.attribute("unknown").is_none());
+    }
+}
@@ -144,6 +144,21 @@ impl AddInstruction {
                         helpers::extract_content(object),
                     ));
                 }
+                Rule::custom_overlay => {
+                    let mut name = String::new();
+                    let mut version = overlay_version;
+                    for part in object.clone().into_inner() {
+                        match part.as_rule() {
+                            Rule::custom_overlay_name => name = part.as_str().to_string(),
+                            Rule::custom_overlay_version => version = part.as_str().to_string(),
+                            _ => (),
+                        }
+                    }
+                    object_kind = Some(ObjectKind::Overlay(
+                        OverlayType::Custom { name, version },
+                        helpers::extract_content(object),
+                    ));
+                }
                 Rule::flagged_attrs => {
                     object_kind = Some(ObjectKind::CaptureBase(CaptureContent {
                         properties: None,
@@ -212,14 +227,14 @@ mod tests {
                             assert_eq!(instruction.kind, CommandType::Add);
                             match instruction.object_kind {
                                 ObjectKind::CaptureBase(content) => {
-                                    if content.attributes.is_some() {
-                                        assert!(!content.attributes.unwrap().is_empty());
+                                    if let Some(attributes) = content.attributes {
+                                        assert!(!attributes.is_empty());
                                     }
-                                    if content.properties.is_some() {
-                                        assert!(!content.properties.unwrap().is_empty());
+                                    if let Some(properties) = content.properties {
+                                        assert!(!properties.is_empty());
                                     }
-                                    if content.flagged_attributes.is_some() {
-                                        assert!(!content.flagged_attributes.unwrap().is_empty());
+                                    if let Some(flagged_attributes) = content.flagged_attributes {
+                                        assert!(!flagged_attributes.is_empty());
                                     }
                                 }
                                 _ => {
@@ -130,7 +130,7 @@ pub fn parse_from_string(unparsed_file: String) -> Result<OCAAst, ParseError> {
 fn format_reference(ref_value: RefValue) -> String {
     match ref_value {
         RefValue::Said(said) => format!("refs:{}", said),
-        _ => panic!("Unsupported reference type: {:?}", ref_value),
+        RefValue::Name(name) => format!("refn:{}", name),
     }
 }
 
@@ -159,6 +159,12 @@ fn oca_file_format(nested: NestedAttrType) -> String {
 pub fn generate_from_ast(ast: &OCAAst) -> String {
     let mut ocafile = String::new();
 
+    let mut meta: Vec<_> = ast.meta.iter().collect();
+    meta.sort();
+    meta.iter().for_each(|(key, value)| {
+        ocafile.push_str(format!("-- {}={}\n", key, value).as_str());
+    });
+
     ast.commands.iter().for_each(|command| {
         let mut line = String::new();
 
@@ -185,6 +191,12 @@ pub fn generate_from_ast(ast: &OCAAst) -> String {
                                 }
                             }
                         }
+                        if let Some(flagged_attributes) = &content.flagged_attributes {
+                            line.push_str("FLAGGED_ATTRIBUTES");
+                            for attr_name in flagged_attributes {
+                                line.push_str(&format!(" {}", attr_name));
+                            }
+                        }
                     }
                     ast::ObjectKind::Overlay(o_type, _) => match o_type {
                         ast::OverlayType::Meta(_) => {
@@ -198,7 +210,7 @@ pub fn generate_from_ast(ast: &OCAAst) -> String {
                                         line.push_str(format!("{} ", lang).as_str());
                                     }
                                     if !properties.is_empty() {
-                                        line.push_str("PROPS ");
+                                        line.push_str("PROPS");
                                         properties.iter().for_each(|(key, value)| {
                                             if let ast::NestedValue::Value(value) = value {
                                                 line.push_str(
@@ -230,7 +242,7 @@ pub fn generate_from_ast(ast: &OCAAst) -> String {
                             if let Some(content) = command.object_kind.overlay_content() {
                                 if let Some(ref properties) = content.properties {
                                     if !properties.is_empty() {
-                                        line.push_str("PROPS ");
+                                        line.push_str("PROPS");
                                         properties.iter().for_each(|(key, value)| {
                                             if let ast::NestedValue::Value(value) = value {
                                                 line.push_str(
@@ -301,7 +313,7 @@ pub fn generate_from_ast(ast: &OCAAst) -> String {
                                         line.push_str(format!("{} ", lang).as_str());
                                     }
                                     if !properties.is_empty() {
-                                        line.push_str("PROPS ");
+                                        line.push_str("PROPS");
                                         properties.iter().for_each(|(key, value)| {
                                             if let ast::NestedValue::Value(value) = value {
                                                 line.push_str(
@@ -311,7 +323,7 @@ pub fn generate_from_ast(ast: &OCAAst) -> String {
                                         });
                                     }
                                     if let Some(ref attributes) = content.attributes {
-                                        line.push_str("ATTRS ");
+                                        line.push_str("ATTRS");
                                         attributes.iter().for_each(|(key, value)| {
                                             // TODO there is no need for NestedValue here
                                             if let ast::NestedValue::Object(values) = value {
@@ -333,7 +345,7 @@ pub fn generate_from_ast(ast: &OCAAst) -> String {
                                                     .collect::<Vec<String>>()
                                                     .join(", ");
                                                 line.push_str(
-                                                    format!("{}={{{}}}", key, codes).as_str(),
+                                                    format!(" {}={{{}}}", key, codes).as_str(),
                                                 );
                                             } else if let ast::NestedValue::Value(value) = value {
                                                 line.push_str(
@@ -350,7 +362,7 @@ pub fn generate_from_ast(ast: &OCAAst) -> String {
                             if let Some(content) = command.object_kind.overlay_content() {
                                 if let Some(ref properties) = content.properties {
                                     if !properties.is_empty() {
-                                        line.push_str("PROPS ");
+                                        line.push_str("PROPS");
                                         properties.iter().for_each(|(key, value)| {
                                             if let ast::NestedValue::Value(value) = value {
                                                 line.push_str(
@@ -447,6 +459,34 @@ pub fn generate_from_ast(ast: &OCAAst) -> String {
                                 }
                             };
                         }
+                        ast::OverlayType::Custom { name, version } => {
+                            line.push_str(format!("OVERLAY {}", name).as_str());
+                            if version != "1.1" {
+                                line.push_str(format!("/{}", version).as_str());
+                            }
+                            if let Some(content) = command.object_kind.overlay_content() {
+                                let mut properties = content.properties.clone().unwrap_or_default();
+                                if let Some(ast::NestedValue::Value(lang)) =
+                                    properties.shift_remove("lang")
+                                {
+                                    line.push_str(format!(" {}", lang).as_str());
+                                }
+                                let attributes = content.attributes.clone().unwrap_or_default();
+                                for (keyword, values) in [("PROPS", properties), ("ATTRS", attributes)] {
+                                    if values.is_empty() {
+                                        continue;
+                                    }
+                                    line.push_str(format!(" {}", keyword).as_str());
+                                    values.iter().for_each(|(key, value)| {
+                                        if let ast::NestedValue::Value(value) = value {
+                                            line.push_str(
+                                                format!(" {}=\"{}\"", key, value).as_str(),
+                                            );
+                                        }
+                                    });
+                                }
+                            };
+                        }
                         _ => {
                             line.push_str(
                                 format!("{} ", o_type.to_string().to_case(Case::UpperSnake))
@@ -462,7 +502,7 @@ pub fn generate_from_ast(ast: &OCAAst) -> String {
                                         line.push_str(format!("{} ", lang).as_str());
                                     }
                                     if !properties.is_empty() {
-                                        line.push_str("PROPS ");
+                                        line.push_str("PROPS");
                                         properties.iter().for_each(|(key, value)| {
                                             if let ast::NestedValue::Value(value) = value {
                                                 line.push_str(
@@ -512,6 +552,12 @@ pub fn generate_from_ast(ast: &OCAAst) -> String {
             },
             ast::CommandType::From => {
                 line.push_str("FROM ");
+                if let ast::ObjectKind::OCABundle(ast::BundleContent {
+                    said: ast::ReferenceAttrType::Reference(RefValue::Said(said)),
+                }) = &command.object_kind
+                {
+                    line.push_str(&said.to_string());
+                }
             }
             ast::CommandType::Modify => todo!(),
         }
@@ -650,6 +696,55 @@ ADD ATTR_FRAMING \
         );
     }
 
+    #[test]
+    fn test_custom_overlay_from_ast_to_ocafile() {
+        let unparsed_file = r#"ADD ATTRIBUTE name=Text
+ADD OVERLAY consent en PROPS purpose="Research" ATTRS name="required"
+ADD OVERLAY consent/2.0 ATTRS name="optional"
+ADD OVERLAY retention PROPS period="P1Y"
+"#;
+        let oca_ast = parse_from_string(unparsed_file.to_string()).unwrap();
+        assert_eq!(
+            oca_ast.commands[2].object_kind,
+            ast::ObjectKind::Overlay(
+                ast::OverlayType::Custom {
+                    name: "consent".to_string(),
+                    version: "2.0".to_string()
+                },
+                ast::Content {
+                    properties: Some(indexmap::IndexMap::new()),
+                    attributes: Some(indexmap::IndexMap::from([(
+                        "name".to_string(),
+                        ast::NestedValue::Value("optional".to_string())
+                    )])),
+                }
+            )
+        );
+
+        let ocafile = generate_from_ast(&oca_ast);
+        assert_eq!(
+            ocafile, unparsed_file,
+            "left:\n{} \n right:\n {}",
+            ocafile, unparsed_file
+        );
+    }
+
+    #[test]
+    fn test_meta_from_ast_to_ocafile() {
+        let unparsed_file = r#"-- name=address
+-- version=0.0.1
+ADD ATTRIBUTE street=Text
+"#;
+        let oca_ast = parse_from_string(unparsed_file.to_string()).unwrap();
+
+        let ocafile = generate_from_ast(&oca_ast);
+        assert_eq!(
+            ocafile, unparsed_file,
+            "left:\n{} \n right:\n {}",
+            ocafile, unparsed_file
+        );
+    }
+
     #[test]
     fn test_attributes_from_ast_to_ocafile() {
         let unparsed_file = r#"ADD ATTRIBUTE name=Text age=Numeric
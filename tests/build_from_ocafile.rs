@@ -4,9 +4,264 @@ mod test {
         data_storage::{DataStorage, InMemoryDataStorage},
         facade::{build::Error, build::ValidationError},
         repositories::SQLiteConfig,
-        EncodeBundle, Facade, HashFunctionCode, SerializationFormats,
+        EncodeBundle, Facade, HashFunctionCode, SaidOptions, SerializationFormats,
     };
 
+    #[test]
+    fn build_with_said_options() -> Result<(), Error> {
+        let db = InMemoryDataStorage::new();
+        let db_cache = InMemoryDataStorage::new();
+        let cache_storage_config = SQLiteConfig::build().unwrap();
+        let ocafile = r#"
+ADD ATTRIBUTE name=Text age=Numeric
+ADD LABEL en ATTRS name="Name" age="Age"
+"#
+        .to_string();
+        let said_options = SaidOptions::new(HashFunctionCode::SHA3_256, SerializationFormats::JSON);
+        let mut facade = Facade::new(Box::new(db), Box::new(db_cache), cache_storage_config)
+            .with_said_options(said_options.clone());
+
+        let result = facade.build_from_ocafile(ocafile)?;
+        let said = result.said.clone().unwrap();
+        assert_eq!(
+            HashFunctionCode::from(said.derivation.clone()),
+            HashFunctionCode::SHA3_256
+        );
+        assert!(result.verify_with(&said_options).is_valid());
+
+        let stored = facade.get_oca_bundle(said.clone(), false).unwrap();
+        assert_eq!(stored.bundle.said, Some(said));
+        assert!(stored.bundle.verify().is_valid());
+
+        Ok(())
+    }
+
+    #[test]
+    fn build_with_cbor_said_options() -> Result<(), Error> {
+        let db = InMemoryDataStorage::new();
+        let db_cache = InMemoryDataStorage::new();
+        let cache_storage_config = SQLiteConfig::build().unwrap();
+        let said_options =
+            SaidOptions::new(HashFunctionCode::Blake3_256, SerializationFormats::CBOR);
+        let mut facade = Facade::new(Box::new(db), Box::new(db_cache), cache_storage_config)
+            .with_said_options(said_options.clone());
+
+        let base = facade.build_from_ocafile(
+            r#"
+ADD ATTRIBUTE name=Text age=Numeric
+ADD LABEL en ATTRS name="Name" age="Age"
+"#
+            .to_string(),
+        )?;
+        assert!(base.verify_with(&said_options).is_valid());
+        assert!(!base.verify().is_valid());
+        let base_said = base.said.clone().unwrap();
+
+        let stored = facade.get_oca_bundle(base_said.clone(), false).unwrap();
+        assert_eq!(stored.bundle.said, Some(base_said.clone()));
+        assert!(stored.bundle.verify_with(&said_options).is_valid());
+
+        let ours = facade.build_from_ocafile(format!(
+            "FROM {base_said}\nADD LABEL pl ATTRS name=\"Imię\"\n"
+        ))?;
+        let theirs = facade.build_from_ocafile(format!(
+            "FROM {base_said}\nADD LABEL de ATTRS name=\"Name\"\n"
+        ))?;
+        assert!(ours.verify_with(&said_options).is_valid());
+
+        let patch = facade
+            .get_oca_bundle_patch(base_said, ours.said.clone().unwrap())
+            .unwrap();
+        assert_eq!(facade.build_from_ocafile(patch)?.said, ours.said);
+
+        let merge = facade
+            .merge_oca_bundles(ours.said.unwrap(), theirs.said.unwrap())
+            .unwrap();
+        assert!(merge.is_clean());
+        assert!(merge.oca_bundle.verify_with(&said_options).is_valid());
+
+        Ok(())
+    }
+
+    #[test]
+    fn build_from_generated_patch() -> Result<(), Error> {
+        let db = InMemoryDataStorage::new();
+        let db_cache = InMemoryDataStorage::new();
+        let cache_storage_config = SQLiteConfig::build().unwrap();
+        let mut facade = Facade::new(Box::new(db), Box::new(db_cache), cache_storage_config);
+
+        let from = facade.build_from_ocafile(
+            r#"
+ADD ATTRIBUTE name=Text age=Numeric radio=Text email=Text
+ADD META en PROPS name="Person"
+ADD LABEL en ATTRS name="Name" age="Age" radio="Radio" email="Email"
+ADD UNIT ATTRS age="year"
+ADD ENTRY_CODE ATTRS radio=["o1", "o2"]
+ADD ENTRY en ATTRS radio={"o1": "Option 1", "o2": "Option 2"}
+"#
+            .to_string(),
+        )?;
+        let to = facade.build_from_ocafile(
+            r#"
+ADD ATTRIBUTE name=Text age=Numeric radio=Text phone=Text
+ADD CLASSIFICATION GICS:35102020
+ADD META en PROPS name="Person" description="Person data"
+ADD LABEL en ATTRS name="Full name" age="Age" radio="Radio" phone="Phone"
+ADD LABEL pl ATTRS name="Imię i nazwisko"
+ADD UNIT ATTRS age="month"
+ADD CONFORMANCE ATTRS name="M"
+ADD ENTRY_CODE ATTRS radio=["o1", "o2", "o3"]
+ADD ENTRY en ATTRS radio={"o1": "Option 1", "o2": "Option 2", "o3": "Option 3"}
+ADD FLAGGED_ATTRIBUTES name
+"#
+            .to_string(),
+        )?;
+        assert_eq!(to.capture_base.flagged_attributes, vec!["name".to_string()]);
+
+        let patch = facade
+            .get_oca_bundle_patch(from.said.clone().unwrap(), to.said.clone().unwrap())
+            .unwrap();
+        assert!(patch.starts_with(&format!("FROM {}\n", from.said.unwrap())));
+        assert!(patch.contains("REMOVE ATTRIBUTE age email radio\n"));
+
+        let result = facade.build_from_ocafile(patch)?;
+        assert_eq!(result.said, to.said);
+
+        Ok(())
+    }
+
+    #[test]
+    fn merge_forked_bundles() -> Result<(), Error> {
+        let db = InMemoryDataStorage::new();
+        let db_cache = InMemoryDataStorage::new();
+        let cache_storage_config = SQLiteConfig::build().unwrap();
+        let mut facade = Facade::new(Box::new(db), Box::new(db_cache), cache_storage_config);
+
+        let base = facade.build_from_ocafile(
+            r#"
+ADD ATTRIBUTE name=Text age=Numeric
+ADD LABEL en ATTRS name="Name" age="Age"
+"#
+            .to_string(),
+        )?;
+        let base_said = base.said.unwrap();
+        let ours = facade.build_from_ocafile(format!(
+            "FROM {base_said}\nADD LABEL pl ATTRS name=\"Imię\" age=\"Wiek\"\n"
+        ))?;
+        let theirs = facade.build_from_ocafile(format!(
+            "FROM {base_said}\nADD LABEL de ATTRS name=\"Name\"\nADD LABEL en ATTRS age=\"Years\"\n"
+        ))?;
+
+        let (ours, theirs) = (ours.said.unwrap(), theirs.said.unwrap());
+        assert_eq!(
+            facade
+                .find_common_ancestor(ours.clone(), theirs.clone())
+                .unwrap(),
+            Some(base_said)
+        );
+
+        let merge = facade.merge_oca_bundles(ours.clone(), theirs).unwrap();
+        assert!(merge.is_clean());
+
+        let expected = facade.build_from_ocafile(
+            r#"
+ADD ATTRIBUTE name=Text age=Numeric
+ADD LABEL en ATTRS name="Name" age="Years"
+ADD LABEL pl ATTRS name="Imię" age="Wiek"
+ADD LABEL de ATTRS name="Name"
+"#
+            .to_string(),
+        )?;
+        assert_eq!(merge.oca_bundle.said, expected.said);
+
+        let ocafile = oca_file_semantics::ocafile::generate_from_ast(&merge.oca_ast);
+        assert!(ocafile.starts_with(&format!("FROM {ours}")));
+        let result = facade.build_from_ocafile(ocafile)?;
+        assert_eq!(result.said, expected.said);
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_relations_of_custom_overlay() -> Result<(), Error> {
+        use oca_bundle_semantics::state::oca::{OCABox, OCABundle};
+
+        let db = InMemoryDataStorage::new();
+        let db_cache = InMemoryDataStorage::new();
+        let cache_storage_config = SQLiteConfig::build().unwrap();
+        let mut facade = Facade::new(Box::new(db), Box::new(db_cache), cache_storage_config);
+
+        let mut bundle =
+            oca_rs::facade::build::build_from_ocafile("ADD ATTRIBUTE name=Text\n".to_string())?;
+        let mut value = serde_json::to_value(&bundle).unwrap();
+        let capture_base = value["capture_base"]["d"].clone();
+        value["overlays"]["community_consent"] = serde_json::json!({
+            "d": "",
+            "capture_base": capture_base,
+            "type": "spec/overlays/community_consent/1.0",
+            "purpose": "research"
+        });
+        bundle =
+            OCABox::from(serde_json::from_value::<OCABundle>(value).unwrap()).generate_bundle();
+        let said = bundle.said.clone().unwrap().to_string();
+
+        facade.add_relations(bundle).unwrap();
+        let relationship = facade.explore(said).unwrap();
+        assert!(relationship.relations.iter().any(|object| {
+            serde_json::to_value(object).unwrap()["metadata"]["kind"]["object_kind"]
+                == "community_consent"
+        }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn build_from_imported_json_schema() -> Result<(), Error> {
+        let db = InMemoryDataStorage::new();
+        let db_cache = InMemoryDataStorage::new();
+        let cache_storage_config = SQLiteConfig::build().unwrap();
+        let mut facade = Facade::new(Box::new(db), Box::new(db_cache), cache_storage_config);
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "title": "Name"},
+                "address": {
+                    "type": "object",
+                    "properties": {"street": {"type": "string"}},
+                    "required": ["street"]
+                }
+            },
+            "required": ["name"]
+        });
+
+        let import =
+            oca_interop::json_schema::from_json_schema(&schema, "person", isolang::Language::Eng)
+                .unwrap();
+        assert!(import.warnings.is_empty());
+        let mut result = None;
+        for bundle in &import.bundles {
+            result = Some(facade.build_from_ocafile(bundle.ocafile.clone())?);
+        }
+        let said = result.unwrap().said.unwrap();
+
+        let with_deps = facade.get_oca_bundle(said, true).unwrap();
+        let exported = oca_interop::json_schema::to_json_schema(
+            &with_deps.bundle,
+            &with_deps.dependencies,
+            Some(isolang::Language::Eng),
+        )
+        .unwrap();
+        assert_eq!(exported["properties"]["name"]["title"], "Name");
+        assert_eq!(exported["required"], serde_json::json!(["name"]));
+        let address_ref = exported["properties"]["address"]["$ref"].as_str().unwrap();
+        let address = exported
+            .pointer(address_ref.strip_prefix('#').unwrap())
+            .unwrap();
+        assert_eq!(address["required"], serde_json::json!(["street"]));
+
+        Ok(())
+    }
+
     #[test]
     fn build_from_base() -> Result<(), Error> {
         let db = InMemoryDataStorage::new();
@@ -201,4 +456,28 @@ ADD ATTRIBUTE C=Array[refn:third]
             assert!(matches!(validation_error, ValidationError::UnknownRefn(_)));
         }
     }
+
+    #[test]
+    fn fail_while_building_with_invalid_condition() {
+        let db = InMemoryDataStorage::new();
+        let db_cache = InMemoryDataStorage::new();
+        let cache_storage_config = SQLiteConfig::build().unwrap();
+        let mut facade = Facade::new(Box::new(db), Box::new(db_cache), cache_storage_config);
+
+        let ocafile = r#"
+ADD ATTRIBUTE age=Numeric name=Text
+ADD CONDITION ATTRS name="${name} > 18"
+"#
+        .to_string();
+        let result = facade.build_from_ocafile(ocafile);
+        let Err(Error::ValidationError(validation_errors)) = result else {
+            panic!("expected validation error");
+        };
+        let validation_error = validation_errors.first().unwrap();
+        let ValidationError::OCABundleSemantics(error) = validation_error else {
+            panic!("expected semantic validation error");
+        };
+        assert_eq!(error.code(), "self_dependent_condition");
+        assert_eq!(error.attribute_path(), Some("name"));
+    }
 }
@@ -75,10 +75,7 @@ pub fn apply_command(
     op: ast::Command,
 ) -> Result<Transformation, Vec<String>> {
     let errors = vec![];
-    let mut transformation: Transformation = match base {
-        Some(transformation) => transformation,
-        None => Transformation::new(),
-    };
+    let mut transformation: Transformation = base.unwrap_or_default();
 
     match (op.kind, op.object_kind) {
         (ast::CommandType::Rename, ast::ObjectKind::Rename(content)) => {
@@ -54,6 +54,10 @@ impl Transformation {
     pub fn fill_said(&mut self) {
         let code = HashFunctionCode::Blake3_256;
         let format = SerializationFormats::JSON;
-        self.compute_digest(&code, &format);
+        self.fill_said_with(&code, &format);
+    }
+
+    pub fn fill_said_with(&mut self, code: &HashFunctionCode, format: &SerializationFormats) {
+        self.compute_digest(code, format);
     }
 }